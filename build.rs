@@ -11,6 +11,7 @@ fn main() {
     let databox_c = fs::read_to_string("c_datalib/databox.c")
         .expect("Failed to read file with path : c_datalib/databox.c")
         .clone()
+        .replace("\\", "\\\\")
         .replace("\"", "\\\"");
 
     let databox_h = fs::read_to_string("c_datalib/databox.h")
@@ -22,6 +23,7 @@ fn main() {
     let print_c = fs::read_to_string("c_datalib/print.c")
         .expect("Failed to read file with path : c_datalib/print.c")
         .clone()
+        .replace("\\", "\\\\")
         .replace("\"", "\\\"");
 
     let print_h = fs::read_to_string("c_datalib/print.h")
@@ -33,6 +35,7 @@ fn main() {
     let dict_c = fs::read_to_string("c_datalib/dict.c")
         .expect("Failed to read file with path : c_datalib/print.c")
         .clone()
+        .replace("\\", "\\\\")
         .replace("\"", "\\\"");
 
     let dict_h = fs::read_to_string("c_datalib/dict.h")
@@ -45,6 +48,7 @@ fn main() {
     let keyval_c = fs::read_to_string("c_datalib/keyval.c")
         .expect("Failed to read file with path : c_datalib/keyval.c")
         .clone()
+        .replace("\\", "\\\\")
         .replace("\"", "\\\"");
 
     let keyval_h = fs::read_to_string("c_datalib/keyval.h")
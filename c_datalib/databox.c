@@ -1,13 +1,387 @@
 #include "databox.h"
+#include "print.h"
 #include <assert.h>
+#include <ctype.h>
 #include <stdlib.h>
 #include <assert.h>
+#include <math.h>
+#include <stdarg.h>
+#include <stdint.h>
+#include <stdio.h>
+
+// reading or writing a property of null or undefined, with the message of node :
+// `Cannot read properties of null (reading 'key')`
+static void throw_nullish_access(char *verb, char *action, databox object, char *key) {
+    char *message = malloc(strlen(key) + 64);
+    sprintf(message, "Cannot %s properties of %s (%s '%s')", verb, object.type == NIL ? "null" : "undefined", action, key);
+    throw_error("TypeError", message);
+}
 
 databox new_object(void) {
     databox d = {.data.dict = dictionary_new(), .type= DICT};
     return d;
 }
 
+// `object.key = value`, the value is copied on the heap so that it outlives
+// the C scope it was computed in
+databox set_property(databox object, char *key, databox value) {
+    if (object.type == NIL || object.type == UNDEFINED) {
+        throw_nullish_access("set", "setting", object, key);
+    }
+    if (object.type == ARRAY) {
+        return set_index(object, new_from_str(key), value);
+    }
+    if (object.type != DICT) {
+        return value;
+    }
+    databox *found = dictionary_find(object.data.dict, key);
+    if (found != dictionary_not_found) {
+        *found = value;
+    } else {
+        databox *stored = malloc(sizeof(databox));
+        *stored = value;
+        dictionary_add(object.data.dict, key, stored);
+    }
+    return value;
+}
+
+// value of `new F()`, the object bound to `this` unless F returned an object
+databox construct_result(databox this, databox returned) {
+    return returned.type == DICT ? returned : this;
+}
+
+databox new_function(char *name, databox (*code)(databox **, databox, int, databox *), databox **env) {
+    function *func = malloc(sizeof(function));
+    func->code = code;
+    func->env = env;
+    func->name = name;
+    databox d = {.data.func = func, .type = FUNC};
+    return d;
+}
+
+// a function expression seeing its own name, the cell `self` of its
+// environment holds the function so the name is the function itself
+databox new_named_function(char *name, databox (*code)(databox **, databox, int, databox *), databox **env, int self) {
+    databox func = new_function(name, code, env);
+    *env[self] = func;
+    return func;
+}
+
+// a captured variable, it lives as long as the closures using it
+databox *new_cell(databox value) {
+    databox *cell = malloc(sizeof(databox));
+    *cell = value;
+    return cell;
+}
+
+// the cells a closure captured, in the order the compiler numbered them
+databox **new_env(int size, ...) {
+    databox **env = malloc(size * sizeof(databox *));
+    va_list cells;
+    va_start(cells, size);
+    for (int i = 0; i < size; i++) {
+        env[i] = va_arg(cells, databox *);
+    }
+    va_end(cells);
+    return env;
+}
+
+// missing arguments are undefined
+databox nth_arg(int argc, databox *argv, int n) {
+    return n < argc ? argv[n] : new_undefined();
+}
+
+databox call_function(databox f, databox this, int argc, databox *argv) {
+    if (f.type != FUNC) {
+        throw_error("TypeError", "not a function");
+    }
+    return f.data.func->code(f.data.func->env, this, argc, argv);
+}
+
+// innermost `try` being run, and the value of the last `throw`
+static try_handler *handlers = NULL;
+static databox thrown;
+
+// the caller does `if(!setjmp(handler->jump))` to run the block it protects
+void try_enter(try_handler *handler) {
+    handler->outer = handlers;
+    handlers = handler;
+}
+
+// the block was left without exception
+void try_leave(void) {
+    handlers = handlers->outer;
+}
+
+databox caught_value(void) {
+    return thrown;
+}
+
+// an uncaught exception ends the program like node does
+void throw_value(databox value) {
+    thrown = value;
+    try_handler *handler = handlers;
+    if (handler == NULL) {
+        databox *name = value.type == DICT ? dictionary_find(value.data.dict, "name") : dictionary_not_found;
+        databox *message = value.type == DICT ? dictionary_find(value.data.dict, "message") : dictionary_not_found;
+        fflush(stdout);
+        if (name != dictionary_not_found && message != dictionary_not_found && name->type == STR && message->type == STR) {
+            fprintf(stderr, "Uncaught %s: %s\n", name->data.str->chars, message->data.str->chars);
+        } else {
+            // other values are shown as `console.log` prints them
+            fprintf(stderr, "Uncaught %s\n", inspect(value));
+        }
+        exit(1);
+    }
+    handlers = handler->outer;
+    longjmp(handler->jump, 1);
+}
+
+// `{name, message}`, what `new TypeError(message)` would give
+databox new_error(char *name, char *message) {
+    databox error = new_object();
+    set_property(error, "name", new_from_str(name));
+    set_property(error, "message", new_from_str(message));
+    return error;
+}
+
+void throw_error(char *name, char *message) {
+    throw_value(new_error(name, message));
+}
+
+// `[a, b]`, the items are copied on the heap
+databox new_array(int length, databox *items) {
+    array *arr = malloc(sizeof(array));
+    arr->capacity = length > 4 ? length : 4;
+    arr->items = malloc(arr->capacity * sizeof(databox));
+    arr->length = length;
+    for (int i = 0; i < length; i++) {
+        arr->items[i] = items[i];
+    }
+    databox d = {.data.arr = arr, .type = ARRAY};
+    return d;
+}
+
+// arrays store every item, writing the index 2000000000 would allocate them all,
+// longer arrays throw a RangeError like the interpreter's MAX_ARRAY_LENGTH
+#define ARRAY_MAX_LENGTH 16777216
+
+// room for `length` items, new items are undefined
+static void array_grow(array *arr, size_t length) {
+    if (length > ARRAY_MAX_LENGTH) {
+        throw_error("RangeError", "Invalid array length");
+    }
+    if (length > arr->capacity) {
+        while (arr->capacity < length) {
+            arr->capacity *= 2;
+        }
+        arr->items = realloc(arr->items, arr->capacity * sizeof(databox));
+    }
+    for (size_t i = arr->length; i < length; i++) {
+        arr->items[i] = new_undefined();
+    }
+    if (length > arr->length) {
+        arr->length = length;
+    }
+}
+
+// returns the new length
+databox array_push(databox a, int argc, databox *argv) {
+    array *arr = a.data.arr;
+    size_t length = arr->length;
+    array_grow(arr, length + argc);
+    for (int i = 0; i < argc; i++) {
+        arr->items[length + i] = argv[i];
+    }
+    return new_from_double(arr->length);
+}
+
+databox array_pop(databox a) {
+    array *arr = a.data.arr;
+    if (arr->length == 0) {
+        return new_undefined();
+    }
+    arr->length--;
+    return arr->items[arr->length];
+}
+
+// the largest javascript array index, 2^32 - 2
+#define MAX_INDEX 4294967294.0
+
+// the index `key` names, `a["1"]` is `a[1]`, -1 if it isn't one
+static long long array_index(databox key) {
+    if (key.type == STR) {
+        char *end;
+        char *name = key.data.str->chars;
+        // only the canonical form names an index, not "01" or "+1"
+        if (*name < '0' || *name > '9' || (name[0] == '0' && name[1] != '\0') || strlen(name) > 10) {
+            return -1;
+        }
+        long long i = strtoll(name, &end, 10);
+        return *end == '\0' && i <= MAX_INDEX ? i : -1;
+    }
+    // checked before converting, a double out of range has no integer value
+    if (key.type != NUM || !(key.data.num >= 0 && key.data.num <= MAX_INDEX) || key.data.num != floor(key.data.num)) {
+        return -1;
+    }
+    return (long long) key.data.num;
+}
+
+// bytes of the UTF-8 character starting with `lead`
+static int utf8_length(char lead) {
+    unsigned char byte = lead;
+    return byte < 0x80 ? 1 : byte < 0xE0 ? 2 : byte < 0xF0 ? 3 : 4;
+}
+
+// the length javascript gives : UTF-16 code units, two for the characters
+// outside the BMP which take four bytes in UTF-8
+static int string_units(string *str) {
+    int units = 0;
+    for (int i = 0; i < str->length; i += utf8_length(str->chars[i])) {
+        units += utf8_length(str->chars[i]) == 4 ? 2 : 1;
+    }
+    return units;
+}
+
+// the one character string at UTF-16 code unit `i`, a half of a character
+// outside the BMP has no UTF-8 form and reads as U+FFFD, as node prints it
+static databox string_at(string *str, long long i) {
+    int unit = 0;
+    for (int byte = 0; byte < str->length; byte += utf8_length(str->chars[byte])) {
+        int length = utf8_length(str->chars[byte]);
+        int units = length == 4 ? 2 : 1;
+        if (i < unit + units) {
+            return units == 2 ? new_from_str("\xEF\xBF\xBD") : new_from_string(string_slice(str, byte, byte + length));
+        }
+        unit += units;
+    }
+    return new_undefined();
+}
+
+// the property `key` names, `o[1]` is `o["1"]`
+static char *property_name(databox key) {
+    switch (key.type) {
+        case STR:
+            return key.data.str->chars;
+        case NUM:
+            return number_to_string(key.data.num);
+        case BOOL:
+            return key.data.boolean ? "true" : "false";
+        case NIL:
+            return "null";
+        case UNDEFINED:
+            return "undefined";
+        default:
+            return "[object Object]";
+    }
+}
+
+// `object[key]`, undefined when there is no such item or property
+databox get_index(databox object, databox key) {
+    if (object.type == NIL || object.type == UNDEFINED) {
+        throw_nullish_access("read", "reading", object, property_name(key));
+    }
+    if (object.type == DICT) {
+        databox *found = dictionary_find(object.data.dict, property_name(key));
+        return found != dictionary_not_found ? *found : new_undefined();
+    }
+    if (object.type == ARRAY) {
+        long long i = array_index(key);
+        if (i >= 0 && i < (long long) object.data.arr->length) {
+            return object.data.arr->items[i];
+        } else if (key.type == STR && strcmp(key.data.str->chars, "length") == 0) {
+            return new_from_double(object.data.arr->length);
+        }
+    }
+    if (object.type == STR) {
+        long long i = array_index(key);
+        if (i >= 0) {
+            return string_at(object.data.str, i);
+        } else if (key.type == STR && strcmp(key.data.str->chars, "length") == 0) {
+            return new_from_double(string_units(object.data.str));
+        }
+    }
+    return new_undefined();
+}
+
+// arrays are shorter than ARRAY_MAX_LENGTH, their length is an int
+int array_length(databox a) {
+    return (int) a.data.arr->length;
+}
+
+// keys `for..in` iterates : property names of objects, indexes of arrays and strings
+databox for_in_keys(databox object) {
+    databox keys = new_array(0, NULL);
+    size_t length = 0;
+    if (object.type == DICT) {
+        for (int i = 0; i < object.data.dict->length; i++) {
+            databox key = new_from_str(object.data.dict->pairs[i]->key);
+            array_push(keys, 1, &key);
+        }
+        return keys;
+    } else if (object.type == ARRAY) {
+        length = object.data.arr->length;
+    } else if (object.type == STR) {
+        length = string_units(object.data.str);
+    }
+    for (size_t i = 0; i < length; i++) {
+        databox key = new_from_str(property_name(new_from_double(i)));
+        array_push(keys, 1, &key);
+    }
+    return keys;
+}
+
+// items `for..of` iterates, the array itself so that items pushed while
+// iterating are visited, or the characters of a string
+databox for_of_items(databox iterable) {
+    if (iterable.type == ARRAY) {
+        return iterable;
+    } else if (iterable.type == STR) {
+        databox items = new_array(0, NULL);
+        string *str = iterable.data.str;
+        for (int i = 0; i < str->length; i += utf8_length(str->chars[i])) {
+            databox item = new_from_string(string_slice(str, i, i + utf8_length(str->chars[i])));
+            array_push(items, 1, &item);
+        }
+        return items;
+    }
+    throw_error("TypeError", "not iterable");
+}
+
+// `object[key] = value`, writing past the end of an array grows it
+databox set_index(databox object, databox key, databox value) {
+    if (object.type == NIL || object.type == UNDEFINED) {
+        throw_nullish_access("set", "setting", object, property_name(key));
+    }
+    if (object.type == DICT) {
+        return set_property(object, property_name(key), value);
+    }
+    long long i = array_index(key);
+    if (object.type == ARRAY && i >= 0) {
+        array_grow(object.data.arr, i + 1);
+        object.data.arr->items[i] = value;
+    } else if (object.type == ARRAY && key.type == STR && strcmp(key.data.str->chars, "length") == 0) {
+        // truncates or fills with undefined
+        double length = data_to_number(value);
+        if (!(length >= 0 && length <= ARRAY_MAX_LENGTH) || length != floor(length)) {
+            throw_error("RangeError", "Invalid array length");
+        }
+        array_grow(object.data.arr, length);
+        object.data.arr->length = length;
+    }
+    return value;
+}
+
+// `object.name(args)`, called with `object` as `this`
+databox call_method(databox object, char *name, int argc, databox *argv) {
+    if (object.type == ARRAY && strcmp(name, "push") == 0) {
+        return array_push(object, argc, argv);
+    } else if (object.type == ARRAY && strcmp(name, "pop") == 0) {
+        return array_pop(object);
+    }
+    return call_function(get_index(object, new_from_str(name)), object, argc, argv);
+}
+
 databox new_from_int(int a) {
     return new_from_double((double) a);
 }
@@ -18,49 +392,328 @@ databox new_from_double(double a) {
 }
 
 databox new_from_str(char *a) {
+    return new_from_string(new_string(a, strlen(a)));
+}
+
+databox new_from_string(string *a) {
     databox d = {.data.str = a, .type = STR};
     return d;
 }
 
+string *new_string(const char *chars, int length) {
+    string *s = malloc(sizeof(string) + length + 1);
+    s->length = length;
+    memcpy(s->chars, chars, length);
+    s->chars[length] = '\0';
+    return s;
+}
+
+string *string_concat(string *a, string *b) {
+    string *s = malloc(sizeof(string) + a->length + b->length + 1);
+    s->length = a->length + b->length;
+    memcpy(s->chars, a->chars, a->length);
+    memcpy(s->chars + a->length, b->chars, b->length + 1);
+    return s;
+}
+
+// the bytes from `start` up to `end`, both kept within the string
+string *string_slice(string *s, int start, int end) {
+    start = start < 0 ? 0 : start > s->length ? s->length : start;
+    end = end < start ? start : end > s->length ? s->length : end;
+    return new_string(s->chars + start, end - start);
+}
+
+// negative, zero or positive like strcmp, a string comes after its prefixes
+// the code point of the UTF-8 character at `chars`
+static long code_point(char *chars) {
+    unsigned char *bytes = (unsigned char *) chars;
+    switch (utf8_length(chars[0])) {
+        case 1:
+            return bytes[0];
+        case 2:
+            return (bytes[0] & 0x1F) << 6 | (bytes[1] & 0x3F);
+        case 3:
+            return (bytes[0] & 0x0F) << 12 | (bytes[1] & 0x3F) << 6 | (bytes[2] & 0x3F);
+        default:
+            return (long) (bytes[0] & 0x07) << 18 | (bytes[1] & 0x3F) << 12 | (bytes[2] & 0x3F) << 6 | (bytes[3] & 0x3F);
+    }
+}
+
+// the first UTF-16 code unit of the code point, a high surrogate for the ones
+// outside the BMP
+static long first_unit(long code_point) {
+    return code_point < 0x10000 ? code_point : 0xD800 + ((code_point - 0x10000) >> 10);
+}
+
+// javascript orders strings by UTF-16 code units, which is the UTF-8 byte order
+// except for the characters outside the BMP, below U+E000..U+FFFF in UTF-16
+int string_compare(string *a, string *b) {
+    int length = a->length < b->length ? a->length : b->length;
+    int i = 0;
+    while (i < length && a->chars[i] == b->chars[i]) {
+        i++;
+    }
+    if (i == length) {
+        return a->length - b->length;
+    }
+    // back to the start of the first different character
+    while (i > 0 && ((unsigned char) a->chars[i] & 0xC0) == 0x80) {
+        i--;
+    }
+    long a_point = code_point(a->chars + i), b_point = code_point(b->chars + i);
+    if (first_unit(a_point) != first_unit(b_point)) {
+        return first_unit(a_point) < first_unit(b_point) ? -1 : 1;
+    }
+    return a_point < b_point ? -1 : 1;
+}
+
+databox new_bool(int a) {
+    databox d = {.data.boolean = a != 0, .type = BOOL};
+    return d;
+}
+
+databox new_null(void) {
+    databox d = {.type = NIL};
+    return d;
+}
+
+databox new_undefined(void) {
+    databox d = {.type = UNDEFINED};
+    return d;
+}
+
 databox copy(databox a) {
     return a;
 }
 
-void increment(databox *a) {
-    if (a->type == NUM) {
-        (a->data).num++;
-    }
+databox increment(databox *a) {
+    *a = new_from_double(data_to_number(*a) + 1);
+    return *a;
 }
 
-int data_eq_data(databox a, databox b) {
-    if (a.type == NUM && b.type == NUM) {
-        return a.data.num == b.data.num;
-    } else if (a.type == STR && b.type == STR) {
-        unsigned int a_len = strlen(a.data.str);
-        unsigned int b_len = strlen(b.data.str);
-        return a_len == b_len;
+databox decrement(databox *a) {
+    *a = new_from_double(data_to_number(*a) - 1);
+    return *a;
+}
+
+// the number a string converts to : blanks are 0, decimal, Infinity and 0x, 0o
+// and 0b prefixed literals are parsed, anything else is NaN
+static double string_to_number(char *str) {
+    while (isspace((unsigned char) *str)) str++;
+    char *end = str + strlen(str);
+    while (end > str && isspace((unsigned char) end[-1])) end--;
+    if (end == str) {
+        return 0;
+    }
+
+    char *unsigned_str = (*str == '+' || *str == '-') ? str + 1 : str;
+    if (end - unsigned_str == 8 && strncmp(unsigned_str, "Infinity", 8) == 0) {
+        return *str == '-' ? -INFINITY : INFINITY;
+    }
+    int base = 10;
+    if (str[0] == '0' && (str[1] == 'x' || str[1] == 'X')) base = 16;
+    if (str[0] == '0' && (str[1] == 'o' || str[1] == 'O')) base = 8;
+    if (str[0] == '0' && (str[1] == 'b' || str[1] == 'B')) base = 2;
+
+    char *parsed;
+    double number;
+    if (base != 10) {
+        if (end == str + 2) return NAN;
+        number = (double) strtoull(str + 2, &parsed, base);
     } else {
-        exit(1);
+        // strtod also reads inf, nan and hexadecimal floats
+        for (char *c = unsigned_str; c < end; c++) {
+            if (isalpha((unsigned char) *c) && *c != 'e' && *c != 'E') return NAN;
+        }
+        number = strtod(str, &parsed);
     }
+    return parsed == end ? number : NAN;
 }
 
-// String parse in C ?
-int double_eq_data(double a, databox b) {
-    if (b.type == NUM) {
-        return a == b.data.num;
+// javascript `Number.prototype.toString` : the shortest digits reading back as
+// `a`, written in decimal from 1e-6 up to 1e21 and in exponent form outside,
+// like `number_to_string` in the compiler
+char *number_to_string(double a) {
+    if (isnan(a)) return "NaN";
+    if (a == 0) return "0";
+    if (isinf(a)) return a > 0 ? "Infinity" : "-Infinity";
+
+    // `-d.ddde-dd`
+    char scientific[32];
+    for (int precision = 0; precision < 17; precision++) {
+        snprintf(scientific, 32, "%.*e", precision, a);
+        if (strtod(scientific, NULL) == a) break;
+    }
+    char digits[20];
+    int k = 0;
+    char *c = scientific + (a < 0);
+    for (; *c != 'e'; c++) {
+        if (*c != '.') digits[k++] = *c;
+    }
+    while (k > 1 && digits[k - 1] == '0') k--;
+    digits[k] = '\0';
+    // the value is 0.digits * 10^point
+    int point = atoi(c + 1) + 1;
+
+    char *text = malloc(32);
+    char *end = text;
+    if (a < 0) *end++ = '-';
+    if (k <= point && point <= 21) {
+        end += sprintf(end, "%s", digits);
+        for (int i = k; i < point; i++) *end++ = '0';
+        *end = '\0';
+    } else if (0 < point && point <= 21) {
+        sprintf(end, "%.*s.%s", point, digits, digits + point);
+    } else if (-6 < point && point <= 0) {
+        end += sprintf(end, "0.");
+        for (int i = point; i < 0; i++) *end++ = '0';
+        sprintf(end, "%s", digits);
     } else {
-        exit(1);
+        sprintf(end, "%c%s%se%c%d", digits[0], k > 1 ? "." : "", digits + 1, point > 0 ? '+' : '-', abs(point - 1));
     }
+    return text;
 }
 
-int data_eq_double(databox a, double b) {
-    if (a.type == NUM) {
-        return a.data.num == b;
+static int is_object(databox a) {
+    return a.type == DICT || a.type == ARRAY || a.type == FUNC;
+}
+
+// the arrays being joined, like javascript an array inside itself joins as ""
+typedef struct joining {
+    array *arr;
+    struct joining *outer;
+} joining;
+
+static char *array_join(array *arr, joining *outer);
+
+// the string an object compared to a primitive converts to, the items of an
+// array are joined with commas. The runtime doesn't keep the source of the
+// functions, they give their name.
+static char *object_to_string(databox a) {
+    if (a.type == DICT) {
+        return "[object Object]";
+    } else if (a.type == FUNC) {
+        return a.data.func->name;
+    }
+    return array_join(a.data.arr, NULL);
+}
+
+static char *array_join(array *arr, joining *outer) {
+    for (joining *j = outer; j != NULL; j = j->outer) {
+        if (j->arr == arr) {
+            return "";
+        }
+    }
+    joining current = {arr, outer};
+
+    int length = 1;
+    char **items = malloc(arr->length * sizeof(char *));
+    for (size_t i = 0; i < arr->length; i++) {
+        databox item = arr->items[i];
+        char *text;
+        if (item.type == NUM) {
+            text = number_to_string(item.data.num);
+        } else if (item.type == STR) {
+            text = item.data.str->chars;
+        } else if (item.type == BOOL) {
+            text = item.data.boolean ? "true" : "false";
+        } else if (item.type == ARRAY) {
+            text = array_join(item.data.arr, &current);
+        } else if (is_object(item)) {
+            text = object_to_string(item);
+        } else {
+            text = "";
+        }
+        items[i] = text;
+        length += strlen(text) + 1;
+    }
+    char *joined = calloc(length, 1);
+    for (size_t i = 0; i < arr->length; i++) {
+        if (i > 0) strcat(joined, ",");
+        strcat(joined, items[i]);
+    }
+    free(items);
+    return joined;
+}
+
+string *data_to_string(databox a) {
+    if (a.type == STR) {
+        return a.data.str;
+    }
+    char *text = is_object(a) ? object_to_string(a) : property_name(a);
+    return new_string(text, strlen(text));
+}
+
+// objects compared or added are converted to their string
+static databox to_primitive(databox a) {
+    return is_object(a) ? new_from_string(data_to_string(a)) : a;
+}
+
+// javascript `==` : null and undefined only equal each other, booleans and
+// strings compared to a number are converted to numbers, and objects compared
+// to a primitive are converted to a string
+int data_eq_data(databox a, databox b) {
+    if (a.type == b.type) {
+        return data_strict_eq_data(a, b);
+    } else if ((a.type == NIL || a.type == UNDEFINED) && (b.type == NIL || b.type == UNDEFINED)) {
+        return 1;
+    } else if (a.type == NIL || a.type == UNDEFINED || b.type == NIL || b.type == UNDEFINED) {
+        return 0;
+    } else if (a.type == BOOL) {
+        return data_eq_data(new_from_int(a.data.boolean), b);
+    } else if (b.type == BOOL) {
+        return data_eq_data(a, new_from_int(b.data.boolean));
+    } else if (a.type == STR && b.type == NUM) {
+        return string_to_number(a.data.str->chars) == b.data.num;
+    } else if (a.type == NUM && b.type == STR) {
+        return a.data.num == string_to_number(b.data.str->chars);
+    } else if (is_object(a) && !is_object(b)) {
+        return data_eq_data(new_from_str(object_to_string(a)), b);
+    } else if (!is_object(a) && is_object(b)) {
+        return data_eq_data(a, new_from_str(object_to_string(b)));
     } else {
-        exit(1);
+        // objects of different types
+        return 0;
     }
 }
 
+// javascript `===`, values of different types are never equal and objects are
+// only equal to themselves
+int data_strict_eq_data(databox a, databox b) {
+    if (a.type != b.type) {
+        return 0;
+    }
+    switch (a.type) {
+        case NUM:
+            return a.data.num == b.data.num;
+        case STR:
+            return string_compare(a.data.str, b.data.str) == 0;
+        case BOOL:
+            return a.data.boolean == b.data.boolean;
+        case DICT:
+            return a.data.dict == b.data.dict;
+        case ARRAY:
+            return a.data.arr == b.data.arr;
+        case FUNC:
+            return a.data.func == b.data.func;
+        default:
+            // null and undefined
+            return 1;
+    }
+}
+
+int data_strict_neq_data(databox a, databox b) {
+    return reverse(data_strict_eq_data(a, b));
+}
+
+int double_eq_data(double a, databox b) {
+    return data_eq_data(new_from_double(a), b);
+}
+
+int data_eq_double(databox a, double b) {
+    return data_eq_data(a, new_from_double(b));
+}
+
 int int_eq_data(int a, databox b) {
     return double_eq_data((double) a, b);
 }
@@ -94,61 +747,81 @@ int reverse(int boolean) {
     else return 1;
 }
 
+int data_to_bool(databox a) {
+    switch (a.type) {
+        case NUM:
+            return double_to_bool(a.data.num);
+        case STR:
+            return a.data.str->length > 0;
+        case BOOL:
+            return a.data.boolean;
+        case NIL:
+        case UNDEFINED:
+            return 0;
+        default:
+            // objects are always truthy
+            return 1;
+    }
+}
+
+int double_to_bool(double a) {
+    return a != 0 && !isnan(a);
+}
+
+int int_to_bool(int a) {
+    return a != 0;
+}
+
+// unary `-`
+databox negate(databox a) {
+    return new_from_double(-data_to_number(a));
+}
+
+// left operand test of `??`
+int is_nullish(databox a) {
+    return a.type == NIL || a.type == UNDEFINED;
+}
+
 // Add
+// javascript `+` : when either operand is a string, or an object converting to
+// one, both are concatenated as strings, otherwise they are added as numbers
 databox data_add_data(databox a, databox b) {
-    if (a.type == NUM && b.type == NUM) {
-        double result = a.data.num + b.data.num;
-        databox out = {.data.num = result};
-        return out;
-
-    } else if (a.type == STR && b.type == STR) {
-        char temp[sizeof(a.data.str) + sizeof(b.data.str)];
-        strcpy(temp, a.data.str);
-        strcat(temp, b.data.str);
-        databox out = {.data.str = temp};
-        return out;
-    } else {
-        exit(1);
+    a = to_primitive(a);
+    b = to_primitive(b);
+    if (a.type == STR || b.type == STR) {
+        return new_from_string(string_concat(data_to_string(a), data_to_string(b)));
     }
+    return new_from_double(data_to_number(a) + data_to_number(b));
 }
 
 databox double_add_data(double a, databox b) {
-    databox a_as_data = {.data.num = a};
-    databox out = data_add_data(b, a_as_data);
-    return out;
+    return data_add_data(new_from_double(a), b);
 }
 
 databox data_add_double(databox a, double b) {
-    return double_add_data(b, a);
+    return data_add_data(a, new_from_double(b));
 }
 
 databox int_add_data(int a, databox b) {
-    return double_add_data((double) a, b);
+    return data_add_data(new_from_double((double) a), b);
 }
 
 databox data_add_int(databox a, int b) {
-    return data_add_double(a, (double) b);
+    return data_add_data(a, new_from_double((double) b));
 }
 
 // Sub
+// javascript `-`, both operands are converted to numbers
 databox data_sub_data(databox a, databox b) {
-    if (a.type == NUM && b.type == NUM) {
-        double result = a.data.num - b.data.num;
-        databox out = {.data.num = result};
-        return out;
-    } else {
-        exit(1);
-    }
+    return new_from_double(data_to_number(a) - data_to_number(b));
 }
 
 databox double_sub_data(double a, databox b) {
-    databox a_as_data = {.data.num = a};
-    databox out = data_sub_data(b, a_as_data);
-    return out;
+    return data_sub_data(new_from_double(a), b);
 }
 
 databox data_sub_double(databox a, double b) {
-    return double_sub_data(b, a);
+    return data_sub_data(a, new_from_double(b));
 }
 
 databox int_sub_data(int a, databox b) {
@@ -159,22 +832,17 @@ databox data_sub_int(databox a, int b) {
     return data_sub_double(a, (double) b);
 }
 
+// javascript `*`, both operands are converted to numbers
 databox data_mul_data(const databox a, const databox b) {
-    assert(a.type == NUM && b.type == NUM);
-    double result = a.data.num * b.data.num;
-    databox out = {.data.num = result, .type=NUM};
-    return out;
+    return new_from_double(data_to_number(a) * data_to_number(b));
 }
 
 databox double_mul_data(const double a, const databox b) {
-    assert(b.type == NUM);
-    databox a_as_data = {.data.num = a, .type=NUM};
-    databox out = data_mul_data(b, a_as_data);
-    return out;
+    return data_mul_data(new_from_double(a), b);
 }
 
 databox data_mul_double(const databox a, const double b) {
-    return double_mul_data(b, a);
+    return data_mul_data(a, new_from_double(b));
 }
 
 databox int_mul_data(const int a, const databox b) {
@@ -182,37 +850,27 @@ databox int_mul_data(const int a, const databox b) {
 }
 
 databox data_mul_int(const databox a, const int b) {
-    return double_mul_data((double) b, a);
+    return data_mul_double(a, (double) b);
 }
 
+// javascript `>` : two strings are compared by their characters, anything else
+// as numbers
 int data_greater_than_data(databox a, databox b) {
-    if (a.type == NUM && b.type == NUM) {
-        return a.data.num > b.data.num;
-    } else if (a.type == STR && b.type == STR) {
-        unsigned int a_len = strlen(a.data.str);
-        unsigned int b_len = strlen(b.data.str);
-        return a_len > b_len;
-    } else {
-        exit(1);
+    a = to_primitive(a);
+    b = to_primitive(b);
+    if (a.type == STR && b.type == STR) {
+        return string_compare(a.data.str, b.data.str) > 0;
     }
+    // false when one of them is NaN
+    return data_to_number(a) > data_to_number(b);
 }
 
 int double_greater_than_data(double a, databox b) {
-    if (b.type == NUM) {
-        return a > b.data.num;
-
-    } else {
-        return a > strlen(b.data.str);
-    }
+    return data_greater_than_data(new_from_double(a), b);
 }
 
 int data_greater_than_double(databox a, double b) {
-    if (a.type == NUM) {
-        return a.data.num > b;
-
-    } else {
-        return strlen(a.data.str) > b;
-    }
+    return data_greater_than_data(a, new_from_double(b));
 }
 
 int int_greater_than_data(int a, databox b) {
@@ -246,3 +904,157 @@ int data_less_than_int(databox a, int b) {
 }
 
 
+
+// javascript ToNumber, objects are converted to their string first
+double data_to_number(databox a) {
+    switch (a.type) {
+        case NUM:
+            return a.data.num;
+        case STR:
+            return string_to_number(a.data.str->chars);
+        case BOOL:
+            return a.data.boolean;
+        case NIL:
+            return 0;
+        case DICT:
+        case ARRAY:
+            return string_to_number(object_to_string(a));
+        default:
+            // undefined and functions
+            return NAN;
+    }
+}
+
+// javascript ToUint32, the number modulo 2^32, NaN and infinities are 0
+static uint32_t to_uint32(databox a) {
+    double n = data_to_number(a);
+    if (!isfinite(n)) {
+        return 0;
+    }
+    double modulo = fmod(trunc(n), 4294967296.0);
+    return (uint32_t) (modulo < 0 ? modulo + 4294967296.0 : modulo);
+}
+
+// javascript ToInt32
+static int32_t to_int32(databox a) {
+    return (int32_t) to_uint32(a);
+}
+
+int data_less_eq_data(databox a, databox b) {
+    a = to_primitive(a);
+    b = to_primitive(b);
+    if (a.type == STR && b.type == STR) {
+        return string_compare(a.data.str, b.data.str) <= 0;
+    }
+    // false when one of them is NaN
+    return data_to_number(a) <= data_to_number(b);
+}
+
+int data_greater_eq_data(databox a, databox b) {
+    return data_less_eq_data(b, a);
+}
+
+databox data_div_data(databox a, databox b) {
+    return new_from_double(data_to_number(a) / data_to_number(b));
+}
+
+// the sign of the result is the one of the dividend, like fmod
+databox data_mod_data(databox a, databox b) {
+    return new_from_double(fmod(data_to_number(a), data_to_number(b)));
+}
+
+// unlike pow, a base of 1 or -1 to an infinite or NaN power is NaN
+databox data_exp_data(databox a, databox b) {
+    double base = data_to_number(a);
+    double exponent = data_to_number(b);
+    if (isnan(exponent) || (fabs(base) == 1 && isinf(exponent))) {
+        return new_from_double(NAN);
+    }
+    return new_from_double(pow(base, exponent));
+}
+
+databox data_bit_and_data(databox a, databox b) {
+    return new_from_double(to_int32(a) & to_int32(b));
+}
+
+databox data_bit_or_data(databox a, databox b) {
+    return new_from_double(to_int32(a) | to_int32(b));
+}
+
+databox data_bit_xor_data(databox a, databox b) {
+    return new_from_double(to_int32(a) ^ to_int32(b));
+}
+
+// only the 5 lowest bits of the count are used, the bits shifted out of a
+// signed integer are dropped
+databox data_shift_left_data(databox a, databox b) {
+    return new_from_double((int32_t) (to_uint32(a) << (to_uint32(b) & 31)));
+}
+
+databox data_shift_right_data(databox a, databox b) {
+    return new_from_double(to_int32(a) >> (to_uint32(b) & 31));
+}
+
+databox data_unsigned_shift_right_data(databox a, databox b) {
+    return new_from_double(to_uint32(a) >> (to_uint32(b) & 31));
+}
+
+databox data_bit_not(databox a) {
+    return new_from_double(~to_int32(a));
+}
+
+// `key in object`, only objects and arrays have properties to search
+int data_in_data(databox key, databox object) {
+    char *name = property_name(key);
+    if (object.type == DICT) {
+        return dictionary_find(object.data.dict, name) != dictionary_not_found;
+    } else if (object.type == ARRAY) {
+        long long i = array_index(key);
+        return strcmp(name, "length") == 0 || (i >= 0 && i < (long long) object.data.arr->length);
+    } else if (object.type == FUNC) {
+        return 0;
+    }
+    char *value = object.type == STR ? object.data.str->chars : property_name(object);
+    char *message = malloc(strlen(name) + strlen(value) + 64);
+    sprintf(message, "Cannot use 'in' operator to search for '%s' in %s", name, value);
+    throw_error("TypeError", message);
+}
+
+// the object `new` creates, it remembers its constructor for instanceof
+databox new_instance(databox constructor) {
+    databox object = new_object();
+    if (constructor.type == FUNC) {
+        object.data.dict->constructor = constructor.data.func;
+    }
+    return object;
+}
+
+// `object instanceof constructor`, true for the objects `new constructor()`
+// created, a function value is the same function wherever it's used
+int data_instanceof_data(databox object, databox constructor) {
+    if (constructor.type != FUNC) {
+        throw_error("TypeError", "Right-hand side of 'instanceof' is not callable");
+    }
+    if (object.type != DICT) {
+        return 0;
+    }
+    return object.data.dict->constructor == constructor.data.func;
+}
+
+databox type_of(databox a) {
+    switch (a.type) {
+        case NUM:
+            return new_from_str("number");
+        case STR:
+            return new_from_str("string");
+        case BOOL:
+            return new_from_str("boolean");
+        case UNDEFINED:
+            return new_from_str("undefined");
+        case FUNC:
+            return new_from_str("function");
+        default:
+            // null, objects and arrays
+            return new_from_str("object");
+    }
+}
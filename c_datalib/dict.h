@@ -7,6 +7,8 @@ extern void *dictionary_not_found;
 typedef struct dictionary {
     keyval **pairs;
     int length;
+    // the function `new` created the object with, see data_instanceof_data
+    void *constructor;
 } dictionary;
 
 
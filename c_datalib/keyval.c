@@ -16,7 +16,7 @@ keyval *keyval_copy(keyval const *in) {
 }
 
 int keyval_matches(keyval const *in, char const *key) {
-    return !strcasecmp(in->key, key);
+    return !strcmp(in->key, key);
 }
 
 void keyval_free(keyval *in) { free(in); }
@@ -0,0 +1,22 @@
+/* `n.toString()` as printed by node for each double `n`, checked against both
+ * the C `number_to_string` (test.c) and the Rust one (src/token/mod.rs) */
+NUMBER_CASE(0.30000000000000004, "0.30000000000000004")
+NUMBER_CASE(0.3333333333333333, "0.3333333333333333")
+NUMBER_CASE(100.0, "100")
+NUMBER_CASE(-0.0, "0")
+NUMBER_CASE(0.5, "0.5")
+NUMBER_CASE(-42.25, "-42.25")
+NUMBER_CASE(1e21, "1e+21")
+NUMBER_CASE(123456789012345680000.0, "123456789012345680000")
+NUMBER_CASE(1e-6, "0.000001")
+NUMBER_CASE(1e-7, "1e-7")
+NUMBER_CASE(1.5e-7, "1.5e-7")
+NUMBER_CASE(-1e-7, "-1e-7")
+NUMBER_CASE(-0.0000015, "-0.0000015")
+NUMBER_CASE(9007199254740992.0, "9007199254740992")
+NUMBER_CASE(1.2345678901234567e-10, "1.2345678901234568e-10")
+NUMBER_CASE(5e-324, "5e-324")
+NUMBER_CASE(1.7976931348623157e308, "1.7976931348623157e+308")
+NUMBER_CASE(NAN, "NaN")
+NUMBER_CASE(INFINITY, "Infinity")
+NUMBER_CASE(-INFINITY, "-Infinity")
@@ -1,22 +1,427 @@
 #include "print.h"
+#include <ctype.h>
+#include <math.h>
+#include <stdarg.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 
+// `console.log` shows strings as they are, other values as `inspect` does
 void print_data(databox a) {
-    if (a.type == NUM) {
-        print_double(a.data.num);
-    } else if (a.type == STR) {
-        print_str(a.data.str);
+    if (a.type == STR) {
+        fwrite(a.data.str->chars, 1, a.data.str->length, stdout);
     } else {
-        exit(1);
+        print_str(inspect(a));
     }
 }
 
+// lines of `console.log` are broken past 80 columns, objects nested deeper than
+// 2 are `[Object]` and the 3 innermost levels can share a line, like node. Arrays
+// show their first 100 items
+#define BREAK_LENGTH 80
+#define DEPTH 2
+#define COMPACT 3
+#define MAX_ITEMS 100
+
+// the state of one inspect : the objects being shown, to find cycles, and
+// the ones a cycle leads back to, numbered in the order they are found. Like
+// node, the indentation and the depth of the last object shown decide if an
+// object fits on a line
+typedef struct inspector {
+    void **seen;
+    int seen_length;
+    void **circular;
+    int circular_length;
+    int indentation;
+    int current_depth;
+} inspector;
+
+static char *inspect_value(inspector *ctx, databox a, int depth);
+
+// a string printf formats
+static char *format(const char *format, ...) {
+    va_list args;
+    va_start(args, format);
+    int length = vsnprintf(NULL, 0, format, args);
+    va_end(args);
+    char *formatted = malloc(length + 1);
+    va_start(args, format);
+    vsnprintf(formatted, length + 1, format, args);
+    va_end(args);
+    return formatted;
+}
+
+// the length javascript gives, in UTF-16 code units
+static int js_length(char *s) {
+    int units = 0;
+    for (unsigned char *byte = (unsigned char *) s; *byte != '\0'; byte++) {
+        if ((*byte & 0xC0) != 0x80) {
+            units += *byte >= 0xF0 ? 2 : 1;
+        }
+    }
+    return units;
+}
+
+static char *spaces(int count) {
+    char *s = malloc(count + 1);
+    memset(s, ' ', count);
+    s[count] = '\0';
+    return s;
+}
+
+static char *join(char **items, int length, char *separator) {
+    size_t size = 1;
+    for (int i = 0; i < length; i++) {
+        size += strlen(items[i]) + strlen(separator);
+    }
+    char *joined = malloc(size);
+    joined[0] = '\0';
+    for (int i = 0; i < length; i++) {
+        if (i > 0) {
+            strcat(joined, separator);
+        }
+        strcat(joined, items[i]);
+    }
+    return joined;
+}
+
+// `padStart` or `padEnd` with spaces
+static char *pad(char *s, int width, int start) {
+    int missing = width - js_length(s);
+    char *padding = spaces(missing > 0 ? missing : 0);
+    return start ? format("%s%s", padding, s) : format("%s%s", s, padding);
+}
+
+// strings are single quoted, or double quoted or in backticks when they contain
+// single quotes, with the escapes of node
+static char *quote(char *s) {
+    char quote = '\'';
+    if (strchr(s, '\'') != NULL) {
+        if (strchr(s, '"') == NULL) {
+            quote = '"';
+        } else if (strchr(s, '`') == NULL && strstr(s, "${") == NULL) {
+            quote = '`';
+        }
+    }
+    char *quoted = malloc(strlen(s) * 4 + 3);
+    char *end = quoted;
+    *end++ = quote;
+    for (unsigned char *c = (unsigned char *) s; *c != '\0'; c++) {
+        if (*c == '\n') {
+            end += sprintf(end, "\\n");
+        } else if (*c == '\t') {
+            end += sprintf(end, "\\t");
+        } else if (*c == '\b') {
+            end += sprintf(end, "\\b");
+        } else if (*c == '\f') {
+            end += sprintf(end, "\\f");
+        } else if (*c == '\r') {
+            end += sprintf(end, "\\r");
+        } else if (*c == '\\') {
+            end += sprintf(end, "\\\\");
+        } else if (*c == '\'' && quote == '\'') {
+            end += sprintf(end, "\\'");
+        } else if (*c < ' ' || *c == 0x7F) {
+            end += sprintf(end, "\\x%02X", *c);
+        } else if (*c == 0xC2 && c[1] >= 0x80 && c[1] <= 0x9F) {
+            // the C1 control characters, two bytes in UTF-8
+            end += sprintf(end, "\\x%02X", c[1]);
+            c++;
+        } else {
+            *end++ = *c;
+        }
+    }
+    *end++ = quote;
+    *end = '\0';
+    return quoted;
+}
+
+// quoted and escaped, a long string is split after its line breaks
+static char *inspect_string(inspector *ctx, char *s) {
+    int length = js_length(s);
+    if (length <= 16 || length + ctx->indentation + 4 <= BREAK_LENGTH) {
+        return quote(s);
+    }
+    char *separator = format(" +\n%s", spaces(ctx->indentation + 2));
+    char *split = "";
+    while (*s != '\0') {
+        char *line_break = strchr(s, '\n');
+        size_t line_length = line_break == NULL ? strlen(s) : (size_t) (line_break - s) + 1;
+        char *chars = malloc(line_length + 1);
+        memcpy(chars, s, line_length);
+        chars[line_length] = '\0';
+        char *line = quote(chars);
+        split = split[0] == '\0' ? line : format("%s%s%s", split, separator, line);
+        s += line_length;
+    }
+    return split;
+}
+
+// keys are quoted like node when they aren't ASCII identifiers
+static char *inspect_key(char *key) {
+    int identifier = isalpha(key[0]) || key[0] == '_';
+    for (int i = 1; identifier && key[i] != '\0'; i++) {
+        identifier = isalnum(key[i]) || key[i] == '_';
+    }
+    return identifier ? key : quote(key);
+}
+
+// an item or a property value, indented by 2 when the object takes several lines
+static char *inspect_property(inspector *ctx, databox a, int depth) {
+    ctx->indentation += 2;
+    char *shown = inspect_value(ctx, a, depth);
+    ctx->indentation -= 2;
+    return shown;
+}
+
+static int is_below_break_length(char **output, int length, int start) {
+    int total_length = length + start;
+    if (total_length + length > BREAK_LENGTH) {
+        return 0;
+    }
+    for (int i = 0; i < length; i++) {
+        total_length += js_length(output[i]);
+        if (total_length > BREAK_LENGTH) {
+            return 0;
+        }
+    }
+    return 1;
+}
+
+// `{ a, b }` if the innermost levels fit on a line, else one entry per line
+static char *reduce_to_single_string(inspector *ctx, char **output, int length, void *object, char *open, char *close,
+                                     int depth, int single_line) {
+    char *base = "";
+    for (int i = 0; i < ctx->circular_length; i++) {
+        if (ctx->circular[i] == object) {
+            base = format("<ref *%d> ", i + 1);
+        }
+    }
+    if (single_line && ctx->current_depth - depth < COMPACT) {
+        // the base is counted without its space
+        int base_length = base[0] == '\0' ? 0 : js_length(base) - 1;
+        int start = length + ctx->indentation + js_length(open) + base_length + 10;
+        if (is_below_break_length(output, length, start)) {
+            char *joined = join(output, length, ", ");
+            if (strchr(joined, '\n') == NULL) {
+                return format("%s%s %s %s", base, open, joined, close);
+            }
+        }
+    }
+    char *indentation = format("\n%s", spaces(ctx->indentation));
+    char *separator = format(",%s  ", indentation);
+    return format("%s%s%s  %s%s%s", base, open, indentation, join(output, length, separator), indentation, close);
+}
+
+// node lays out the short items of long arrays in aligned columns, numbers are
+// aligned on the right. Returns the number of lines
+static int group_array_elements(inspector *ctx, char ***lines, int length, array *arr) {
+    char **output = *lines;
+    int separator_space = 2;
+    int output_length = arr->length > MAX_ITEMS ? length - 1 : length;
+    int *data_length = malloc(length * sizeof(int));
+    int total_length = 0;
+    int max_length = 0;
+    for (int i = 0; i < output_length; i++) {
+        data_length[i] = js_length(output[i]);
+        total_length += data_length[i] + separator_space;
+        if (data_length[i] > max_length) {
+            max_length = data_length[i];
+        }
+    }
+    int actual_max = max_length + separator_space;
+    if (actual_max * 3 + ctx->indentation >= BREAK_LENGTH || ((double) total_length / actual_max <= 5 && max_length > 6)) {
+        return length;
+    }
+
+    double approx_char_heights = 2.5;
+    double average_bias = sqrt(actual_max - (double) total_length / length);
+    double biased_max = fmax(actual_max - 3 - average_bias, 1);
+    // javascript Math.round
+    int columns = floor(sqrt(approx_char_heights * biased_max * output_length) / biased_max + 0.5);
+    int limits[] = {(BREAK_LENGTH - ctx->indentation) / actual_max, COMPACT * 4, 15};
+    for (int i = 0; i < 3; i++) {
+        if (limits[i] < columns) {
+            columns = limits[i];
+        }
+    }
+    if (columns <= 1) {
+        return length;
+    }
+
+    int *max_line_length = malloc(columns * sizeof(int));
+    for (int i = 0; i < columns; i++) {
+        max_line_length[i] = 0;
+        for (int j = i; j < output_length; j += columns) {
+            if (data_length[j] > max_line_length[i]) {
+                max_line_length[i] = data_length[j];
+            }
+        }
+        max_line_length[i] += separator_space;
+    }
+    int numbers = 1;
+    for (int i = 0; i < length; i++) {
+        numbers = numbers && arr->items[i].type == NUM;
+    }
+    char **grouped = malloc(length * sizeof(char *));
+    int grouped_length = 0;
+    for (int i = 0; i < output_length; i += columns) {
+        int max = i + columns < output_length ? i + columns : output_length;
+        char *line = "";
+        int j = i;
+        for (; j < max - 1; j++) {
+            line = format("%s%s", line, pad(format("%s, ", output[j]), max_line_length[j - i], numbers));
+        }
+        if (numbers) {
+            line = format("%s%s", line, pad(output[j], max_line_length[j - i] - separator_space, 1));
+        } else {
+            line = format("%s%s", line, output[j]);
+        }
+        grouped[grouped_length++] = line;
+    }
+    if (output_length < length) {
+        grouped[grouped_length++] = output[output_length];
+    }
+    *lines = grouped;
+    return grouped_length;
+}
+
+// like node : `[ 1, 'a', [] ]`, `[Array]` past the depth limit
+static char *inspect_array(inspector *ctx, array *arr, int depth) {
+    if (arr->length == 0) {
+        return "[]";
+    }
+    if (depth > DEPTH) {
+        return "[Array]";
+    }
+
+    ctx->seen = realloc(ctx->seen, (ctx->seen_length + 1) * sizeof(void *));
+    ctx->seen[ctx->seen_length++] = arr;
+    ctx->current_depth = depth + 1;
+    int length = arr->length > MAX_ITEMS ? MAX_ITEMS + 1 : arr->length;
+    char **output = malloc(length * sizeof(char *));
+    for (int i = 0; i < length && i < MAX_ITEMS; i++) {
+        output[i] = inspect_property(ctx, arr->items[i], depth + 1);
+    }
+    if (arr->length > MAX_ITEMS) {
+        size_t more = arr->length - MAX_ITEMS;
+        output[MAX_ITEMS] = format("... %zu more item%s", more, more > 1 ? "s" : "");
+    }
+    ctx->seen_length--;
+
+    int entries = length;
+    if (entries > 6) {
+        length = group_array_elements(ctx, &output, length, arr);
+    }
+    return reduce_to_single_string(ctx, output, length, arr, "[", "]", depth + 1, entries == length);
+}
+
+// like node : `P { a: 1, b: 'x' }`, prefixed with the name of the constructor,
+// `[P]` past the depth limit
+static char *inspect_object(inspector *ctx, dictionary *dict, int depth) {
+    function *constructor = dict->constructor;
+    char *name = constructor != NULL && constructor->name[0] != '\0' ? constructor->name : "Object";
+    char *open = strcmp(name, "Object") == 0 ? "{" : format("%s {", name);
+    if (dict->length == 0) {
+        return format("%s}", open);
+    }
+    if (depth > DEPTH) {
+        return format("[%s]", name);
+    }
+
+    ctx->seen = realloc(ctx->seen, (ctx->seen_length + 1) * sizeof(void *));
+    ctx->seen[ctx->seen_length++] = dict;
+    ctx->current_depth = depth + 1;
+    char **output = malloc(dict->length * sizeof(char *));
+    for (int i = 0; i < dict->length; i++) {
+        char *value = inspect_property(ctx, *(databox *) dict->pairs[i]->value, depth + 1);
+        output[i] = format("%s: %s", inspect_key(dict->pairs[i]->key), value);
+    }
+    ctx->seen_length--;
+    return reduce_to_single_string(ctx, output, dict->length, dict, open, "}", depth + 1, 1);
+}
+
+// the number of the object a cycle leads back to, `[Circular *1]`
+static int circular_index(inspector *ctx, void *object) {
+    for (int i = 0; i < ctx->circular_length; i++) {
+        if (ctx->circular[i] == object) {
+            return i + 1;
+        }
+    }
+    ctx->circular = realloc(ctx->circular, (ctx->circular_length + 1) * sizeof(void *));
+    ctx->circular[ctx->circular_length++] = object;
+    return ctx->circular_length;
+}
+
+// an object inside itself is `[Circular *1]`, the object is then `<ref *1> { .. }`
+static char *inspect_value(inspector *ctx, databox a, int depth) {
+    switch (a.type) {
+        case NUM:
+            return a.data.num == 0 && signbit(a.data.num) ? "-0" : number_to_string(a.data.num);
+        case STR:
+            return inspect_string(ctx, a.data.str->chars);
+        case BOOL:
+            return a.data.boolean ? "true" : "false";
+        case NIL:
+            return "null";
+        case UNDEFINED:
+            return "undefined";
+        case FUNC:
+            return a.data.func->name[0] == '\0' ? "[Function (anonymous)]" : format("[Function: %s]", a.data.func->name);
+        case ARRAY:
+        case DICT:
+            break;
+    }
+
+    void *object = a.type == ARRAY ? (void *) a.data.arr : (void *) a.data.dict;
+    for (int i = 0; i < ctx->seen_length; i++) {
+        if (ctx->seen[i] == object) {
+            return format("[Circular *%d]", circular_index(ctx, object));
+        }
+    }
+    return a.type == ARRAY ? inspect_array(ctx, a.data.arr, depth) : inspect_object(ctx, a.data.dict, depth);
+}
+
+// how `console.log` shows a value like node's `util.inspect` : strings as they
+// are, `[ 1, 'a' ]` for arrays and `P { a: 1 }` for objects
+char *inspect(databox a) {
+    if (a.type == STR) {
+        return a.data.str->chars;
+    }
+    inspector ctx = {NULL, 0, NULL, 0, 0, 0};
+    char *shown = inspect_value(&ctx, a, 0);
+    free(ctx.seen);
+    free(ctx.circular);
+    return shown;
+}
+
+// several values separated with spaces on a line, like node's `console.log`
+void print_all(int argc, databox *argv) {
+    for (int i = 0; i < argc; i++) {
+        if (i > 0) {
+            print_str(" ");
+        }
+        print_data(argv[i]);
+    }
+    print_str("\n");
+}
+
+databox builtin_print(databox **env, databox this, int argc, databox *argv) {
+    print_all(argc, argv);
+    return new_undefined();
+}
+
+databox new_console(void) {
+    databox console = new_object();
+    set_property(console, "log", new_function("log", builtin_print, NULL));
+    return console;
+}
+
 void print_int(int a) {
     printf("%d", a);
 }
 void print_double(double a) {
-    printf("%f", a);
+    print_str(inspect(new_from_double(a)));
 }
 void print_str(char* a) {
     printf("%s", a);
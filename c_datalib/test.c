@@ -2,20 +2,26 @@
 #include "print.h"
 #include <assert.h>
 #include <stdio.h>
+#include <math.h>
 
 #define true 1
 #define false 0
 static databox one = { .data.num = 1, .type=NUM };
 static databox two = { .data.num = 2.0, .type=NUM };
 static databox three = { .data.num = 3.0, .type=NUM };
-static databox hello = { .data.str = "Hello", .type=STR };
-static databox box_42 = { .data.str = "42", .type=STR };
+static databox hello;
+static databox box_42;
 
 void test_inc() {
     printf("\n INCREMENT() TEST : \n");
     increment(&one);
     assert(one.data.num == 2.0);
     printf("%f", one.data.num);
+    databox count = new_from_str("1");
+    assert(increment(&count).data.num == 2 && count.type == NUM);
+    assert(decrement(&count).data.num == 1);
+    databox missing = new_undefined();
+    assert(isnan(increment(&missing).data.num));
 }
 
 
@@ -33,6 +39,28 @@ void test_print() {
     printf("Testing print() with char* : \n");
     print("hello");
     printf("\n");
+    printf("Testing print_all() : \n");
+    databox values[] = {two, new_from_str("a"), new_null()};
+    print_all(3, values);
+    printf("\n");
+    printf("Testing console.log() : \n");
+    databox log = get_index(new_console(), new_from_str("log"));
+    assert(log.type == FUNC);
+    assert(call_function(log, new_undefined(), 3, values).type == UNDEFINED);
+    printf("\n");
+    printf("Testing print() with objects : \n");
+    databox object = new_instance(new_function("P", builtin_print, NULL));
+    set_property(object, "nom", new_from_str("x"));
+    set_property(object, "a-b", new_array(3, values));
+    print(object);
+    print(" ");
+    print(new_object());
+    printf("\n");
+    set_property(object, "self", object);
+    assert(strcmp(inspect(object), "<ref *1> P { nom: 'x', 'a-b': [ 2, 'a', null ], self: [Circular *1] }") == 0);
+    databox nested = new_array(1, (databox[]){new_array(1, (databox[]){new_array(1, (databox[]){new_array(1, values)})})});
+    assert(strcmp(inspect(nested), "[ [ [ [Array] ] ] ]") == 0);
+    assert(strcmp(inspect(new_array(2, (databox[]){new_from_str("it's\n"), new_from_double(-0.0)})), "[ \"it's\\n\", -0 ]") == 0);
 }
 
 void test_new() {
@@ -42,7 +70,8 @@ void test_new() {
     databox c = new_from_double(12.0);
     databox obj = new_object();
 
-    assert(a.data.str == "brand new bag");
+    assert(strcmp(a.data.str->chars, "brand new bag") == 0);
+    assert(a.data.str->length == 13);
     assert(a.type == STR);
     assert(b.type == NUM);
     assert(c.type == NUM);
@@ -65,6 +94,10 @@ void test_mul() {
     printf("\n MUL() TEST : \n");
     databox result = mul(three, two);
     assert(result.data.num==6.0);
+    assert(mul(3, two).data.num == 6);
+    assert(mul(two, 1.5).data.num == 3);
+    assert(data_mul_data(new_from_str("3"), new_from_str("4")).data.num == 12);
+    assert(isnan(data_mul_data(hello, two).data.num));
 }
 
 void test_generic_gt() {
@@ -99,9 +132,12 @@ void test_add() {
 void test_sub() {
     double number = 1;
     databox result = sub(number, one);
-    printf("testing databox + number: \n");
-    assert(result.data.num);
-    printf("result: %f + %f = %f Ok!\n", one.data.num, number, result.data.num);
+    printf("testing number - databox: \n");
+    assert(result.data.num == number - one.data.num);
+    printf("result: %f - %f = %f Ok!\n", number, one.data.num, result.data.num);
+    assert(sub(10, two).data.num == 8);
+    assert(sub(two, 0.5).data.num == 1.5);
+    assert(data_sub_data(new_from_str("5"), new_bool(1)).data.num == 4);
 }
 
 void test_greater_than() {
@@ -115,15 +151,245 @@ void test_greater_than() {
     printf("testing data: %f > data: %f\n",two.data.num, one.data.num);
     assert(result == 1);
 
-    printf("testing data: %s > data: %s\n",hello.data.str, box_42.data.str);
+    printf("testing data: %s > data: %s\n",hello.data.str->chars, box_42.data.str->chars);
     result = data_greater_than_data(hello, box_42);
     assert(result == 1);
 
-    printf("testing data: %s > data: %s\n",box_42.data.str, hello.data.str);
+    printf("testing data: %s > data: %s\n",box_42.data.str->chars, hello.data.str->chars);
     result = data_greater_than_data(box_42, hello);
     assert(result == 0);
 }
 
+void test_bool_null_undefined() {
+    printf("\n BOOL, NULL AND UNDEFINED TEST : \n");
+    databox yes = new_bool(1);
+    databox nil = new_null();
+    databox undef = new_undefined();
+
+    assert(yes.type == BOOL);
+    assert(nil.type == NIL);
+    assert(undef.type == UNDEFINED);
+    assert(eq(yes, new_bool(2)) == true);
+    assert(eq(nil, undef) == true);
+    assert(eq(nil, new_bool(0)) == false);
+
+    print(yes);
+    printf(" ");
+    print(nil);
+    printf(" ");
+    print(undef);
+    printf("\n");
+}
+
+void test_to_bool() {
+    printf("\n TO_BOOL() TEST : \n");
+    assert(to_bool(one) == true);
+    assert(to_bool(new_from_double(0)) == false);
+    assert(to_bool(0.0 / 0.0) == false);
+    assert(to_bool(hello) == true);
+    assert(to_bool(new_from_str("")) == false);
+    assert(to_bool(new_object()) == true);
+    assert(to_bool(new_bool(0)) == false);
+    assert(to_bool(new_null()) == false);
+    assert(to_bool(new_undefined()) == false);
+    assert(to_bool(2) == true);
+}
+
+void test_is_nullish() {
+    printf("\n IS_NULLISH() TEST : \n");
+    assert(is_nullish(new_null()) == true);
+    assert(is_nullish(new_undefined()) == true);
+    assert(is_nullish(new_from_double(0)) == false);
+    assert(is_nullish(new_bool(0)) == false);
+}
+
+void test_strict_eq() {
+    printf("\n STRICT_EQ() TEST : \n");
+    assert(data_strict_eq_data(new_from_double(12), new_from_int(12)) == true);
+    assert(data_strict_eq_data(new_from_str("ab"), new_from_str("ab")) == true);
+    assert(data_strict_eq_data(new_from_str("ab"), new_from_str("cd")) == false);
+    assert(data_strict_eq_data(new_from_str("12"), new_from_double(12)) == false);
+    assert(data_strict_eq_data(new_null(), new_undefined()) == false);
+    assert(data_strict_eq_data(new_from_double(NAN), new_from_double(NAN)) == false);
+    databox object = new_object();
+    assert(data_strict_eq_data(object, object) == true);
+    assert(data_strict_eq_data(object, new_object()) == false);
+    assert(data_strict_neq_data(new_from_int(1), new_from_str("1")) == true);
+}
+
+void test_loose_eq() {
+    printf("\n LOOSE_EQ() TEST : \n");
+    assert(eq(new_from_str("ab"), new_from_str("cd")) == false);
+    assert(eq(new_from_str("1"), new_from_int(1)) == true);
+    assert(eq(new_from_int(16), new_from_str(" 0x10 ")) == true);
+    assert(eq(new_from_str(""), new_from_int(0)) == true);
+    assert(eq(new_from_str("1a"), new_from_int(1)) == false);
+    assert(eq(new_from_str("-Infinity"), new_from_double(-INFINITY)) == true);
+    assert(eq(new_bool(1), new_from_str("1")) == true);
+    assert(eq(new_bool(0), new_null()) == false);
+    assert(eq(new_undefined(), new_from_int(0)) == false);
+    assert(neq(new_from_int(2), new_bool(1)) == true);
+
+    databox items[] = {new_from_double(1.5), new_from_str("a"), new_null()};
+    databox array = new_array(3, items);
+    assert(eq(array, new_from_str("1.5,a,")) == true);
+    assert(eq(new_array(0, items), new_from_int(0)) == true);
+    assert(eq(new_object(), new_from_str("[object Object]")) == true);
+    assert(eq(new_object(), new_object()) == false);
+}
+
+void test_negate() {
+    printf("\n NEGATE() TEST : \n");
+    assert(negate(new_from_double(123)).data.num == -123);
+    assert(negate(new_bool(1)).data.num == -1);
+    assert(isnan(negate(new_undefined()).data.num));
+    assert(negate(new_from_str("3")).data.num == -3);
+}
+
+void test_operators() {
+    printf("\n OPERATORS() TEST : \n");
+    assert(data_mod_data(new_from_int(-7), new_from_int(3)).data.num == -1);
+    assert(data_exp_data(new_from_int(2), new_from_int(-1)).data.num == 0.5);
+    assert(isnan(data_exp_data(new_from_int(1), new_from_double(INFINITY)).data.num));
+    assert(data_div_data(new_from_str("7"), new_from_int(2)).data.num == 3.5);
+    assert(data_bit_and_data(new_from_int(5), new_from_int(3)).data.num == 1);
+    assert(data_bit_or_data(new_from_double(4294967297), new_from_int(0)).data.num == 1);
+    assert(data_bit_xor_data(new_from_int(5), new_from_int(3)).data.num == 6);
+    assert(data_bit_not(new_from_str("7")).data.num == -8);
+    assert(data_shift_left_data(new_from_int(1), new_from_int(31)).data.num == -2147483648.0);
+    assert(data_shift_left_data(new_from_int(1), new_from_int(32)).data.num == 1);
+    assert(data_shift_right_data(new_from_int(-16), new_from_int(2)).data.num == -4);
+    assert(data_unsigned_shift_right_data(new_from_int(-16), new_from_int(28)).data.num == 15);
+    assert(data_less_eq_data(new_from_str("a"), new_from_str("b")) == true);
+    assert(data_greater_eq_data(new_null(), new_from_int(0)) == true);
+    assert(data_less_eq_data(new_undefined(), new_from_int(0)) == false);
+    assert(data_strict_eq_data(type_of(new_null()), new_from_str("object")) == true);
+    assert(data_strict_eq_data(type_of(new_undefined()), new_from_str("undefined")) == true);
+
+    databox object = new_object();
+    set_property(object, "a", new_from_int(1));
+    assert(data_in_data(new_from_str("a"), object) == true);
+    assert(data_in_data(new_from_str("b"), object) == false);
+}
+
+void test_set_property() {
+    printf("\n SET_PROPERTY() TEST : \n");
+    databox object = new_object();
+    set_property(object, "age", new_from_double(33));
+    set_property(object, "age", new_from_double(34));
+    assert(object.data.dict->length == 1);
+    assert(((databox *) dictionary_find(object.data.dict, "age"))->data.num == 34);
+    assert(construct_result(object, new_undefined()).data.dict == object.data.dict);
+}
+
+databox add_captured(databox **env, databox this, int argc, databox *argv) {
+    *env[0] = data_add_data(*env[0], nth_arg(argc, argv, 0));
+    return *env[0];
+}
+
+void test_closure() {
+    printf("\n CLOSURE TEST : \n");
+    databox *total = new_cell(new_from_double(1));
+    databox f = new_function("add_captured", add_captured, new_env(1, total));
+    databox args[] = {new_from_double(2)};
+    assert(call_function(f, new_undefined(), 1, args).data.num == 3);
+    // the cell is shared with the closure
+    assert(total->data.num == 3);
+    assert(nth_arg(0, NULL, 0).type == UNDEFINED);
+    // a named function expression finds itself in the last cell
+    databox named = new_named_function("g", add_captured, new_env(2, total, new_cell(new_undefined())), 1);
+    assert(data_strict_eq_data(*named.data.func->env[1], named));
+    assert(data_strict_eq_data(named, new_function("g", add_captured, named.data.func->env)) == 0);
+}
+
+void test_array() {
+    printf("\n ARRAY TEST : \n");
+    databox a = new_array(2, (databox[]){new_from_double(1), new_from_str("a")});
+    databox pushed[] = {new_from_double(3), new_from_double(4), new_from_double(5)};
+    assert(array_push(a, 3, pushed).data.num == 5);
+    assert(array_pop(a).data.num == 5);
+    assert(get_index(a, new_from_str("length")).data.num == 4);
+    assert(strcmp(get_index(a, new_from_double(1)).data.str->chars, "a") == 0);
+    assert(get_index(a, new_from_double(7)).type == UNDEFINED);
+    assert(strcmp(get_index(a, new_from_str("1")).data.str->chars, "a") == 0);
+    assert(get_index(a, new_from_str("01")).type == UNDEFINED);
+    // writing past the end leaves holes
+    set_index(a, new_from_double(5), new_bool(1));
+    assert(a.data.arr->length == 6);
+    assert(get_index(a, new_from_double(4)).type == UNDEFINED);
+    assert(call_method(a, "pop", 0, NULL).type == BOOL);
+    // not indexes, the array has no other properties
+    set_index(a, new_from_double(4294967295.0), two);
+    set_index(a, new_from_str("99999999999"), two);
+    assert(a.data.arr->length == 5);
+    set_property(a, "length", new_from_double(3));
+    assert(a.data.arr->length == 3);
+    print_data(a);
+}
+
+void test_object() {
+    printf("\n OBJECT TEST : \n");
+    databox o = new_object();
+    set_property(o, "nom", new_from_str("Ken"));
+    assert(strcmp(get_index(o, new_from_str("nom")).data.str->chars, "Ken") == 0);
+    assert(get_index(o, new_from_str("age")).type == UNDEFINED);
+    // numbers name the same property as their string
+    set_index(o, new_from_double(1), new_from_double(39));
+    assert(get_index(o, new_from_str("1")).data.num == 39);
+    set_index(o, new_from_str("1"), new_from_double(40));
+    assert(get_index(o, new_from_double(1)).data.num == 40);
+    // property names are case sensitive
+    set_property(o, "a", new_from_double(1));
+    set_property(o, "A", new_from_double(2));
+    assert(get_index(o, new_from_str("a")).data.num == 1);
+    assert(get_index(o, new_from_str("A")).data.num == 2);
+}
+
+void test_loops() {
+    printf("\n LOOPS TEST : \n");
+    databox o = new_object();
+    set_property(o, "a", new_from_double(1));
+    set_property(o, "b", new_from_double(2));
+    databox keys = for_in_keys(o);
+    assert(array_length(keys) == 2);
+    assert(strcmp(get_index(keys, new_from_double(1)).data.str->chars, "b") == 0);
+    databox indexes = for_in_keys(new_array(2, (databox[]){new_null(), new_null()}));
+    assert(strcmp(get_index(indexes, new_from_double(1)).data.str->chars, "1") == 0);
+    databox characters = for_of_items(new_from_str("hey"));
+    assert(array_length(characters) == 3);
+    assert(strcmp(get_index(characters, new_from_double(2)).data.str->chars, "y") == 0);
+    assert(get_index(new_from_str("hey"), new_from_str("length")).data.num == 3);
+}
+
+void test_exceptions() {
+    printf("\n EXCEPTIONS TEST : \n");
+    try_handler outer;
+    try_enter(&outer);
+    if (!setjmp(outer.jump)) {
+        try_handler inner;
+        try_enter(&inner);
+        if (!setjmp(inner.jump)) {
+            throw_value(two);
+        }
+        assert(caught_value().data.num == 2);
+        // the inner handler is gone, this one reaches the outer handler
+        get_index(new_null(), hello);
+        assert(false);
+    }
+    databox error = caught_value();
+    assert(strcmp(get_index(error, new_from_str("name")).data.str->chars, "TypeError") == 0);
+    assert(strcmp(get_index(error, new_from_str("message")).data.str->chars, "Cannot read properties of null (reading 'Hello')") == 0);
+
+    // an array doesn't grow past ARRAY_MAX_LENGTH
+    try_handler range;
+    try_enter(&range);
+    if (!setjmp(range.jump)) {
+        set_index(new_array(0, NULL), new_from_double(2000000000), two);
+        assert(false);
+    }
+    assert(strcmp(get_index(caught_value(), new_from_str("name")).data.str->chars, "RangeError") == 0);
+}
+
 void test_dict() {
     dictionary *dict = dictionary_new();
     databox kevin = new_from_str("Eeasy peasy lemon squizzy");
@@ -134,7 +400,78 @@ void test_dict() {
     print_data(*(databox*) dictionary_find(dict, "jason"));
 }
 
+void test_number_to_string() {
+    printf("\n NUMBER_TO_STRING() TEST : \n");
+    // the same cases are checked against the Rust `number_to_string`
+#define NUMBER_CASE(number, expected) assert(strcmp(number_to_string(number), expected) == 0);
+#include "number_cases.h"
+#undef NUMBER_CASE
+    databox items[] = {new_from_double(1.5), new_from_int(2)};
+    assert(strcmp(data_add_data(new_from_str("n="), new_from_double(0.5)).data.str->chars, "n=0.5") == 0);
+    assert(strcmp(data_add_data(new_array(2, items), new_from_int(1)).data.str->chars, "1.5,21") == 0);
+    assert(data_add_data(new_bool(1), new_null()).data.num == 1);
+}
+
+void test_strings() {
+    printf("\n STRINGS TEST : \n");
+    string *joined = string_concat(new_string("ab", 2), new_string("cde", 3));
+    assert(joined->length == 5 && strcmp(joined->chars, "abcde") == 0);
+    assert(strcmp(string_slice(joined, 1, 3)->chars, "bc") == 0);
+    assert(string_slice(joined, 4, 10)->length == 1);
+    assert(string_slice(joined, 3, 1)->length == 0);
+    assert(string_compare(new_string("ab", 2), joined) < 0);
+    assert(string_compare(new_string("b", 1), joined) > 0);
+    assert(string_compare(new_string("abcde", 5), joined) == 0);
+    // by UTF-16 code units, U+1F600 starts with the surrogate U+D83D
+    assert(string_compare(new_string("\xF0\x9F\x98\x80", 4), new_string("\xEE\x80\x80", 3)) < 0);
+    assert(string_compare(new_string("a\xEE\x80\x80", 4), new_string("a\xF0\x9F\x98\x80", 5)) > 0);
+    assert(string_compare(new_string("\xF0\x9F\x98\x80", 4), new_string("\xF0\x9F\x98\x81", 4)) < 0);
+    assert(string_compare(new_string("\xC3\xA9", 2), new_string("z", 1)) > 0);
+
+    // a nul character is part of the string
+    databox with_nul = new_from_string(new_string("a\0b", 3));
+    assert(get_index(with_nul, new_from_str("length")).data.num == 3);
+    assert(data_strict_eq_data(with_nul, new_from_str("a")) == false);
+
+    // lengths and indexes count UTF-16 code units like javascript
+    databox accents = new_from_str("\xC3\xA9t\xC3\xA9");
+    assert(get_index(accents, new_from_str("length")).data.num == 3);
+    assert(strcmp(get_index(accents, new_from_int(2)).data.str->chars, "\xC3\xA9") == 0);
+    assert(get_index(accents, new_from_int(3)).type == UNDEFINED);
+    databox emoji = new_from_str("\xF0\x9F\x98\x80!");
+    assert(get_index(emoji, new_from_str("length")).data.num == 3);
+    assert(strcmp(get_index(emoji, new_from_int(1)).data.str->chars, "\xEF\xBF\xBD") == 0);
+    assert(strcmp(get_index(emoji, new_from_int(2)).data.str->chars, "!") == 0);
+    assert(array_length(for_of_items(emoji)) == 2);
+    assert(array_length(for_in_keys(emoji)) == 3);
+
+    databox i = new_from_int(2);
+    assert(strcmp(data_add_data(new_from_str("a"), i).data.str->chars, "a2") == 0);
+    assert(strcmp(data_add_data(new_null(), new_from_str("b")).data.str->chars, "nullb") == 0);
+    databox x = new_from_str("x");
+    assert(strcmp(add(1, x).data.str->chars, "1x") == 0);
+    assert(strcmp(add(1.5, x).data.str->chars, "1.5x") == 0);
+    assert(strcmp(add(x, 1).data.str->chars, "x1") == 0);
+    assert(strcmp(add(x, 1.5).data.str->chars, "x1.5") == 0);
+    assert(add(2, new_bool(1)).data.num == 3);
+    assert(strcmp(data_to_string(new_from_double(1e21))->chars, "1e+21") == 0);
+    assert(data_less_than_data(new_from_str("10"), new_from_str("9")) == true);
+    assert(data_less_than_data(new_from_str("10"), new_from_int(9)) == false);
+    assert(data_greater_than_data(new_from_str("b"), new_from_str("abc")) == true);
+    assert(data_greater_than_data(new_from_str("abc"), new_from_str("ab")) == true);
+    assert(data_greater_than_data(new_from_str("a"), new_from_int(1)) == false);
+    assert(data_less_than_data(new_from_str("a"), new_from_int(1)) == false);
+    databox items[] = {new_from_int(2)};
+    databox tens[] = {new_from_int(10)};
+    assert(data_greater_than_data(new_array(1, items), new_array(1, tens)) == true);
+}
+
 int main() {
+    hello = new_from_str("Hello");
+    box_42 = new_from_str("42");
+    // before test_inc increments `one`
+    test_eq();
+    test_new();
     test_generic_gt();
     test_generic_lt();
     test_greater_than();
@@ -144,5 +481,20 @@ int main() {
     test_mul();
     test_print();
     test_dict();
+    test_bool_null_undefined();
+    test_to_bool();
+    test_is_nullish();
+    test_strict_eq();
+    test_loose_eq();
+    test_negate();
+    test_operators();
+    test_number_to_string();
+    test_strings();
+    test_set_property();
+    test_closure();
+    test_array();
+    test_object();
+    test_loops();
+    test_exceptions();
     return 0;
 }
@@ -0,0 +1,357 @@
+#ifndef DATABOX_H
+#define DATABOX_H
+#include <setjmp.h>
+#include <string.h>
+#include "dict.h"
+
+typedef enum type_e {
+    NUM,
+    STR,
+    DICT,
+    BOOL,
+    // javascript null, NULL is already taken by the C library
+    NIL,
+    UNDEFINED,
+    FUNC,
+    ARRAY,
+} type_e;
+
+struct databox;
+
+// a javascript array, growing as items are pushed
+typedef struct array {
+    struct databox *items;
+    size_t length;
+    size_t capacity;
+} array;
+
+// a javascript function : the compiled C function and the environment holding
+// the variables it captured, shared with the function that created it
+typedef struct function {
+    struct databox (*code)(struct databox **env, struct databox this, int argc, struct databox *argv);
+    struct databox **env;
+    char *name;
+} function;
+
+// a javascript string allocated with its length in bytes, the characters are
+// UTF-8 and also nul terminated for the C library
+typedef struct string {
+    int length;
+    char chars[];
+} string;
+
+typedef union data_u {
+    double num;
+    string *str;
+    dictionary *dict;
+    int boolean;
+    function *func;
+    array *arr;
+} data_u;
+
+typedef struct databox {
+    data_u data;
+    type_e type;
+} databox;
+
+databox copy(databox a);
+databox new_from_int(int a);
+databox new_from_double(double a);
+databox new_from_str(char *a);
+databox new_from_string(string *a);
+databox new_object();
+databox new_instance(databox constructor);
+databox set_property(databox object, char *key, databox value);
+databox construct_result(databox this, databox returned);
+databox new_bool(int a);
+databox new_null(void);
+databox new_undefined(void);
+databox new_function(char *name, databox (*code)(databox **, databox, int, databox *), databox **env);
+databox new_named_function(char *name, databox (*code)(databox **, databox, int, databox *), databox **env, int self);
+
+// arrays
+databox new_array(int length, databox *items);
+databox array_push(databox a, int argc, databox *argv);
+databox array_pop(databox a);
+databox get_index(databox object, databox key);
+databox set_index(databox object, databox key, databox value);
+databox call_method(databox object, char *name, int argc, databox *argv);
+int array_length(databox a);
+
+// loops
+databox for_in_keys(databox object);
+databox for_of_items(databox iterable);
+
+// closures
+databox *new_cell(databox value);
+databox **new_env(int size, ...);
+databox nth_arg(int argc, databox *argv, int n);
+databox call_function(databox f, databox this, int argc, databox *argv);
+
+// exceptions : a `try` block registers a handler, `throw` jumps back to the
+// innermost one, the thrown value is then read with caught_value()
+typedef struct try_handler {
+    jmp_buf jump;
+    struct try_handler *outer;
+} try_handler;
+
+void try_enter(try_handler *handler);
+void try_leave(void);
+databox caught_value(void);
+void throw_value(databox value) __attribute__((noreturn));
+databox new_error(char *name, char *message);
+void throw_error(char *name, char *message) __attribute__((noreturn));
+
+// `++a` and `--a` : the variable is converted to a number, then updated
+databox decrement(databox *a);
+databox increment(databox *a);
+
+databox data_add_data(databox a, databox b);
+databox double_add_data(double a, databox b);
+databox data_add_double(databox a, double b);
+databox int_add_data(int a, databox b);
+databox data_add_int(databox a, int b);
+
+databox data_sub_data(databox a, databox b);
+databox double_sub_data(double a, databox b);
+databox data_sub_double(databox a, double b);
+databox int_sub_data(int a, databox b);
+databox data_sub_int(databox a, int b);
+
+int data_greater_than_data(databox a, databox b);
+int double_greater_than_data(double a, databox b);
+int data_greater_than_double(databox a, double b);
+int double_greater_than_double(double a, double b);
+int int_greater_than_data(int a, databox b);
+int data_greater_than_int(databox a, int b);
+
+int data_less_than_data(databox a, databox b);
+int double_less_than_data(double a, databox b);
+int data_less_than_double(databox a, double b);
+int double_less_than_double(double a, double b);
+int int_less_than_data(int a, databox b);
+int data_less_than_int(databox a, int b);
+
+int data_eq_data(databox a, databox b);
+int double_eq_data(double a, databox b);
+int data_eq_double(databox a, double b);
+int int_eq_data(int a, databox b);
+int data_eq_int(databox a, int b);
+
+int data_strict_eq_data(databox a, databox b);
+int data_strict_neq_data(databox a, databox b);
+
+int data_neq_data(databox a, databox b);
+int double_neq_data(double a, databox b);
+int data_neq_double(databox a, double b);
+int int_neq_data(int a, databox b);
+int data_neq_int(databox a, int b);
+
+int data_less_eq_data(databox a, databox b);
+int data_greater_eq_data(databox a, databox b);
+
+// javascript `Number.prototype.toString`
+char *number_to_string(double a);
+
+string *new_string(const char *chars, int length);
+string *string_concat(string *a, string *b);
+string *string_slice(string *s, int start, int end);
+int string_compare(string *a, string *b);
+// javascript ToString
+string *data_to_string(databox a);
+
+// the operators converting their operands to numbers
+double data_to_number(databox a);
+databox data_div_data(databox a, databox b);
+databox data_mod_data(databox a, databox b);
+databox data_exp_data(databox a, databox b);
+databox data_bit_and_data(databox a, databox b);
+databox data_bit_or_data(databox a, databox b);
+databox data_bit_xor_data(databox a, databox b);
+databox data_shift_left_data(databox a, databox b);
+databox data_shift_right_data(databox a, databox b);
+databox data_unsigned_shift_right_data(databox a, databox b);
+databox data_bit_not(databox a);
+
+int data_in_data(databox key, databox object);
+int data_instanceof_data(databox object, databox constructor);
+databox type_of(databox a);
+
+int reverse(int boolean);
+
+int data_to_bool(databox a);
+int double_to_bool(double a);
+int int_to_bool(int a);
+int is_nullish(databox a);
+
+databox negate(databox a);
+
+databox double_mul_data(const double a, const databox b);
+databox data_mul_double(const databox a, const double b);
+databox int_mul_data(const int a, const databox b);
+databox data_mul_int(const databox a, const int b);
+databox data_mul_data(databox a, databox b);
+
+// dead code to make _Generic happy
+int def(double a, double b);
+int def_2(double a);
+
+#define new(a) _Generic((a),                            \
+        databox: copy,                                  \
+        double: new_from_double,                        \
+        int: new_from_int,                              \
+        char*: new_from_str,                            \
+        default: def_2)(a)
+
+
+// javascript truthiness
+#define to_bool(a) _Generic((a),                        \
+        databox: data_to_bool,                          \
+        double:  double_to_bool,                        \
+        int:     int_to_bool,                           \
+        default: def_2)(a)
+
+#define mul(a, b) _Generic((a),                         \
+        double:  mul_given_double(b),                   \
+        int: mul_given_int(b),                          \
+        databox: mul_given_data(b),                     \
+        default: def)((a),(b))
+
+#define mul_given_double(b) _Generic((b),               \
+        databox: double_mul_data,                       \
+        default: def)
+
+#define mul_given_int(b) _Generic((b),                  \
+        databox: int_mul_data,                          \
+        default: def)
+
+#define mul_given_data(b) _Generic((b),                 \
+        databox: data_mul_data,                         \
+        double:  data_mul_double,                       \
+        int:     data_mul_int)
+
+
+#define add(a, b) _Generic((a),                         \
+        double:  add_given_double(b),                   \
+        int:     add_given_int(b),                      \
+        databox: add_given_data(b),                     \
+        default: def)((a),(b))
+
+#define add_given_double(b) _Generic((b),               \
+        databox: double_add_data,                       \
+        default: def)
+
+#define add_given_data(b) _Generic((b),                 \
+        databox: data_add_data,                         \
+        double:  data_add_double,                       \
+        int:     data_add_int)
+
+#define add_given_int(b) _Generic((b),                  \
+        databox: int_add_data,                          \
+        default: def)                                   \
+
+
+#define sub(a, b) _Generic((a),                         \
+        double:  sub_given_double(b),                   \
+        int:     sub_given_int(b),                      \
+        databox: sub_given_data(b),                     \
+        default: def)((a),(b))
+
+#define sub_given_double(b) _Generic((b),               \
+        databox: double_sub_data,                       \
+        default: def)
+
+#define sub_given_data(b) _Generic((b),                 \
+        databox: data_sub_data,                         \
+        double:  data_sub_double,                       \
+        int:     data_sub_int)
+
+#define sub_given_int(b) _Generic((b),                  \
+        databox: int_sub_data,                          \
+        default: def)                                   \
+
+
+#define gt(a, b) _Generic((a),                          \
+        double:  greater_than_given_double(b),          \
+        int: greater_than_given_int(b),                 \
+        databox: greater_than_given_data(b),            \
+        default: def)((a),(b))
+
+#define greater_than_given_double(b) _Generic((b),      \
+        databox: double_greater_than_data,              \
+        double:  double_greater_than_double,            \
+        default: def)
+
+#define greater_than_given_data(b) _Generic((b),        \
+        databox: data_greater_than_data,                \
+        double:  data_greater_than_double,              \
+        int:  data_greater_than_int)
+
+#define greater_than_given_int(b) _Generic((b),         \
+        databox: int_greater_than_data,                 \
+        default: def)                                   \
+
+
+#define lt(a, b) _Generic((a),                          \
+        double:  less_than_given_double(b),             \
+        int:     less_than_given_int(b),                \
+        databox: less_than_given_data(b),               \
+        default: def)((a),(b))
+
+#define less_than_given_double(b) _Generic((b),         \
+        databox: double_less_than_data,                 \
+        double: double_less_than_double,                \
+        default: def)
+
+#define less_than_given_data(b) _Generic((b),           \
+        databox: data_less_than_data,                   \
+        int:     data_less_than_int,                    \
+        double:  data_less_than_double)
+
+#define less_than_given_int(b) _Generic((b),            \
+        databox: int_less_than_data,                    \
+        default: def)                                   \
+
+
+// Todo : implement equals for char*
+#define eq(a, b) _Generic((a),                          \
+        double:  eq_given_double(b),                    \
+        int:     eq_given_int(b),                       \
+        databox: eq_given_data(b),                      \
+        default: def)((a),(b))
+
+#define eq_given_double(b) _Generic((b),                \
+        databox: double_eq_data,                        \
+        default: def)
+
+#define eq_given_data(b) _Generic((b),                  \
+        databox: data_eq_data,                          \
+        int:     data_eq_int,                           \
+        double:  data_eq_double)
+
+#define eq_given_int(b) _Generic((b),                   \
+        databox: int_eq_data,                           \
+        default: def)                                   \
+
+#define neq(a, b) _Generic((a),                         \
+        double:  neq_given_double(b),                   \
+        int:     neq_given_int(b),                      \
+        databox: neq_given_data(b),                     \
+        default: def)((a),(b))
+
+#define neq_given_double(b) _Generic((b),               \
+        databox: double_neq_data,                       \
+        default: def)
+
+#define neq_given_data(b) _Generic((b),                 \
+        databox: data_neq_data,                         \
+        int:     data_neq_int,                          \
+        double:  data_neq_double)
+
+#define neq_given_int(b) _Generic((b),                  \
+        databox: int_neq_data,                          \
+        default: def)                                   \
+
+#endif
+
+
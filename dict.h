@@ -0,0 +1,21 @@
+#ifndef DICT_H
+#define DICT_H
+#include "keyval.h"
+
+extern void *dictionary_not_found;
+
+typedef struct dictionary {
+    keyval **pairs;
+    int length;
+    // the function `new` created the object with, see data_instanceof_data
+    void *constructor;
+} dictionary;
+
+
+dictionary * dictionary_new (void);
+dictionary * dictionary_copy (dictionary *in);
+void dictionary_free(dictionary *in);
+void dictionary_add(dictionary *in, char *key, void *value);
+void *dictionary_find(dictionary const *in, char const *key);
+
+#endif
@@ -0,0 +1,20 @@
+#include "databox.h"
+
+void print_data(databox a);
+char *inspect(databox a);
+void print_all(int argc, databox *argv);
+void print_int(int a);
+void print_double(double a);
+void print_str(char* a);
+void eprint();
+
+// `print` and `console.log` as javascript functions, and the `console` object
+databox builtin_print(databox **env, databox this, int argc, databox *argv);
+databox new_console(void);
+
+#define print(a) _Generic((a),  \
+        databox:  print_data,   \
+        int: print_int,         \
+        double: print_double,   \
+        char*: print_str,       \
+        default: eprint) (a)
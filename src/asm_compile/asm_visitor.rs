@@ -9,61 +9,90 @@ use crate::asm_compile::Register;
 use crate::asm_compile::*;
 use crate::token::to_token::ToToken;
 use crate::token::to_token::postfix;
+use crate::error::CompileError;
+use crate::error::CompileResult;
 
 impl<'pr> Visitor for ASMWriter<'pr> {
-    fn visit_block_statement(&mut self, s: &BlockStmt) {
-        unimplemented!();
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`BlockStatement` in the asm backend", &s.loc))
     }
 
-    fn visit_variable_declarator(&mut self, v: &Variable) {
-        unimplemented!();
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()> {
+        Err(CompileError::unsupported("`VariableDeclarator` in the asm backend", &v.loc))
     }
 
-    fn visit_while_statement(&mut self, w: &WhileStmt) {
-        unimplemented!();
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`WhileStatement` in the asm backend", &w.loc))
     }
 
-    fn visit_variable_declaration(&mut self, v: &VariableDec) {
-        unimplemented!();
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()> {
+        Err(CompileError::unsupported("`VariableDeclaration` in the asm backend", &v.loc))
     }
 
-    fn visit_if_statement(&mut self, i: &IfStmt) {
-        unimplemented!();
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`IfStatement` in the asm backend", &i.loc))
     }
 
-    fn visit_switch_statement(&mut self, s: &SwitchStmt) {
-        unimplemented!();
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`SwitchStatement` in the asm backend", &s.loc))
     }
 
-    fn visit_case(&mut self, case: &CaseStmt) {
-        unimplemented!();
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`SwitchCase` in the asm backend", &case.loc))
     }
 
-    fn visit_for_statement(&mut self, f: &ForStmt) {
-        unimplemented!();
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ForStatement` in the asm backend", &f.loc))
     }
 
-    fn visit_break_statement(&mut self, f: &BreakStmt) {
-        unimplemented!();
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`DoWhileStatement` in the asm backend", &d.loc))
     }
 
-    fn visit_return_statement(&mut self, r: &ReturnStmt) {
-        unimplemented!();
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ForInStatement` in the asm backend", &f.loc))
     }
 
-    fn visit_continue_statement(&mut self, c: &ContinueStmt) {
-        unimplemented!();
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ForOfStatement` in the asm backend", &f.loc))
     }
 
-    fn visit_function_declaration(&mut self, f: &FunctionDec) {
-        unimplemented!();
+    fn visit_break_statement(&mut self, f: &BreakStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`BreakStatement` in the asm backend", &f.loc))
     }
 
-    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) {
-        unimplemented!();
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ReturnStatement` in the asm backend", &r.loc))
     }
 
-    fn visit_expression(&mut self, exp: &Expression) {
+    fn visit_continue_statement(&mut self, c: &ContinueStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ContinueStatement` in the asm backend", &c.loc))
+    }
+
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`LabeledStatement` in the asm backend", &l.loc))
+    }
+
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ThrowStatement` in the asm backend", &t.loc))
+    }
+
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()> {
+        Err(CompileError::unsupported("`TryStatement` in the asm backend", &t.loc))
+    }
+
+    fn visit_function_declaration(&mut self, f: &FunctionDec) -> CompileResult<()> {
+        Err(CompileError::unsupported("`FunctionDeclaration` in the asm backend", &f.loc))
+    }
+
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()> {
+        match exp {
+            Some(expression) => self.visit_expression(expression),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()> {
         match exp {
             UpdateExpression(ref u) => self.visit_update_expression(u),
             BinaryExpression(ref b) => self.visit_binary_expression(b),
@@ -72,50 +101,72 @@ impl<'pr> Visitor for ASMWriter<'pr> {
             CallExpression(ref c) => self.visit_call_expression(c),
             AssignmentExpression(ref e) => self.visit_assign(e),
             LogicalExpression(ref l) => self.visit_logical_expression(l),
-            _ => (),
-        };
+            NewExpression(ref n) => self.visit_new_expression(n),
+            FunctionExpression(ref f) => self.visit_function_expression(f),
+            ArrowFunctionExpression(ref f) => self.visit_arrow_function_expression(f),
+            ArrayExpression(ref a) => self.visit_array_expression(a),
+            _ => Ok(()),
+        }
     }
 
-    fn visit_expression_statement(&mut self, s: &ExpressionStmt) {
-        self.visit_expression(&s.expression);
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()> {
+        self.visit_expression(&s.expression)
     }
 
-    fn visit_binary_expression(&mut self, b: &BinaryExp) {
-        let tokens = &b.to_token();
-        let mut postfix_expression = postfix(&mut tokens.clone());
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()> {
+        let tokens = &b.to_token()?;
+        let mut postfix_expression = postfix(&mut tokens.clone(), &b.loc)?;
         self.postfix_to_asm(&mut postfix_expression);
         self.append(TAB);
         self.append(NEW_LINE);
+        Ok(())
+    }
+
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`AssignmentExpression` in the asm backend", &a.loc))
+    }
 
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`UnaryExpression` in the asm backend", &u.loc))
     }
 
-    fn visit_assign(&mut self, a: &AssignmentExp) { unimplemented!(); }
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`UpdateExpression` in the asm backend", &u.loc))
+    }
+
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`MemberExpression` in the asm backend", &m.loc))
+    }
+
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`LogicalExpression` in the asm backend", &l.loc))
+    }
 
-    fn visit_unary_expression(&mut self, u: &UnaryExp) {
-        unimplemented!();
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`CallExpression` in the asm backend", &e.loc))
     }
 
-    fn visit_update_expression(&mut self, u: &UpdateExp) {
-        unimplemented!();
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`NewExpression` in the asm backend", &n.loc))
     }
 
-    fn visit_member_expression(&mut self, m: &MemberExp) {
-        unimplemented!();
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`FunctionExpression` in the asm backend", &f.loc))
     }
 
-    fn visit_logical_expression(&mut self, l: &LogicalExp) {
-        unimplemented!();
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ArrowFunctionExpression` in the asm backend", &f.loc))
     }
 
-    fn visit_call_expression(&mut self, e: &CallExp) {
-        unimplemented!();
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ArrayExpression` in the asm backend", &a.loc))
     }
 
-    fn visit_object_expression(&mut self, o: &ObjectExp, id: String) {
-        unimplemented!();
+    fn visit_object_expression(&mut self, o: &ObjectExp, _id: String) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ObjectExpression` in the asm backend", &o.loc))
     }
 
-    fn visit_property_expression(&mut self, id: &str, p: &Property) {
-        unimplemented!();
+    fn visit_property_expression(&mut self, _id: &str, p: &Property) -> CompileResult<()> {
+        Err(CompileError::unsupported("`ObjectProperty` in the asm backend", &p.key.get_loc()))
     }
 }
\ No newline at end of file
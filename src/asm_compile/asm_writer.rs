@@ -16,6 +16,7 @@ use crate::token::token::Node;
 use crate::ast::statement::Statement::*;
 use crate::token::token::Token::OperatorToken;
 use crate::c_compile::RETURN;
+use crate::error::CompileResult;
 
 pub struct ASMWriter<'printer> {
     pub out: &'printer mut String,
@@ -26,35 +27,36 @@ pub struct ASMWriter<'printer> {
 }
 
 impl<'printer> ASMWriter<'printer> {
-    pub fn build(&mut self, ast: Vec<Box<Statement>>) {
+    pub fn build(&mut self, ast: Vec<Box<Statement>>) -> CompileResult<()> {
         let mut ast_mut = ast.clone();
 
         let mut indexes_to_remove = vec![];
-        ast_mut.iter_mut().enumerate().for_each(|(i, statement)| {
+        for (i, statement) in ast_mut.iter_mut().enumerate() {
             if let box VariableDeclaration(var) = statement {
-                self.visit_variable_declaration(var);
+                self.visit_variable_declaration(var)?;
                 indexes_to_remove.push(i);
             };
-        });
+        }
 
         indexes_to_remove.iter().for_each(|i| { ast_mut.remove(*i); });
         let mut indexes_to_remove = vec![];
 
-        ast.iter().enumerate().for_each(|(i, statement)| {
+        for (i, statement) in ast.iter().enumerate() {
             if let box FunctionDeclaration(function) = statement {
-                self.visit_function_declaration(function);
+                self.visit_function_declaration(function)?;
                 indexes_to_remove.push(i);
             };
-        });
+        }
 
         indexes_to_remove.iter().for_each(|i| { ast_mut.remove(*i); });
 
         self.append(ASM_INIT);
-        ast.iter().enumerate().for_each(|(i, statement)| {
-            self.visit_statement(statement);
-        });
+        for statement in ast.iter() {
+            self.visit_statement(statement)?;
+        }
         self.append(TAB);
         self.append(ASM_RET);
+        Ok(())
     }
 
     pub fn append(&mut self, word: &str) {
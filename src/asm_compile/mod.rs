@@ -3,6 +3,11 @@ use crate::asm_compile::asm_writer::ASMWriter;
 pub mod asm_writer;
 pub mod asm_visitor;
 
+/// Estree node types the asm backend compiles, see `check`
+pub const SUPPORTED_NODES: &[&'static str] = &[
+    "ExpressionStatement", "BinaryExpression", "NumericLiteral", "StringLiteral",
+];
+
 pub const ASM_INIT: &'static str = ".text\n.global main\n.type main, @function\nmain:\n";
 
 pub const RAX: &'static str ="rax";
@@ -2,15 +2,26 @@
 // see : https://github.com/serde-rs/serde/issues/1402
 // could be useful for literals
 
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
 use crate::ast::expression::Expression::*;
+use crate::ast::statement::BlockStmt;
+use crate::ast::statement::Statement;
+use crate::ast::UnsupportedNode;
 
+/// (De)serialized by hand so that unknown node types end up in `Unsupported`
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(tag = "type")]
+#[serde(tag = "type", remote = "Self")]
 pub enum Expression {
     BinaryExpression(BinaryExp),
     UnaryExpression(UnaryExp),
     NumericLiteral(NumericLit),
     StringLiteral(StringLit),
+    BooleanLiteral(BooleanLit),
+    NullLiteral(NullLit),
     Identifier(Id),
     UpdateExpression(UpdateExp),
     CallExpression(CallExp),
@@ -18,6 +29,37 @@ pub enum Expression {
     LogicalExpression(LogicalExp),
     MemberExpression(MemberExp),
     ObjectExpression(ObjectExp),
+    NewExpression(NewExp),
+    ThisExpression(ThisExp),
+    FunctionExpression(FunctionExp),
+    ArrowFunctionExpression(ArrowFunctionExp),
+    ArrayExpression(ArrayExp),
+    #[serde(skip)]
+    Unsupported(UnsupportedNode),
+}
+
+/// Node types with an `Expression` variant, anything else is unsupported
+const EXPRESSION_TYPES: &[&'static str] = &[
+    "BinaryExpression", "UnaryExpression", "NumericLiteral", "StringLiteral", "BooleanLiteral",
+    "NullLiteral", "Identifier",
+    "UpdateExpression", "CallExpression", "AssignmentExpression", "LogicalExpression",
+    "MemberExpression", "ObjectExpression", "NewExpression", "ThisExpression",
+    "FunctionExpression", "ArrowFunctionExpression", "ArrayExpression",
+];
+
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Unsupported(node) => node.serialize(serializer),
+            known => Expression::serialize(known, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Expression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        super::deserialize_node(deserializer, EXPRESSION_TYPES, Expression::deserialize, Unsupported)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -25,6 +67,22 @@ pub struct Id {
     pub name: String,
     #[serde(skip_serializing_if = "super::with_loc")]
     pub loc: Loc,
+    /// What the identifier refers to, set by `scope::resolve`
+    #[serde(skip)]
+    pub binding: Option<Binding>,
+}
+
+/// Where the variable an identifier refers to is declared
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    /// In the top level code, outside of any block
+    Global,
+    /// In the function using it, or in one of its blocks
+    Local,
+    /// In an enclosing function, or a block of the top level code
+    Enclosing,
+    /// Nowhere, the backends provide it, see `scope::BUILTINS`
+    Builtin,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -41,11 +99,24 @@ pub struct NumericLit {
     pub loc: Loc,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BooleanLit {
+    pub value: bool,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NullLit {
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UpdateExp {
     pub operator: String,
     pub argument: Box<Expression>,
-    prefix: bool,
+    pub prefix: bool,
     #[serde(skip_serializing_if = "super::with_loc")]
     pub loc: Loc,
 }
@@ -58,6 +129,63 @@ pub struct CallExp {
     pub loc: Loc,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewExp {
+    pub callee: Box<Expression>,
+    pub arguments: Vec<Box<Expression>>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThisExp {
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FunctionExp {
+    pub id: Option<Id>,
+    pub params: Vec<Id>,
+    pub body: BlockStmt,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArrowFunctionExp {
+    pub params: Vec<Id>,
+    pub body: ArrowBody,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+/// `x => { return x; }` has a block body, `x => x` an expression one
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ArrowBody {
+    Block(BlockStmt),
+    Expression(Box<Expression>),
+}
+
+/// Serialized as the estree node, the block body is a `BlockStatement`
+impl Serialize for ArrowBody {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ArrowBody::Block(block) => Statement::BlockStatement(block.clone()).serialize(serializer),
+            ArrowBody::Expression(expression) => expression.serialize(serializer),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArrayExp {
+    /// `None` for the holes of `[1, , 3]`
+    pub elements: Vec<Option<Box<Expression>>>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BinaryExp {
     pub left: Box<Expression>,
@@ -157,6 +285,13 @@ impl BinaryExp {
     }
 }
 
+impl Id {
+    /// True if the identifier is the builtin `name` rather than a variable
+    pub fn is_builtin(&self, name: &str) -> bool {
+        self.binding == Some(Binding::Builtin) && self.name == name
+    }
+}
+
 impl ToString for Id {
     fn to_string(&self) -> String {
         self.name.clone()
@@ -164,6 +299,12 @@ impl ToString for Id {
 }
 
 impl ToString for NumericLit {
+    fn to_string(&self) -> String {
+        crate::token::number_to_string(self.value)
+    }
+}
+
+impl ToString for BooleanLit {
     fn to_string(&self) -> String {
         format!("{}", self.value)
     }
@@ -184,13 +325,46 @@ impl Expression {
             UnaryExpression(exp)=> exp.loc.clone(),
             NumericLiteral(exp)=> exp.loc.clone(),
             StringLiteral(exp)=> exp.loc.clone(),
+            BooleanLiteral(exp)=> exp.loc.clone(),
+            NullLiteral(exp)=> exp.loc.clone(),
             Identifier(exp)=> exp.loc.clone(),
             UpdateExpression(exp)=> exp.loc.clone(),
             CallExpression(exp)=> exp.loc.clone(),
             AssignmentExpression(exp)=> exp.loc.clone(),
             LogicalExpression(exp)=> exp.loc.clone(),
             MemberExpression(exp)=> exp.loc.clone(),
-            ObjectExpression(exp)=> exp.loc.clone()
+            ObjectExpression(exp)=> exp.loc.clone(),
+            NewExpression(exp)=> exp.loc.clone(),
+            ThisExpression(exp)=> exp.loc.clone(),
+            FunctionExpression(exp)=> exp.loc.clone(),
+            ArrowFunctionExpression(exp)=> exp.loc.clone(),
+            ArrayExpression(exp)=> exp.loc.clone(),
+            Unsupported(node) => node.loc.clone(),
+        }
+    }
+
+    /// The estree node type, used to report unsupported expressions
+    pub fn type_name(&self) -> &str {
+        match self {
+            BinaryExpression(_) => "BinaryExpression",
+            UnaryExpression(_) => "UnaryExpression",
+            NumericLiteral(_) => "NumericLiteral",
+            StringLiteral(_) => "StringLiteral",
+            BooleanLiteral(_) => "BooleanLiteral",
+            NullLiteral(_) => "NullLiteral",
+            Identifier(_) => "Identifier",
+            UpdateExpression(_) => "UpdateExpression",
+            CallExpression(_) => "CallExpression",
+            AssignmentExpression(_) => "AssignmentExpression",
+            LogicalExpression(_) => "LogicalExpression",
+            MemberExpression(_) => "MemberExpression",
+            ObjectExpression(_) => "ObjectExpression",
+            NewExpression(_) => "NewExpression",
+            ThisExpression(_) => "ThisExpression",
+            FunctionExpression(_) => "FunctionExpression",
+            ArrowFunctionExpression(_) => "ArrowFunctionExpression",
+            ArrayExpression(_) => "ArrayExpression",
+            Unsupported(node) => &node.node_type,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,74 @@
+use crate::ast::expression::*;
+use crate::ast::statement::*;
+
+/// Function declarations, function expressions and arrows once their
+/// differences don't matter anymore : an arrow with an expression body gets a
+/// block returning it
+#[derive(Clone, Debug)]
+pub struct Function {
+    /// Empty for an anonymous function
+    pub name: String,
+    pub params: Vec<Id>,
+    pub body: BlockStmt,
+    pub kind: FunctionKind,
+    pub loc: Loc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionKind {
+    Declaration,
+    /// A named function expression can refer to itself by its name
+    Expression,
+    /// Arrows take `this` from the function they are created in
+    Arrow,
+}
+
+impl Function {
+    /// True if the function body sees its own name, which is bound to the
+    /// function itself
+    pub fn binds_own_name(&self) -> bool {
+        self.kind == FunctionKind::Expression && !self.name.is_empty()
+    }
+
+    pub fn has_own_this(&self) -> bool {
+        self.kind != FunctionKind::Arrow
+    }
+}
+
+impl From<&FunctionDec> for Function {
+    fn from(f: &FunctionDec) -> Function {
+        Function {
+            name: f.id.name.clone(),
+            params: f.params.clone(),
+            body: f.body.clone(),
+            kind: FunctionKind::Declaration,
+            loc: f.loc.clone(),
+        }
+    }
+}
+
+impl From<&FunctionExp> for Function {
+    fn from(f: &FunctionExp) -> Function {
+        Function {
+            name: f.id.as_ref().map_or(String::new(), |id| id.name.clone()),
+            params: f.params.clone(),
+            body: f.body.clone(),
+            kind: FunctionKind::Expression,
+            loc: f.loc.clone(),
+        }
+    }
+}
+
+impl From<&ArrowFunctionExp> for Function {
+    fn from(f: &ArrowFunctionExp) -> Function {
+        let body = match &f.body {
+            ArrowBody::Block(block) => block.clone(),
+            ArrowBody::Expression(expression) => {
+                let loc = expression.get_loc();
+                let argument = Some(expression.clone());
+                BlockStmt { body: vec![Box::new(Statement::ReturnStatement(ReturnStmt { argument, loc: loc.clone() }))], loc }
+            }
+        };
+        Function { name: String::new(), params: f.params.clone(), body, kind: FunctionKind::Arrow, loc: f.loc.clone() }
+    }
+}
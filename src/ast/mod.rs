@@ -1,9 +1,42 @@
+use serde::de::Error;
+use serde::Deserializer;
+use serde::Deserialize;
+use serde_json::Value;
+
 use crate::ast::expression::Loc;
 
 pub mod statement;
 pub mod expression;
+pub mod function;
 
 #[allow(unused)]
 pub fn with_loc(loc: &Loc) -> bool {
     true
 }
+
+/// An estree node the ast has no variant for, kept so that every unsupported
+/// construct can be reported at once instead of failing the whole file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnsupportedNode {
+    #[serde(rename = "type")]
+    pub node_type: String,
+    #[serde(skip_serializing_if = "with_loc")]
+    pub loc: Loc,
+}
+
+/// Deserialize a node with one of the `known` types with `known_node`, any other type
+/// becomes an unsupported node
+pub fn deserialize_node<'de, D, T>(deserializer: D,
+                                   known: &[&str],
+                                   known_node: fn(Value) -> Result<T, serde_json::Error>,
+                                   unsupported: fn(UnsupportedNode) -> T) -> Result<T, D::Error>
+    where D: Deserializer<'de> {
+    let node = Value::deserialize(deserializer)?;
+    let node_type = node.get("type").and_then(Value::as_str).unwrap_or("");
+
+    if known.contains(&node_type) {
+        known_node(node).map_err(D::Error::custom)
+    } else {
+        serde_json::from_value(node).map(unsupported).map_err(D::Error::custom)
+    }
+}
@@ -4,13 +4,21 @@ use crate::ast::expression::Loc;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Error;
-use std::cell::RefCell;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::ast::statement::Statement::*;
+use crate::ast::UnsupportedNode;
 
 pub trait Named {
     fn get_name(&self) -> String;
 }
+/// (De)serialized by hand so that unknown node types end up in `Unsupported`
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(tag = "type")]
+#[serde(tag = "type", remote = "Self")]
 pub enum Statement {
     BlockStatement(BlockStmt),
     ExpressionStatement(ExpressionStmt),
@@ -22,33 +30,67 @@ pub enum Statement {
     SwitchStatement(SwitchStmt),
     SwitchCase(CaseStmt),
     ForStatement(ForStmt),
+    DoWhileStatement(DoWhileStmt),
+    ForInStatement(ForEachStmt),
+    ForOfStatement(ForEachStmt),
     BreakStatement(BreakStmt),
     ContinueStatement(ContinueStmt),
+    LabeledStatement(LabeledStmt),
+    ThrowStatement(ThrowStmt),
+    TryStatement(TryStmt),
     ReturnStatement(ReturnStmt),
     EmptyStatement,
     Root(Vec<Statement>),
+    #[serde(skip)]
+    Unsupported(UnsupportedNode),
+}
+
+/// Node types with a `Statement` variant, anything else is unsupported
+const STATEMENT_TYPES: &[&'static str] = &[
+    "BlockStatement", "ExpressionStatement", "WhileStatement", "VariableDeclaration",
+    "VariableDeclarator", "FunctionDeclaration", "IfStatement", "SwitchStatement", "SwitchCase",
+    "ForStatement", "BreakStatement", "ContinueStatement", "ReturnStatement", "EmptyStatement",
+    "DoWhileStatement", "ForInStatement", "ForOfStatement", "LabeledStatement",
+    "ThrowStatement", "TryStatement",
+];
+
+impl Serialize for Statement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Unsupported(node) => node.serialize(serializer),
+            known => Statement::serialize(known, serializer),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+impl<'de> Deserialize<'de> for Statement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        super::deserialize_node(deserializer, STATEMENT_TYPES, Statement::deserialize, Unsupported)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum RootStatement {
     Program(Program),
     File(File),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct File {
     pub program: Program,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Program {
     pub body: Vec<Box<Statement>>
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BlockStmt {
-    pub body: Vec<Box<Statement>>
+    pub body: Vec<Box<Statement>>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -85,7 +127,7 @@ pub struct IfStmt {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ForStmt {
-    pub init: Option<Box<Expression>>,
+    pub init: Option<ForInit>,
     pub test: Option<Box<Expression>>,
     pub update: Option<Box<Expression>>,
     pub body: Box<Statement>,
@@ -93,6 +135,42 @@ pub struct ForStmt {
     pub loc: Loc,
 }
 
+/// `init` of a `for` and `left` of `for..in` and `for..of`, `var i = 0` or `i = 0`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ForInit {
+    Declaration(VariableDec),
+    Expression(Box<Expression>),
+}
+
+/// Serialized as the estree node, the declaration is a `VariableDeclaration`
+impl Serialize for ForInit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ForInit::Declaration(declaration) => VariableDeclaration(declaration.clone()).serialize(serializer),
+            ForInit::Expression(expression) => expression.serialize(serializer),
+        }
+    }
+}
+
+/// `for (left in right)` iterates the keys of `right`, `for (left of right)` its items
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ForEachStmt {
+    pub left: ForInit,
+    pub right: Box<Expression>,
+    pub body: Box<Statement>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DoWhileStmt {
+    pub body: Box<Statement>,
+    pub test: Box<Expression>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct WhileStmt {
     pub test: Box<Expression>,
@@ -108,6 +186,41 @@ pub struct BreakStmt {
     pub loc: Loc,
 }
 
+/// `label: body`, the label can be used by `break` and `continue` inside the body
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LabeledStmt {
+    pub label: Id,
+    pub body: Box<Statement>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ThrowStmt {
+    pub argument: Box<Expression>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+/// `try {} catch (e) {} finally {}`, at least one of `handler` and `finalizer` is there
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TryStmt {
+    pub block: BlockStmt,
+    pub handler: Option<CatchClause>,
+    pub finalizer: Option<BlockStmt>,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
+/// `catch (e) {}`, the parameter can be left out
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatchClause {
+    pub param: Option<Id>,
+    pub body: BlockStmt,
+    #[serde(skip_serializing_if = "super::with_loc")]
+    pub loc: Loc,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ReturnStmt {
     pub argument: Option<Box<Expression>>,
@@ -146,12 +259,6 @@ pub struct Variable {
     pub init: Option<Box<Expression>>,
     #[serde(skip_serializing_if = "super::with_loc")]
     pub loc: Loc,
-    #[serde(default = "delay")]
-    pub delayed:  RefCell<bool>,
-}
-
-pub fn delay() -> RefCell<bool> {
-    RefCell::new(true)
 }
 
 impl ToString for Variable {
@@ -161,6 +268,13 @@ impl ToString for Variable {
 }
 
 impl RootStatement {
+    pub fn into_program(self) -> Program {
+        match self {
+            RootStatement::Program(p) => p,
+            RootStatement::File(f) => f.program,
+        }
+    }
+
     pub fn get_program_root(&self) -> Option<Vec<Box<Statement>>> {
         match self {
             RootStatement::Program(p) => Some( p.body.to_owned()),
@@ -169,6 +283,41 @@ impl RootStatement {
     }
 }
 
+impl Statement {
+    /// Statements a `continue` can go back to
+    pub fn is_loop(&self) -> bool {
+        matches!(self, WhileStatement(_) | DoWhileStatement(_) | ForStatement(_) | ForInStatement(_) | ForOfStatement(_))
+    }
+
+    /// The estree node type, used to report unsupported statements
+    pub fn type_name(&self) -> &str {
+        match self {
+            BlockStatement(_) => "BlockStatement",
+            ExpressionStatement(_) => "ExpressionStatement",
+            WhileStatement(_) => "WhileStatement",
+            VariableDeclaration(_) => "VariableDeclaration",
+            VariableDeclarator(_) => "VariableDeclarator",
+            FunctionDeclaration(_) => "FunctionDeclaration",
+            IfStatement(_) => "IfStatement",
+            SwitchStatement(_) => "SwitchStatement",
+            SwitchCase(_) => "SwitchCase",
+            ForStatement(_) => "ForStatement",
+            DoWhileStatement(_) => "DoWhileStatement",
+            ForInStatement(_) => "ForInStatement",
+            ForOfStatement(_) => "ForOfStatement",
+            BreakStatement(_) => "BreakStatement",
+            ContinueStatement(_) => "ContinueStatement",
+            LabeledStatement(_) => "LabeledStatement",
+            ThrowStatement(_) => "ThrowStatement",
+            TryStatement(_) => "TryStatement",
+            ReturnStatement(_) => "ReturnStatement",
+            EmptyStatement => "EmptyStatement",
+            Root(_) => "Root",
+            Unsupported(node) => &node.node_type,
+        }
+    }
+}
+
 impl Display for Loc {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         write!(f, "start: {}:{}, end: {}:{}",
@@ -5,17 +5,18 @@ use crate::ast::statement::Statement::*;
 use crate::c_compile::*;
 use crate::c_compile::c_write_utils::*;
 use crate::c_compile::c_writer::CWriter;
+use crate::c_compile::c_writer::JumpTarget;
+use crate::c_compile::closure::lexicals;
+use crate::ast::function::Function;
 use crate::visitor::Visitor;
-use std::cell::RefCell;
-use rand::Rng;
-use rand::distributions::Alphanumeric;
-use rand::thread_rng;
 use crate::writer::*;
+use crate::error::CompileError;
+use crate::error::CompileResult;
 
 impl<'pr> Visitor for  CWriter<'pr> {
 
     // statement
-    fn visit_statement(&mut self, s: &Statement) {
+    fn visit_statement(&mut self, s: &Statement) -> CompileResult<()> {
         match s {
             BlockStatement(block) => self.visit_block_statement(block),
             VariableDeclaration(var) => self.visit_variable_declaration(var),
@@ -24,303 +25,621 @@ impl<'pr> Visitor for  CWriter<'pr> {
             IfStatement(if_stmt) => self.visit_if_statement(if_stmt),
             SwitchStatement(sw_stmt) => self.visit_switch_statement(sw_stmt),
             ForStatement(for_stmt) => self.visit_for_statement(for_stmt),
+            DoWhileStatement(do_while) => self.visit_do_while_statement(do_while),
+            ForInStatement(for_in) => self.visit_for_in_statement(for_in),
+            ForOfStatement(for_of) => self.visit_for_of_statement(for_of),
             BreakStatement(break_stmt) => self.visit_break_statement(break_stmt),
             ContinueStatement(ctn_stmt) => self.visit_continue_statement(ctn_stmt),
+            LabeledStatement(labeled) => self.visit_labeled_statement(labeled),
+            ThrowStatement(throw) => self.visit_throw_statement(throw),
+            TryStatement(try_stmt) => self.visit_try_statement(try_stmt),
             ReturnStatement(ret_stmt) => self.visit_return_statement(ret_stmt),
             SwitchCase(case) => self.visit_case(case),
-            _ => (),
-        };
+            VariableDeclarator(v) => self.visit_variable_declarator(v),
+            // written before `main`, or created with the closures of the enclosing function
+            FunctionDeclaration(_) | EmptyStatement => Ok(()),
+            Root(statements) => statements.iter().try_for_each(|statement| self.visit_statement(statement)),
+            Statement::Unsupported(node) => {
+                Err(CompileError::unsupported(&format!("`{}` in the C backend", node.node_type), &node.loc))
+            }
+        }
     }
 
 
-    fn visit_block_statement(&mut self, s: &BlockStmt) {
-        s.body.iter().for_each(|statement| self.visit_statement(statement))
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()> {
+        self.append_fresh_cells(&lexicals(&s.body));
+        for statement in s.body.iter() {
+            self.visit_statement(statement)?;
+        }
+        Ok(())
     }
 
-    fn visit_variable_declarator(&mut self, v: &Variable) {
-
-        if !*v.delayed.borrow() {
-            if let Some(box init) = &v.init {
-                self.append(&v.to_string());
-                self.append(EQ);
-                &self.append_ref_as_databox(init, v.id.name.clone());
-            };
-        } else {
-            self.append(DATABOX);
-            self.append(&v.to_string());
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()> {
+        // variables are declared when entering their function, or are globals,
+        // the declaration assigns them
+        let variable = self.variable(&v.id.name);
+        self.append(&variable);
+        self.append(EQ);
+        match &v.init {
+            Some(init) => self.append_ref_as_databox(init)?,
+            None => self.append(NEW_UNDEFINED),
         }
-
         self.append(SEMI_COL);
-        *v.delayed.borrow_mut() = false;
+        Ok(())
     }
 
-    fn visit_while_statement(&mut self, w: &WhileStmt) {
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()> {
         self.append(WHILE);
         self.append(PARENTHESIS_LEFT);
-        self.visit_expression(&w.test);
+        self.visit_test(&w.test)?;
         self.append(PARENTHESIS_RIGHT);
         self.append(BRACKET_LEFT);
-        self.visit_statement(&w.body);
+        self.visit_loop_body(&w.body)?;
         self.append(BRACKET_RIGHT);
+        Ok(())
     }
 
-    fn visit_variable_declaration(&mut self, v: &VariableDec) {
-        v.declarations.iter().for_each(|declaration| {
+    /// `var x;` keeps the value `x` already has, `let x;` resets it
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()> {
+        for declaration in v.declarations.iter() {
             if let box VariableDeclarator(declarator) = declaration {
-                self.visit_variable_declarator(&declarator);
+                if declarator.init.is_none() && v.kind == "var" {
+                    continue;
+                }
+                self.visit_variable_declarator(declarator)?;
                 self.append(NEW_LINE);
             }
-        });
+        }
+        Ok(())
     }
 
-    fn visit_if_statement(&mut self, i: &IfStmt) {
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()> {
         self.append(IF);
         self.append(PARENTHESIS_LEFT);
-        self.visit_expression(&i.test);
+        self.visit_test(&i.test)?;
         self.append(PARENTHESIS_RIGHT);
         self.append(BRACKET_LEFT);
-        self.visit_statement(&i.consequent);
+        self.visit_statement(&i.consequent)?;
         self.append(BRACKET_RIGHT);
         if let Some(alternate) = &i.alternate {
             self.append(ELSE);
             self.append(BRACKET_LEFT);
-            self.visit_statement(alternate);
+            self.visit_statement(alternate)?;
             self.append(BRACKET_RIGHT);
         }
+        Ok(())
     }
 
-    fn visit_switch_statement(&mut self, s: &SwitchStmt) {
-        self.append(SWITCH);
+    /// A C switch can't dispatch on a databox nor on a non constant case, the
+    /// switch is lowered to a chain of strict equality tests jumping to the
+    /// case labels, which keeps javascript fallthrough :
+    ///
+    /// ```c
+    /// {databox rjsc_switch_0=v;
+    /// if(data_strict_eq_data(rjsc_switch_0,new(12.0)))goto rjsc_switch_0_case_0;
+    /// goto rjsc_switch_0_case_1;
+    /// rjsc_switch_0_case_0:;return new(0.0);
+    /// rjsc_switch_0_case_1:;return new(3.0);
+    /// rjsc_switch_0_end:;}
+    /// ```
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()> {
+        let switch = self.new_label(SWITCH);
+        let case_label = |i: usize| format!("{}_case_{}", switch, i);
+        let end_label = format!("{}_end", switch);
+
         self.append(BRACKET_LEFT);
-        self.visit_expression(&s.discriminant);
-        &s.cases.iter().for_each(|case| {
-            self.visit_case(case);
-        });
+        self.append(DATABOX);
+        self.append(&switch);
+        self.append(EQ);
+        self.visit_value(&s.discriminant)?;
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+        let case_lexicals: Vec<String> = s.cases.iter()
+            .flat_map(|case| lexicals(&case.consequent))
+            .collect();
+        self.append_fresh_cells(&case_lexicals);
+
+        for (i, case) in s.cases.iter().enumerate() {
+            if let Some(test) = &case.test {
+                self.append(IF);
+                self.append(PARENTHESIS_LEFT);
+                self.append(STD_STRICT_EQ);
+                self.append(PARENTHESIS_LEFT);
+                self.append(&switch);
+                self.append(COMA);
+                self.visit_value(test)?;
+                self.append(PARENTHESIS_RIGHT);
+                self.append(PARENTHESIS_RIGHT);
+                self.append_goto(&case_label(i));
+            }
+        }
+
+        // default is only taken once every case test failed, wherever it is
+        let default = s.cases.iter().position(|case| case.test.is_none());
+        self.append_goto(&default.map_or(end_label.clone(), case_label));
+
+        self.jumps.push(JumpTarget { end: Some(end_label.clone()), breakable: true, ..JumpTarget::default() });
+        for (i, case) in s.cases.iter().enumerate() {
+            self.append_label(&case_label(i));
+            self.visit_case(case)?;
+        }
+        self.jumps.pop();
+
+        self.append_label(&end_label);
         self.append(BRACKET_RIGHT);
+        Ok(())
     }
 
-    fn visit_case(&mut self, case: &CaseStmt) {
-        self.append(CASE);
-        self.append(COL);
-        self.append(" ");
-        if let Some(test) = &case.test {
-            self.visit_expression(test);
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()> {
+        for consequent in case.consequent.iter() {
+            self.visit_statement(consequent)?;
         }
-        self.append(NEW_LINE);
-        &case.consequent.iter().for_each(|consequent| {
-            self.visit_statement(consequent);
-        });
-        self.append(BREAK);
-        self.append(SEMI_COL);
+        Ok(())
     }
 
-    fn visit_for_statement(&mut self, f: &ForStmt) {
-        self.visit_option_expression(&f.init);
-        self.append(SEMI_COL);
-        self.append(FOR);
-        self.append(PARENTHESIS_LEFT);
+    /// A declared loop variable lives in a C block around the loop
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()> {
+        match &f.init {
+            Some(ForInit::Declaration(declaration)) => {
+                self.append_fresh_cells(&lexicals(&[Box::new(VariableDeclaration(declaration.clone()))]));
+                self.visit_variable_declaration(declaration)?;
+            }
+            Some(ForInit::Expression(expression)) => {
+                self.visit_expression(expression)?;
+                self.append(SEMI_COL);
+            }
+            None => self.append(SEMI_COL),
+        }
+        self.append_for(f)
+    }
 
-        self.append(SEMI_COL);
-        self.visit_option_expression(&f.test);
-        self.append(SEMI_COL);
-        self.visit_option_expression(&f.update);
-        self.append(PARENTHESIS_RIGHT);
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()> {
+        self.append(DO);
         self.append(BRACKET_LEFT);
-        self.append(NEW_LINE);
-        self.visit_statement(&f.body);
+        self.visit_loop_body(&d.body)?;
         self.append(BRACKET_RIGHT);
+        self.append(WHILE);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_test(&d.test)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(SEMI_COL);
+        Ok(())
     }
 
-    fn visit_break_statement(&mut self, f: &BreakStmt) {
-        self.append(BREAK);
-        self.append(SEMI_COL);
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        self.append_for_each(f, FOR_IN, FOR_IN_KEYS)
     }
 
-    fn visit_return_statement(&mut self, r: &ReturnStmt) {
-        self.append(RETURN);
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        self.append_for_each(f, FOR_OF, FOR_OF_ITEMS)
+    }
 
-        match &r.argument {
-            Some(box NumericLiteral(_)) |
-            Some(box StringLiteral(_)) => {
-                self.append(NEW);
-                self.append(PARENTHESIS_LEFT);
-                self.visit_option_expression(&r.argument);
-                self.append(PARENTHESIS_RIGHT);
+    fn visit_break_statement(&mut self, f: &BreakStmt) -> CompileResult<()> {
+        let target = self.jump_target(&f.label, false)?;
+        self.append_unwind(target.map_or(self.jumps.len(), |i| i + 1))?;
+        match target.and_then(|i| self.jumps[i].end.clone()) {
+            Some(end_label) => self.append_goto(&end_label),
+            None => {
+                self.append(BREAK);
+                self.append(SEMI_COL);
             }
-            Some(box Identifier(id)) => self.append(&id.name),
-            _ => self.visit_option_expression(&r.argument),
-        };
-        self.append(SEMI_COL);
+        }
+        Ok(())
     }
 
-    fn visit_continue_statement(&mut self, c: &ContinueStmt) {
-        self.append(CONTINUE);
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()> {
+        // the value is computed before the finally blocks of the try statements left
+        if self.jumps.iter().any(|target| target.unwind.is_some()) {
+            self.append(BRACKET_LEFT);
+            self.append(DATABOX);
+            self.append(RETURNED);
+            self.append(EQ);
+            match &r.argument {
+                Some(argument) => self.visit_value(argument)?,
+                None => self.append(NEW_UNDEFINED),
+            }
+            self.append(SEMI_COL);
+            self.append_unwind(0)?;
+            self.append(RETURN);
+            self.append(RETURNED);
+            self.append(SEMI_COL);
+            self.append(BRACKET_RIGHT);
+            return Ok(());
+        }
+
+        self.append(RETURN);
+        match &r.argument {
+            Some(argument) => self.visit_value(argument)?,
+            None => self.append(NEW_UNDEFINED),
+        }
         self.append(SEMI_COL);
+        Ok(())
     }
 
-    fn visit_function_declaration(&mut self, f: &FunctionDec) {
-        self.append(&f.id.to_string());
-        self.append(PARENTHESIS_LEFT);
-        f.params.iter().for_each(|param| {
-            self.append(DATABOX);
-            self.append(param.name.as_str());
-        });
+    /// An unlabeled `continue` is a plain C one, lowered switches aren't C loops
+    fn visit_continue_statement(&mut self, c: &ContinueStmt) -> CompileResult<()> {
+        let target = self.jump_target(&c.label, true)?;
+        self.append_unwind(target.map_or(self.jumps.len(), |i| i + 1))?;
+        match (&c.label, target.and_then(|i| self.jumps[i].next.clone())) {
+            (None, _) => {
+                self.append(CONTINUE);
+                self.append(SEMI_COL);
+                Ok(())
+            }
+            (Some(_), Some(next_label)) => {
+                self.append_goto(&next_label);
+                Ok(())
+            }
+            (Some(label), None) => Err(CompileError::undefined_label(label, true)),
+        }
+    }
 
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()> {
+        self.append(THROW_VALUE);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_value(&t.argument)?;
         self.append(PARENTHESIS_RIGHT);
-        self.append(BRACKET_LEFT);
-        self.append(NEW_LINE);
-        self.visit_block_statement(&f.body);
-        self.append(BRACKET_RIGHT);
+        self.append(SEMI_COL);
+        Ok(())
+    }
+
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()> {
+        self.append_try(t)
     }
 
-    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) {
-        if let Some(expression) = exp {
-            self.visit_expression(&expression);
+    /// `a: b: body` is written once with both labels, the end label is after the
+    /// body and a loop body ends with the label of `continue` :
+    ///
+    /// ```c
+    /// while(test){body rjsc_label_a_0_next:;}rjsc_label_a_0_end:;
+    /// ```
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()> {
+        let mut labels = vec![l.label.name.clone()];
+        let mut body = &l.body;
+        while let box LabeledStatement(inner) = body {
+            labels.push(inner.label.name.clone());
+            body = &inner.body;
         }
+        let label = self.new_label(&format!("label_{}", l.label.name));
+        let end = format!("{}_end", label);
+
+        if body.is_loop() {
+            let next = Some(format!("{}_next", label));
+            self.labeled_loop = Some(JumpTarget { labels, end: Some(end.clone()), next, breakable: true, is_loop: true, unwind: None });
+            self.visit_statement(body)?;
+        } else {
+            self.jumps.push(JumpTarget { labels, end: Some(end.clone()), ..JumpTarget::default() });
+            let result = self.visit_statement(body);
+            self.jumps.pop();
+            result?;
+        }
+        self.append_label(&end);
+        Ok(())
+    }
+
+    fn visit_function_declaration(&mut self, _f: &FunctionDec) -> CompileResult<()> {
+        Ok(())
     }
 
-    fn visit_expression(&mut self, exp: &Expression) {
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()> {
         match exp {
-            NumericLiteral(ref n) => self.append(&n.to_string()),
-            StringLiteral(ref s) => self.append(&s.to_string()),
-            UpdateExpression(ref u) => self.visit_update_expression(u),
-            BinaryExpression(ref b) => self.visit_binary_expression(b),
-            UnaryExpression(ref u) => self.visit_unary_expression(u),
-            MemberExpression(ref m) => self.visit_member_expression(m),
-            CallExpression(ref c) => self.visit_call_expression(c),
-            AssignmentExpression(ref e) => self.visit_assign(e),
-            LogicalExpression(ref l) => self.visit_logical_expression(l),
-            _ => (),
-        };
+            Some(expression) => self.visit_expression(expression),
+            None => Ok(()),
+        }
     }
 
-    fn visit_expression_statement(&mut self, s: &ExpressionStmt) {
-        self.visit_expression(&s.expression);
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()> {
+        match exp {
+            NumericLiteral(ref n) => self.append(&number_to_c(n.value)),
+            StringLiteral(ref s) => self.append(&format!("new({})", string_to_c(&s.value))),
+            BooleanLiteral(ref b) => self.append(if b.value { NEW_TRUE } else { NEW_FALSE }),
+            NullLiteral(_) => self.append(NEW_NULL),
+            Identifier(ref id) => self.append_identifier(id),
+            UpdateExpression(ref u) => self.visit_update_expression(u)?,
+            BinaryExpression(ref b) => self.visit_binary_expression(b)?,
+            UnaryExpression(ref u) => self.visit_unary_expression(u)?,
+            MemberExpression(ref m) => self.visit_member_expression(m)?,
+            CallExpression(ref c) => self.visit_call_expression(c)?,
+            AssignmentExpression(ref e) => self.visit_assign(e)?,
+            LogicalExpression(ref l) => self.visit_logical_expression(l)?,
+            NewExpression(ref n) => self.visit_new_expression(n)?,
+            ThisExpression(_) => {
+                // `this` of the top level code
+                let this = self.closures.last().and_then(|closure| closure.reference(THIS));
+                self.append(&this.unwrap_or(NEW_UNDEFINED.to_string()));
+            }
+            FunctionExpression(ref f) => self.visit_function_expression(f)?,
+            ArrowFunctionExpression(ref f) => self.visit_arrow_function_expression(f)?,
+            ArrayExpression(ref a) => self.visit_array_expression(a)?,
+            // `({databox rjsc_object_literal=new_object();set_property(rjsc_object_literal,"a",new(1.0));rjsc_object_literal;})`
+            ObjectExpression(ref o) => {
+                self.append(STATEMENT_EXPRESSION_LEFT);
+                self.append(DATABOX);
+                self.append(OBJECT_LITERAL);
+                self.append(EQ);
+                self.append(NEW_DICT);
+                self.append(SEMI_COL);
+                self.visit_object_expression(o, OBJECT_LITERAL.to_string())?;
+                self.append(OBJECT_LITERAL);
+                self.append(SEMI_COL);
+                self.append(STATEMENT_EXPRESSION_RIGHT);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// A `print` whose value isn't used is the runtime macro, see `append_print`
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()> {
+        match &s.expression {
+            box CallExpression(c) if c.is_print() => self.append_print(&c.arguments)?,
+            expression => self.visit_expression(expression)?,
+        }
         self.append(SEMI_COL);
+        Ok(())
     }
 
-    fn visit_binary_expression(&mut self, b: &BinaryExp) {
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()> {
         let mut option_left = &b.left.try_as_identifier();
         let mut option_right = &b.right.try_as_identifier();
         let mut temp_expression = String::new();
         if b.has_parenthesis() { self.append(PARENTHESIS_LEFT); }
 
-        if b.has_idendifier(option_left, option_right) {
-            self.append(bin_op_to_c(&b.operator));
+        // the runtime converts the operands with the javascript rules, literals are boxed too
+        if let Some(function) = boxed_bin_op_to_c(&b.operator) {
+            self.append(function);
+            self.append(PARENTHESIS_LEFT);
+            self.visit_value(&b.left)?;
+            self.append(COMA);
+            self.visit_value(&b.right)?;
+            self.append(PARENTHESIS_RIGHT);
+        } else if b.has_idendifier(option_left, option_right) || !(b.left.is_c_number() && b.right.is_c_number()) {
+            let left_is_number = option_left.is_none() && b.left.is_c_number();
+            let right_is_number = option_right.is_none() && b.right.is_c_number();
+            self.append(&bin_op_to_c(&b.operator, left_is_number, right_is_number, &b.loc)?);
             self.append(PARENTHESIS_LEFT);
 
-            self.append_option_identifier_or_visit_expression(option_left, &b.left);
+            self.append_operand(option_left, &b.left)?;
             self.append(COMA);
-            self.append_option_identifier_or_visit_expression(option_right, &b.right);
+            self.append_operand(option_right, &b.right)?;
 
             self.append(PARENTHESIS_RIGHT);
         } else {
-            self.visit_expression(&b.left);
+            self.visit_expression(&b.left)?;
             self.append(&b.operator);
-            self.visit_expression(&b.right);
+            self.visit_expression(&b.right)?;
         }
         if b.has_parenthesis() { self.append(PARENTHESIS_RIGHT); }
+        Ok(())
     }
 
 
     /// if the assignment operator is equal,  use the new macro from std
     /// else generate a binary expression from the assignment and visit it
-    fn visit_assign(&mut self, a: &AssignmentExp) {
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()> {
         if let box Identifier(id) = &a.left {
-            let identifier = &id.to_string();
-            self.append(&id.name);
+            let variable = self.variable(&id.name);
+            self.append(&variable);
             self.append(EQ);
             if &a.operator != EQ {
                 let assign_bin_op = assign_to_c(id.clone(),
                                                 &a.operator,
                                                 a.right.clone(),
-                                                a.loc.clone());
-                self.visit_binary_expression(&assign_bin_op);
+                                                a.loc.clone())?;
+                self.visit_binary_expression(&assign_bin_op)
             } else {
-                self.append(NEW);
+                self.visit_value(&a.right)
+            }
+        } else if let box MemberExpression(m) = &a.left {
+            self.visit_property_assign(m, a)
+        } else {
+            let feature = format!("assignment to `{}` in the C backend", a.left.type_name());
+            Err(CompileError::unsupported(&feature, &a.left.get_loc()))
+        }
+    }
+
+    /// `typeof x` gives `type_of(x)`, `~x` gives `data_bit_not(x)`, `+x` gives
+    /// `new_from_double(data_to_number(x))` and `void x` gives `(x,new_undefined())`
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()> {
+        let function = match u.operator.as_str() {
+            // the identifiers `scope::resolve` found no declaration for are undefined
+            "typeof" if u.argument.try_as_identifier().is_some_and(|id| id.binding.is_none()) => {
+                self.append(&format!("{}(\"undefined\")", NEW));
+                return Ok(());
+            }
+            "typeof" => TYPE_OF,
+            "~" => STD_BIT_NOT,
+            MINUS if !u.argument.is_numeric_literal() => NEGATE,
+            "+" => {
+                self.append(&format!("{}({}(", NEW_FROM_DOUBLE, TO_NUMBER));
+                self.visit_value(&u.argument)?;
+                self.append("))");
+                return Ok(());
+            }
+            "void" => {
                 self.append(PARENTHESIS_LEFT);
-                self.visit_expression(&a.right);
+                self.visit_value(&u.argument)?;
+                self.append(COMA);
+                self.append(NEW_UNDEFINED);
                 self.append(PARENTHESIS_RIGHT);
+                return Ok(());
             }
+            _ => "",
+        };
+        if !function.is_empty() {
+            self.append(function);
+            self.append(PARENTHESIS_LEFT);
+            self.visit_value(&u.argument)?;
+            self.append(PARENTHESIS_RIGHT);
+            return Ok(());
         }
-    }
 
-    fn visit_unary_expression(&mut self, u: &UnaryExp) {
         self.append(u.operator.as_str());
-        self.visit_expression(&u.argument);
+        if u.operator == NOT {
+            self.visit_test(&u.argument)
+        } else {
+            self.visit_expression(&u.argument)
+        }
     }
 
-    fn visit_update_expression(&mut self, u: &UpdateExp) {
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()> {
         let arg = &u.argument.try_as_identifier();
         if let Some(idendifier) = *arg {
-            let update_c_string = update_to_c(&u.operator, &idendifier.to_string());
-            self.append(update_c_string
-                .expect("unable to parse update expression").as_str());
+            let variable = self.variable(&idendifier.name);
+            self.visit_variable_update(&variable, u)
+        } else if let box MemberExpression(m) = &u.argument {
+            self.visit_property_update(m, u)
+        } else {
+            let feature = format!("update of `{}` in the C backend", u.argument.type_name());
+            Err(CompileError::unsupported(&feature, &u.argument.get_loc()))
         }
     }
 
-    fn visit_member_expression(&mut self, m: &MemberExp) {
-        self.visit_expression(&m.object);
-        self.visit_expression(&m.property);
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()> {
+        if m.is_console_log() {
+            self.append(&format!("{}(\"log\",{},{})", NEW_FUNCTION, BUILTIN_PRINT, NULL));
+            return Ok(());
+        }
+        self.append(GET_INDEX);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_value(&m.object)?;
+        self.append(COMA);
+        self.append_property_key(m)?;
+        self.append(PARENTHESIS_RIGHT);
+        Ok(())
     }
 
-    fn visit_logical_expression(&mut self, l: &LogicalExp) {
-        self.visit_expression(&l.left);
-        self.append(l.operator.as_str());
-        self.visit_expression(&l.right);
+    /// The operand the expression evaluates to is returned as is, the right one is
+    /// only evaluated if needed : `a || b` becomes
+    /// `({databox rjsc_logical_operand=a; to_bool(rjsc_logical_operand) ? rjsc_logical_operand : b;})`
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()> {
+        let left_test = match l.operator.as_str() {
+            "||" => format!("{}({})", TO_BOOL, LOGICAL_OPERAND),
+            "&&" => format!("!{}({})", TO_BOOL, LOGICAL_OPERAND),
+            "??" => format!("!{}({})", IS_NULLISH, LOGICAL_OPERAND),
+            other => return Err(CompileError::unknown_operator(other, &l.loc)),
+        };
+
+        self.append(STATEMENT_EXPRESSION_LEFT);
+        self.append(DATABOX);
+        self.append(LOGICAL_OPERAND);
+        self.append(EQ);
+        self.visit_value(&l.left)?;
+        self.append(SEMI_COL);
+        self.append(&left_test);
+        self.append(QUESTION_MARK);
+        self.append(LOGICAL_OPERAND);
+        self.append(COL);
+        self.visit_value(&l.right)?;
+        self.append(SEMI_COL);
+        self.append(STATEMENT_EXPRESSION_RIGHT);
+        Ok(())
     }
 
-    fn visit_call_expression(&mut self, e: &CallExp) {
-        let mut standard_lib_call = false;
-        if let box Identifier(id) = &e.callee {
-            if STD_LIB.contains(&id.name.as_str()) {
-                standard_lib_call = true;
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()> {
+        match &e.callee {
+            // the runtime macro has no value
+            _ if e.is_print() => {
+                self.append(BUILTIN_PRINT);
+                self.append(PARENTHESIS_LEFT);
+                self.append(NULL);
+                self.append(COMA);
+                self.append(NEW_UNDEFINED);
+                self.append(COMA);
+                self.append_arguments(&e.arguments)?;
+                self.append(PARENTHESIS_RIGHT);
+                Ok(())
             }
-            self.append(&id.to_string());
+            callee => self.append_call(callee, NEW_UNDEFINED, &e.arguments),
         }
+    }
 
+    /// The constructor is called with a fresh object remembering it as `this` :
+    /// `({databox rjsc_new_this=new_instance(F);construct_result(rjsc_new_this,F(NULL,rjsc_new_this,argc,argv));})`,
+    /// a constructor which isn't an identifier is first stored in `rjsc_new_callee`
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()> {
+        self.append(STATEMENT_EXPRESSION_LEFT);
+        let callee = match &n.callee {
+            callee @ box Identifier(_) => callee.clone(),
+            callee => {
+                self.append(DATABOX);
+                self.append(NEW_CALLEE);
+                self.append(EQ);
+                self.visit_value(callee)?;
+                self.append(SEMI_COL);
+                Box::new(Identifier(Id { name: NEW_CALLEE.to_string(), loc: callee.get_loc(), binding: None }))
+            }
+        };
+        self.append(DATABOX);
+        self.append(NEW_THIS);
+        self.append(EQ);
+        self.append(NEW_INSTANCE);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_value(&callee)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(SEMI_COL);
+        self.append(CONSTRUCT_RESULT);
         self.append(PARENTHESIS_LEFT);
-        let last = e.arguments.len() - 1;
+        self.append(NEW_THIS);
+        self.append(COMA);
+        self.append_call(&callee, NEW_THIS, &n.arguments)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(SEMI_COL);
+        self.append(STATEMENT_EXPRESSION_RIGHT);
+        Ok(())
+    }
 
-        e.arguments.iter().enumerate().for_each(|(i, expression)| {
-            if !standard_lib_call {
-                self.append(NEW);
-                self.append(PARENTHESIS_LEFT);
-            }
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()> {
+        self.append_new_function(&Function::from(f))
+    }
 
-            if let box Identifier(id) = expression {
-                self.append(&id.to_string());
-            }
-            self.visit_expression(expression);
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()> {
+        self.append_new_function(&Function::from(f))
+    }
 
-            if i != last { self.append(COMA); };
-            if !standard_lib_call { self.append(PARENTHESIS_RIGHT); }
-        });
+    /// `[1, , 3]` becomes `new_array(3,(databox[]){new(1.0),new_undefined(),new(3.0)})`
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()> {
+        self.append(NEW_ARRAY);
+        self.append(PARENTHESIS_LEFT);
+        self.append(&a.elements.len().to_string());
+        self.append(COMA);
+        if a.elements.is_empty() {
+            self.append(NULL);
+        } else {
+            self.append(ARGV);
+            self.append(BRACKET_LEFT);
+            for (i, element) in a.elements.iter().enumerate() {
+                if i > 0 { self.append(COMA); }
+                match element {
+                    Some(element) => self.visit_value(element)?,
+                    None => self.append(NEW_UNDEFINED),
+                }
+            }
+            self.append(BRACKET_RIGHT);
+        }
         self.append(PARENTHESIS_RIGHT);
+        Ok(())
     }
 
-    fn visit_object_expression(&mut self, e: &ObjectExp, id: String) {
-        e.properties.iter().for_each(|prop| {
-            self.visit_property_expression( &id, prop);
-        })
+    fn visit_object_expression(&mut self, e: &ObjectExp, id: String) -> CompileResult<()> {
+        for prop in e.properties.iter() {
+            self.visit_property_expression(&id, prop)?;
+        }
+        Ok(())
     }
 
-    fn visit_property_expression(&mut self, id: &str, p: &Property) {
-        self.append(DATABOX);
-        let warning = format!("Object property at {} key must be a string literal", &p.key.get_loc());
-        let prop_id = &p.key.try_as_string_from_identifier().or(p.key.try_as_string_from_lit());
-        let prop_id = &prop_id.clone().expect(&warning);
-
-        // generate a random variable name for object properties so we can use databoxes everywhere.
-        let mut rstr: String = thread_rng().sample_iter(&Alphanumeric).take(7).collect();
-        rstr.insert(0, 'A');
-        self.append(&rstr);
-        self.append(EQ);
-        self.append_ref_as_databox(&p.value, id.to_string());
+    fn visit_property_expression(&mut self, id: &str, p: &Property) -> CompileResult<()> {
+        let prop_id = p.key.try_as_string_from_identifier().or(p.key.try_as_string_from_lit());
+        let prop_id = &prop_id.ok_or_else(|| CompileError::InvalidPropertyKey { loc: p.key.get_loc() })?;
+        self.append(SET_PROPERTY);
+        self.append(PARENTHESIS_LEFT);
+        self.append(id);
+        self.append(COMA);
+        self.append(&string_to_c(prop_id));
+        self.append(COMA);
+        self.visit_value(&p.value)?;
+        self.append(PARENTHESIS_RIGHT);
         self.append(SEMI_COL);
-        self.append(NEW_LINE);
-
-        self.append(&format!("dictionary_add({}.data.dict, \"{}\" , &{});", id, prop_id, rstr));
-        self.append(NEW_LINE);
+        Ok(())
     }
 }
\ No newline at end of file
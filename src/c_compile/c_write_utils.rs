@@ -7,6 +7,8 @@ use crate::c_compile::c_write_utils::FunctionReserved::*;
 use crate::ast::expression::BinaryExp;
 use crate::ast::expression::Id;
 use crate::ast::expression::Loc;
+use crate::error::CompileError;
+use crate::error::CompileResult;
 use super::*;
 
 pub enum FunctionReserved {
@@ -20,67 +22,124 @@ pub enum FunctionReserved {
     NotEq,
 }
 
-impl From<String> for FunctionReserved {
-    fn from(function_id: String) -> Self {
-        let id_as_str = function_id.as_str();
-        match id_as_str {
-            STD_PRINT => Print,
-            MAIN => Main,
-            STD_ADD => Add,
-            STD_MUL => Mull,
-            STD_GT => GreaterThan,
-            STD_LT => LessThan,
-            STD_EQ => Eq,
-            STD_NEQ => NotEq,
-            _ => panic!("error")
+impl FunctionReserved {
+    /// The runtime function named `function_id`, called at `loc`
+    pub fn new(function_id: &str, loc: &Loc) -> CompileResult<Self> {
+        match function_id {
+            STD_PRINT => Ok(Print),
+            MAIN => Ok(Main),
+            STD_ADD => Ok(Add),
+            STD_MUL => Ok(Mull),
+            STD_GT => Ok(GreaterThan),
+            STD_LT => Ok(LessThan),
+            STD_EQ => Ok(Eq),
+            STD_NEQ => Ok(NotEq),
+            _ => Err(CompileError::unsupported(&format!("`{}` as a runtime function", function_id), loc)),
         }
     }
 }
 
-pub fn bin_op_to_c(js_string: &str) -> &str {
+/// The runtime function of `+`, `-`, `*`, `<` and `>` for the C types of their
+/// operands, `data_add_double` for a databox plus a C number. They are called
+/// directly rather than through the generic macros of `databox.h`, which
+/// repeat their arguments and would double the size of the C with each
+/// nested operation
+pub fn bin_op_to_c(js_string: &str, left_is_number: bool, right_is_number: bool, loc: &Loc) -> CompileResult<String> {
+    let operation = match js_string {
+        ">" => "greater_than",
+        "<" => "less_than",
+        "+" => "add",
+        "*" => "mul",
+        "-" => "sub",
+        _ => return Err(CompileError::unknown_operator(js_string, loc)),
+    };
+    let operand = |is_number| if is_number { "double" } else { "data" };
+    Ok(format!("{}_{}_{}", operand(left_is_number), operation, operand(right_is_number)))
+}
+
+/// The runtime function of the operators whose operands are always boxed, the
+/// runtime converts them with the javascript rules
+pub fn boxed_bin_op_to_c(js_string: &str) -> Option<&'static str> {
     match js_string {
-        ">" => STD_GT,
-        "<" => STD_LT,
-        "==" => STD_EQ,
-        "!=" => STD_NEQ,
-        "+" => STD_ADD,
-        "*" => STD_MUL,
-        "/" => STD_DIV,
-        "-" => STD_SUB,
-        "||" => "||",
-        _ => {
-            print!("{}", js_string);
+        "==" => Some(STD_EQ),
+        "!=" => Some(STD_NEQ),
+        "===" => Some(STD_STRICT_EQ),
+        "!==" => Some(STD_STRICT_NEQ),
+        "<=" => Some(STD_LESS_EQ),
+        ">=" => Some(STD_GREATER_EQ),
+        "/" => Some(STD_DIV),
+        "%" => Some(STD_MOD),
+        "**" => Some(STD_EXP),
+        "&" => Some(STD_BIT_AND),
+        "|" => Some(STD_BIT_OR),
+        "^" => Some(STD_BIT_XOR),
+        "<<" => Some(STD_SHIFT_LEFT),
+        ">>" => Some(STD_SHIFT_RIGHT),
+        ">>>" => Some(STD_UNSIGNED_SHIFT_RIGHT),
+        "in" => Some(STD_IN),
+        "instanceof" => Some(STD_INSTANCEOF),
+        _ => None,
+    }
+}
+
+/// A C double literal of `value`, `1.0` rather than `1` so the C arithmetic on
+/// literals doesn't overflow like int arithmetic does
+pub fn number_to_c(value: f64) -> String {
+    format!("{:?}", value)
+}
 
-            panic!("Unkown javascript token")
+/// A quoted C literal of `value`, quotes, backslashes and control characters
+/// are escaped
+pub fn string_to_c(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // octal escapes stop after three digits, unlike the hexadecimal ones
+            c if c.is_control() && c.is_ascii() => literal.push_str(&format!("\\{:03o}", c as u32)),
+            c => literal.push(c),
         }
     }
+    literal.push('"');
+    literal
 }
 
 /// Generate a binary expression from special assign operators
-pub fn assign_to_c(identifier: Id, js_op: &str, right: Box<Expression>, loc: Loc) -> BinaryExp {
+pub fn assign_to_c(identifier: Id, js_op: &str, right: Box<Expression>, loc: Loc) -> CompileResult<BinaryExp> {
     let operator = match js_op {
         "-=" => "-",
         "+=" => "+",
         "*=" => "*",
         "/=" => "/",
         "%=" => "%",
-        _ => panic!("Unknown assignment operator")
+        "**=" => "**",
+        "<<=" => "<<",
+        ">>=" => ">>",
+        ">>>=" => ">>>",
+        "&=" => "&",
+        "|=" => "|",
+        "^=" => "^",
+        _ => return Err(CompileError::unknown_operator(js_op, &loc)),
     };
 
-    BinaryExp {
-        left: box Identifier(identifier),
+    Ok(BinaryExp {
+        left: Box::new(Identifier(identifier)),
         operator: operator.to_string(),
         extra: None,
         right,
         loc
-    }
+    })
 }
 
-pub fn update_to_c(js_string: &str, var: &str) -> Result<String, io::Error> {
+pub fn update_to_c(js_string: &str, var: &str, loc: &Loc) -> CompileResult<String> {
     match js_string {
         "++" => Ok(format!("increment(&{})", var)),
         "--" => Ok(format!("decrement(&{})", var)),
-        _ => unimplemented!()
+        _ => Err(CompileError::unknown_operator(js_string, loc)),
     }
 }
 
@@ -4,12 +4,61 @@ use crate::ast::expression::*;
 use crate::ast::expression::Expression::*;
 use crate::ast::statement::Statement::*;
 use crate::ast::statement::Statement;
+use crate::ast::statement::*;
+use crate::ast::function::Function;
+use crate::ast::function::FunctionKind;
 use crate::c_compile::*;
+use crate::c_compile::closure;
+use crate::c_compile::c_write_utils::*;
+use crate::c_compile::closure::Closure;
 use crate::visitor::Visitor;
 use crate::writer::*;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+use crate::diagnostic::Diagnostic;
 
 pub struct CWriter<'printer> {
     pub out: &'printer mut String,
+    /// Constructs that compile but may not behave like javascript
+    pub warnings: Vec<Diagnostic>,
+    /// Number of labels generated so far, keeps their names unique
+    pub labels: usize,
+    /// The statements a `break` or `continue` can leave, innermost last
+    pub jumps: Vec<JumpTarget>,
+    /// The target of a labeled loop, taken by the loop when its body is written
+    pub labeled_loop: Option<JumpTarget>,
+    /// The functions being written, innermost last, `this` is only bound inside them
+    pub closures: Vec<Closure>,
+    /// Top level functions, they are C functions callable directly
+    pub functions: Vec<String>,
+    /// Top level `let` and `const` that functions use, heap cells like captured
+    /// variables, see `Closure::global_cells`
+    pub global_cells: Vec<String>,
+}
+
+/// A loop, a switch or a labeled statement, in the C code `break` and `continue`
+/// become a `goto` when a plain C `break` or `continue` wouldn't land at the same place
+#[derive(Default)]
+pub struct JumpTarget {
+    /// The javascript labels naming the statement
+    pub labels: Vec<String>,
+    /// The C label right after the statement, `None` for a plain C `break`
+    pub end: Option<String>,
+    /// The C label at the end of a loop body, where a labeled `continue` goes
+    pub next: Option<String>,
+    /// Whether an unlabeled `break` can leave it, labeled blocks are only left by a labeled one
+    pub breakable: bool,
+    /// Whether an unlabeled `continue` goes back to it
+    pub is_loop: bool,
+    /// Set for a `try`, which can't be a target but has to be left properly
+    pub unwind: Option<Unwind>,
+}
+
+/// What leaving a `try` statement with a jump or a `return` takes
+pub struct Unwind {
+    /// Whether the handler of the `try` is registered, it's gone once the exception is caught
+    pub handler: bool,
+    pub finalizer: Option<BlockStmt>,
 }
 
 impl <'pr> CWriter <'pr> {
@@ -17,77 +66,805 @@ impl <'pr> CWriter <'pr> {
         true
     }
 
-    pub fn visit_program_root(&mut self, root_nodes: Vec<Box<Statement>>) {
+    pub fn visit_program_root(&mut self, root_nodes: Vec<Box<Statement>>) -> CompileResult<()> {
         self.append(INCLUDES);
-        let vars_gen = self.visit_global_vars(root_nodes);
-        let func_gen = self.visit_function_declarations(vars_gen);
-        let main_gen = self.visit_calls(func_gen);
+        let declarations = Closure::declarations(&root_nodes)?;
+        for function in declarations.iter() {
+            self.functions.push(function.name.clone());
+            self.append_prototypes(function, None)?;
+        }
+        let expressions = Closure::program(&root_nodes)?;
+        for function in expressions.iter() {
+            self.append_prototypes(function, None)?;
+        }
+        let globals = Closure::globals(&root_nodes)?;
+        self.global_cells = Closure::global_cells(&root_nodes)?;
+        self.visit_global_vars(&globals);
+        for name in self.functions.clone().iter() {
+            self.append(&format!("{}{}{}{};", STATIC, DATABOX, FUNCTION_VALUE, name));
+            self.append(NEW_LINE);
+        }
+        for function in declarations.iter().chain(expressions.iter()) {
+            self.write_function(function)?;
+        }
+        self.visit_calls(&root_nodes, &globals)?;
         self.append(NEW_LINE);
         self.append(BRACKET_RIGHT);
+        Ok(())
     }
 
-    fn visit_calls(&mut self, nodes: Vec<Box<Statement>>) -> Vec<Box<Statement>> {
+    /// The top level code becomes `main`, which starts with every global undefined
+    /// and creates the one function value of each top level function
+    fn visit_calls(&mut self, nodes: &[Box<Statement>], globals: &[String]) -> CompileResult<()> {
         self.append(MAIN);
-        let mut new_root = nodes.clone();
-        nodes.iter().enumerate().for_each(|(i, statement)| {
-            self.visit_statement(statement);
-        });
+        for name in globals.iter() {
+            if self.is_cell(name) {
+                self.append(&format!("{}={}({});", name, NEW_CELL, NEW_UNDEFINED));
+            } else {
+                self.append(&format!("{}={};", name, NEW_UNDEFINED));
+            }
+            self.append(NEW_LINE);
+        }
+        for name in self.functions.clone().iter() {
+            self.append(&format!("{}{}={}(\"{}\",{},{});", FUNCTION_VALUE, name, NEW_FUNCTION, name, name, NULL));
+            self.append(NEW_LINE);
+        }
+        for statement in nodes.iter() {
+            self.visit_statement(statement)?;
+        }
         self.append(END);
-        new_root
+        Ok(())
     }
 
-    fn visit_global_vars(&mut self, nodes: Vec<Box<Statement>>) -> Vec<Box<Statement>> {
-        let mut new_root = nodes.clone();
-        new_root.iter().enumerate().for_each(|(i, statement)| {
-            if let box VariableDeclaration(var) = statement {
-                self.visit_variable_declaration(var);
+    /// Every variable of the top level code is a C global, including the ones
+    /// declared in blocks and loops
+    fn visit_global_vars(&mut self, globals: &[String]) {
+        for name in globals.iter() {
+            self.append(STATIC);
+            self.append(DATABOX);
+            if self.is_cell(name) {
+                self.append("*");
             }
-        });
-        new_root
+            self.append(name);
+            self.append(SEMI_COL);
+            self.append(NEW_LINE);
+        }
     }
 
-    fn visit_function_declarations(&mut self, nodes: Vec<Box<Statement>>) -> Vec<Box<Statement>> {
-        let mut new_root = nodes.clone();
-        nodes.iter().enumerate().for_each(|(i, statement)| {
-            if let box FunctionDeclaration(function) = statement {
-                new_root.remove(i);
-                self.append(DATABOX);
-                self.visit_function_declaration(function);
-            };
-        });
-        new_root
+    /// Nested functions are written before the function declaring them, so
+    /// every function is declared upfront
+    fn append_prototypes(&mut self, f: &Function, parent: Option<&Closure>) -> CompileResult<()> {
+        let c_name = closure::c_name(f, parent);
+        self.append(STATIC);
+        self.append(DATABOX);
+        self.append(&c_name);
+        self.append(FUNCTION_PARAMS);
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+        let closure = Closure::new(f, c_name, None)?;
+        for nested in closure.nested.iter().chain(closure.expressions.iter()) {
+            self.append_prototypes(nested, Some(&closure))?;
+        }
+        Ok(())
+    }
+
+    /// Every function takes its environment, `this` and its arguments, see
+    /// `FUNCTION_PARAMS`. Nested functions are written first, then the cells of the
+    /// captured variables and the closures of the nested functions are created
+    /// before the body runs
+    pub fn write_function(&mut self, f: &Function) -> CompileResult<()> {
+        let top_level;
+        let parent = match self.closures.last() {
+            None if f.kind != FunctionKind::Declaration => {
+                top_level = Closure::top_level(&self.global_cells);
+                Some(&top_level)
+            }
+            parent => parent,
+        };
+        let c_name = closure::c_name(f, parent);
+        let closure = Closure::new(f, c_name.clone(), parent)?;
+        let nested = closure.nested.clone();
+        let expressions = closure.expressions.clone();
+        let captured = closure.captured.clone();
+        let declared = closure.declared.clone();
+        let self_cell = closure.env.len();
+        self.closures.push(closure);
+        let jumps = std::mem::take(&mut self.jumps);
+
+        for function in nested.iter().chain(expressions.iter()) {
+            self.write_function(function)?;
+        }
+
+        self.append(STATIC);
+        self.append(DATABOX);
+        self.append(&c_name);
+        self.append(FUNCTION_PARAMS);
+        self.append(BRACKET_LEFT);
+        self.append(NEW_LINE);
+        let is_param = |name: &str| f.params.iter().any(|param| param.name == name);
+        for (i, param) in f.params.iter().enumerate() {
+            // of two parameters with the same name, the last one gets the argument
+            if f.params[i + 1..].iter().any(|other| other.name == param.name) {
+                continue;
+            }
+            let argument = format!("{}({},{},{})", NTH_ARG, ARGC, ARGV_PARAM, i);
+            self.append_local(&param.name, &argument, captured.contains(&param.name));
+        }
+        // the cell after the captured variables holds the function, see `append_new_function`
+        if f.binds_own_name() && !is_param(&f.name) {
+            let itself = format!("(*{}[{}])", ENV, self_cell);
+            self.append_local(&f.name, &itself, captured.contains(&f.name));
+        }
+        // the other variables are undefined until their declaration runs, the
+        // nested functions are declared along with their closure
+        let is_nested = |name: &str| nested.iter().any(|function| function.name == name);
+        for name in declared.iter().filter(|name| !is_param(name) && !(f.binds_own_name() && **name == f.name)) {
+            let captured = captured.contains(name);
+            if name == THIS {
+                if captured {
+                    self.append_local(name, THIS, true);
+                }
+            } else if captured || !is_nested(name) {
+                self.append_local(name, NEW_UNDEFINED, captured);
+            }
+        }
+        for function in nested.iter() {
+            self.append_closure(function)?;
+        }
+
+        // the cells of the variables of the body were just created
+        for statement in f.body.body.iter() {
+            self.visit_statement(statement)?;
+        }
+        // falling off the end of a javascript function returns undefined
+        self.append(RETURN);
+        self.append(NEW_UNDEFINED);
+        self.append(SEMI_COL);
+        self.append(BRACKET_RIGHT);
+        self.append(NEW_LINE);
+        self.jumps = jumps;
+        self.closures.pop();
+        Ok(())
+    }
+
+    /// Declare a local initialized with `value`, in a heap cell if it's captured
+    fn append_local(&mut self, name: &str, value: &str, captured: bool) {
+        if captured {
+            self.append(&format!("{}*{}={}({});", DATABOX, closure::cell_name(name), NEW_CELL, value));
+        } else {
+            self.append(&format!("{}{}={};", DATABOX, name, value));
+        }
+        self.append(NEW_LINE);
+    }
+
+    /// Create the closure of a nested function declaration
+    pub fn append_closure(&mut self, f: &Function) -> CompileResult<()> {
+        let parent = self.closures.last()
+            .ok_or_else(|| CompileError::unsupported("a nested function declaration outside of a function", &f.loc))?;
+        let target = if parent.is_captured(&f.name) {
+            format!("(*{})", f.name)
+        } else {
+            format!("{}{}", DATABOX, f.name)
+        };
+
+        self.append(&target);
+        self.append(EQ);
+        self.append_new_function(f)?;
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+        Ok(())
+    }
+
+    /// `new_function(name,code,env)` of a function created by the innermost
+    /// function being written, with the cells it captures. A function expression
+    /// seeing its own name gets one more cell holding the function itself,
+    /// `new_named_function(name,code,env,self)` fills it
+    pub fn append_new_function(&mut self, f: &Function) -> CompileResult<()> {
+        // the functions the top level code creates capture its cells, its
+        // declarations are C functions using the globals
+        let top_level = Closure::top_level(&self.global_cells);
+        let parent = self.closures.last().or(Some(&top_level));
+        let c_name = closure::c_name(f, parent);
+        let closure = Closure::new(f, c_name.clone(), parent)?;
+        let mut cells: Vec<String> = match parent {
+            Some(parent) => closure.env.iter().map(|name| parent.cell(name)).collect(),
+            None => vec![],
+        };
+
+        if f.binds_own_name() {
+            let self_cell = cells.len();
+            cells.push(format!("{}({})", NEW_CELL, NEW_UNDEFINED));
+            self.append(&format!("{}(\"{}\",{},", NEW_NAMED_FUNCTION, f.name, c_name));
+            self.append(&format!("{}({},{}),{}", NEW_ENV, cells.len(), cells.join(COMA), self_cell));
+        } else {
+            self.append(&format!("{}(\"{}\",{},", NEW_FUNCTION, f.name, c_name));
+            if cells.is_empty() {
+                self.append(NULL);
+            } else {
+                self.append(&format!("{}({},{})", NEW_ENV, cells.len(), cells.join(COMA)));
+            }
+        }
+        self.append(PARENTHESIS_RIGHT);
+        Ok(())
+    }
+
+    /// `argc,argv` of a call
+    pub fn append_arguments(&mut self, arguments: &[Box<Expression>]) -> CompileResult<()> {
+        self.append(&arguments.len().to_string());
+        self.append(COMA);
+        if arguments.is_empty() {
+            self.append(NULL);
+            return Ok(());
+        }
+
+        self.append(ARGV);
+        self.append(BRACKET_LEFT);
+        for (i, argument) in arguments.iter().enumerate() {
+            if i > 0 { self.append(COMA); }
+            self.visit_value(argument)?;
+        }
+        self.append(BRACKET_RIGHT);
+        Ok(())
+    }
+
+    /// Call the function `callee` evaluates to with `this`, top level functions
+    /// are called directly and anything else through the runtime
+    pub fn append_call(&mut self, callee: &Expression, this: &str, arguments: &[Box<Expression>]) -> CompileResult<()> {
+        match callee {
+            Identifier(id) if self.is_top_level_function(id) => {
+                self.append(&id.name);
+                self.append(PARENTHESIS_LEFT);
+                self.append(NULL);
+            }
+            // a method call, `this` is the object
+            MemberExpression(m) if !m.computed && this == NEW_UNDEFINED => {
+                self.append(CALL_METHOD);
+                self.append(PARENTHESIS_LEFT);
+                self.visit_value(&m.object)?;
+                self.append(COMA);
+                self.append(&string_to_c(&self.property_name(m)?));
+                self.append(COMA);
+                self.append_arguments(arguments)?;
+                self.append(PARENTHESIS_RIGHT);
+                return Ok(());
+            }
+            _ => {
+                self.append(CALL_FUNCTION);
+                self.append(PARENTHESIS_LEFT);
+                self.visit_value(callee)?;
+            }
+        }
+        self.append(COMA);
+        self.append(this);
+        self.append(COMA);
+        self.append_arguments(arguments)?;
+        self.append(PARENTHESIS_RIGHT);
+        Ok(())
+    }
+
+    /// True if `id` refers to a top level function
+    pub fn is_top_level_function(&self, id: &Id) -> bool {
+        id.binding == Some(Binding::Global) && self.functions.contains(&id.name)
+    }
+
+    /// The C lvalue of a javascript variable
+    pub fn variable(&self, name: &str) -> String {
+        self.closures.last()
+            .and_then(|closure| closure.reference(name))
+            .unwrap_or_else(|| if self.global_cells.iter().any(|cell| cell == name) {
+                format!("(*{})", name)
+            } else {
+                name.to_string()
+            })
+    }
+
+    /// True if the variable `name` of the code being written lives in a heap cell
+    pub fn is_cell(&self, name: &str) -> bool {
+        match self.closures.last() {
+            Some(closure) => closure.is_captured(name),
+            None => self.global_cells.iter().any(|cell| cell == name),
+        }
+    }
+
+    /// `x=new_cell(new_undefined())` for the variables among `names` living in
+    /// cells : entering a block or starting an iteration of a loop creates new
+    /// `let` and `const`, the functions created before keep the old ones. A `for`
+    /// loop variable is copied to its new cell, `x=new_cell(*x)`, like the
+    /// javascript CreatePerIterationEnvironment does
+    pub fn fresh_cells(&self, names: &[String], copied: bool) -> Vec<String> {
+        names.iter().filter(|name| self.is_cell(name)).map(|name| {
+            let cell = closure::cell_name(name);
+            let value = if copied { format!("*{}", cell) } else { NEW_UNDEFINED.to_string() };
+            format!("{}={}({})", cell, NEW_CELL, value)
+        }).collect()
     }
 
-    pub fn append_ref_as_databox(&mut self, init: &Expression, id: String) {
+    pub fn append_fresh_cells(&mut self, names: &[String]) {
+        for cell in self.fresh_cells(names, false) {
+            self.append(&cell);
+            self.append(SEMI_COL);
+        }
+    }
+
+    pub fn append_ref_as_databox(&mut self, init: &Expression) -> CompileResult<()> {
         match init {
             StringLiteral(ref s) => {
-                let as_databox = format!("new(\"{}\")", &s.to_string());
+                let as_databox = format!("new({})", string_to_c(&s.value));
                 self.append(&as_databox);
             },
             NumericLiteral(n) => {
-                let as_databox = format!("new({})", &n.to_string());
+                let as_databox = format!("new({})", number_to_c(n.value));
                 self.append(&as_databox);
             },
-            ObjectExpression(ref o) => {
-                self.append(NEW_DICT);
-                self.append(SEMI_COL);
-                self.append(NEW_LINE);
-                self.visit_object_expression(&o, id);
-            }
             _ => {
-                self.visit_expression(init);
+                self.visit_expression(init)?;
             }
         }
+        Ok(())
+    }
+
+    /// An argument of the runtime functions, C truth values are boxed as
+    /// booleans so `print(a < b)` writes `true`
+    pub fn append_std_argument(&mut self, expression: &Expression) -> CompileResult<()> {
+        if expression.is_c_condition() {
+            self.visit_value(expression)
+        } else {
+            self.visit_expression(expression)
+        }
     }
 
-    pub fn append_option_identifier_or_visit_expression(&mut self, identifier: &Option<&Id>, expression: &Expression) {
+    /// `print_all(2,(databox[]){a,b})` writes the values and ends the line
+    pub fn append_print(&mut self, arguments: &[Box<Expression>]) -> CompileResult<()> {
+        self.append(STD_PRINT_ALL);
+        self.append(PARENTHESIS_LEFT);
+        self.append_arguments(arguments)?;
+        self.append(PARENTHESIS_RIGHT);
+        Ok(())
+    }
+
+    /// An operand of the typed operator functions, a C number when
+    /// `is_c_number` says so and a databox otherwise
+    pub fn append_operand(&mut self, identifier: &Option<&Id>, expression: &Expression) -> CompileResult<()> {
         if let Some(id) = identifier {
-            self.append(&id.to_string());
+            self.append_identifier(id);
+            Ok(())
+        } else if expression.is_c_number() {
+            self.visit_expression(expression)
+        } else {
+            self.visit_value(expression)
+        }
+    }
+
+    /// Visit an expression used as a condition, databoxes are converted with
+    /// the javascript truthiness rules
+    pub fn visit_test(&mut self, test: &Expression) -> CompileResult<()> {
+        if test.is_c_condition() {
+            self.visit_expression(test)
         } else {
-            self.visit_expression(expression);
+            self.append(TO_BOOL);
+            self.append(PARENTHESIS_LEFT);
+            self.visit_expression(test)?;
+            self.append(PARENTHESIS_RIGHT);
+            Ok(())
+        }
+    }
+
+    /// Visit an expression whose result must be a databox, C truth values
+    /// become javascript booleans and C numbers are boxed. The other
+    /// expressions already are databoxes and aren't wrapped in the generic
+    /// `new` macro, which repeats its argument and would double the size of
+    /// the C each time the expressions nest
+    pub fn visit_value(&mut self, exp: &Expression) -> CompileResult<()> {
+        match exp {
+            NumericLiteral(_) | StringLiteral(_) => self.append_ref_as_databox(exp),
+            _ if exp.is_c_condition() || exp.is_c_number() => {
+                self.append(if exp.is_c_condition() { NEW_BOOL } else { NEW_FROM_DOUBLE });
+                self.append(PARENTHESIS_LEFT);
+                self.visit_expression(exp)?;
+                self.append(PARENTHESIS_RIGHT);
+                Ok(())
+            }
+            _ => self.visit_expression(exp),
+        }
+    }
+
+    /// The name of `object.name`
+    pub fn property_name(&self, m: &MemberExp) -> CompileResult<String> {
+        match (&m.property, m.computed) {
+            (box Identifier(id), false) => Ok(id.name.clone()),
+            _ => Err(CompileError::InvalidPropertyKey { loc: m.property.get_loc() }),
+        }
+    }
+
+    /// The key of a member expression as a databox, `a[i]` gives `i` and
+    /// `a.length` gives `"length"`
+    pub fn append_property_key(&mut self, m: &MemberExp) -> CompileResult<()> {
+        if m.computed {
+            self.visit_value(&m.property)
+        } else {
+            let name = self.property_name(m)?;
+            self.append(&format!("new({})", string_to_c(&name)));
+            Ok(())
+        }
+    }
+
+    /// `object.key = value` goes through the runtime `set_property` and
+    /// `object[key] = value` through `set_index`, `object.key += value` becomes
+    /// `({databox rjsc_member_object=..;databox rjsc_member_key=..;databox rjsc_member_value=get_index(..);set_index(..,data_add_data(rjsc_member_value,value));})`
+    pub fn visit_property_assign(&mut self, m: &MemberExp, a: &AssignmentExp) -> CompileResult<()> {
+        if a.operator != EQ {
+            let member_value = Id { name: MEMBER_VALUE.to_string(), loc: m.loc.clone(), binding: None };
+            let operation = assign_to_c(member_value, &a.operator, a.right.clone(), a.loc.clone())?;
+            self.append_member_read(m)?;
+            self.append_member_write(|writer| {
+                writer.visit_value(&BinaryExpression(operation))
+            })?;
+            self.append(STATEMENT_EXPRESSION_RIGHT);
+            return Ok(());
+        }
+        if m.computed {
+            self.append(SET_INDEX);
+            self.append(PARENTHESIS_LEFT);
+            self.visit_value(&m.object)?;
+            self.append(COMA);
+            self.visit_value(&m.property)?;
+            self.append(COMA);
+            self.visit_value(&a.right)?;
+            self.append(PARENTHESIS_RIGHT);
+            return Ok(());
+        }
+        let key = self.property_name(m)?;
+
+        self.append(SET_PROPERTY);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_value(&m.object)?;
+        self.append(COMA);
+        self.append(&string_to_c(&key));
+        self.append(COMA);
+        self.visit_value(&a.right)?;
+        self.append(PARENTHESIS_RIGHT);
+        Ok(())
+    }
+
+    /// `object[key]++` evaluates to the value before the update, `++object[key]` to the one after
+    pub fn visit_property_update(&mut self, m: &MemberExp, u: &UpdateExp) -> CompileResult<()> {
+        self.append_member_read(m)?;
+        if !u.prefix {
+            self.append_update_old(MEMBER_VALUE);
+        }
+        self.append(&update_to_c(&u.operator, MEMBER_VALUE, &u.loc)?);
+        self.append(SEMI_COL);
+        self.append_member_write(|writer| {
+            writer.append(MEMBER_VALUE);
+            Ok(())
+        })?;
+        if !u.prefix {
+            self.append(UPDATE_OLD);
+            self.append(SEMI_COL);
+        }
+        self.append(STATEMENT_EXPRESSION_RIGHT);
+        Ok(())
+    }
+
+    /// `++x` is the value the runtime gives back, `x++` is the number `x` was :
+    /// `({databox rjsc_update_old=new_from_double(data_to_number(x));increment(&x);rjsc_update_old;})`
+    pub fn visit_variable_update(&mut self, variable: &str, u: &UpdateExp) -> CompileResult<()> {
+        let update = update_to_c(&u.operator, variable, &u.loc)?;
+        if u.prefix {
+            self.append(&update);
+            return Ok(());
+        }
+        self.append(STATEMENT_EXPRESSION_LEFT);
+        self.append_update_old(variable);
+        self.append(&update);
+        self.append(SEMI_COL);
+        self.append(UPDATE_OLD);
+        self.append(SEMI_COL);
+        self.append(STATEMENT_EXPRESSION_RIGHT);
+        Ok(())
+    }
+
+    fn append_update_old(&mut self, value: &str) {
+        self.append(&format!("{}{}={}({}({}));", DATABOX, UPDATE_OLD, NEW_FROM_DOUBLE, TO_NUMBER, value));
+    }
+
+    /// Opens the statement expression of an update of `object[key]`, its object,
+    /// key and current value are stored once
+    fn append_member_read(&mut self, m: &MemberExp) -> CompileResult<()> {
+        self.append(STATEMENT_EXPRESSION_LEFT);
+        self.append(DATABOX);
+        self.append(MEMBER_OBJECT);
+        self.append(EQ);
+        self.visit_value(&m.object)?;
+        self.append(SEMI_COL);
+        self.append(DATABOX);
+        self.append(MEMBER_KEY);
+        self.append(EQ);
+        self.append_property_key(m)?;
+        self.append(SEMI_COL);
+        self.append(DATABOX);
+        self.append(MEMBER_VALUE);
+        self.append(EQ);
+        self.append(&format!("{}({},{})", GET_INDEX, MEMBER_OBJECT, MEMBER_KEY));
+        self.append(SEMI_COL);
+        Ok(())
+    }
+
+    /// Stores the value `append_value` writes back in `object[key]`
+    fn append_member_write<F>(&mut self, append_value: F) -> CompileResult<()>
+        where F: FnOnce(&mut Self) -> CompileResult<()> {
+        self.append(SET_INDEX);
+        self.append(PARENTHESIS_LEFT);
+        self.append(MEMBER_OBJECT);
+        self.append(COMA);
+        self.append(MEMBER_KEY);
+        self.append(COMA);
+        append_value(self)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(SEMI_COL);
+        Ok(())
+    }
+
+    /// A generated C name starting with `prefix` which is not used anywhere else,
+    /// it also names the variables the statement declares
+    pub fn new_label(&mut self, prefix: &str) -> String {
+        let label = format!("{}{}_{}", GENERATED, prefix, self.labels);
+        self.labels += 1;
+        label
+    }
+
+    pub fn append_label(&mut self, label: &str) {
+        self.append(label);
+        self.append(COL);
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+    }
+
+    pub fn append_goto(&mut self, label: &str) {
+        self.append(GOTO);
+        self.append(label);
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+    }
+
+    /// A `break` in a loop body leaves the loop, even inside a lowered switch, a
+    /// labeled loop ends its body with the label of `continue`
+    pub fn visit_loop_body(&mut self, body: &Statement) -> CompileResult<()> {
+        let target = self.labeled_loop.take().unwrap_or(JumpTarget { breakable: true, is_loop: true, ..JumpTarget::default() });
+        let next = target.next.clone();
+        self.jumps.push(target);
+        let result = self.visit_statement(body);
+        self.jumps.pop();
+        result?;
+        if let Some(next) = next {
+            self.append_label(&next);
+        }
+        Ok(())
+    }
+
+    /// Index in `jumps` of the statement a `break` leaves or a `continue` goes back
+    /// to, the innermost breakable one or loop without label
+    pub fn jump_target(&self, label: &Option<Id>, is_continue: bool) -> CompileResult<Option<usize>> {
+        match label {
+            Some(label) => self.jumps.iter()
+                .rposition(|target| target.labels.contains(&label.name))
+                .map(Some)
+                .ok_or_else(|| CompileError::undefined_label(label, is_continue)),
+            None if is_continue => Ok(self.jumps.iter().rposition(|target| target.is_loop)),
+            None => Ok(self.jumps.iter().rposition(|target| target.breakable)),
+        }
+    }
+
+    /// Leave the `try` statements above `depth` in `jumps`, innermost first : pop
+    /// their handler if it's still registered and run their `finally` block
+    pub fn append_unwind(&mut self, depth: usize) -> CompileResult<()> {
+        for i in (depth..self.jumps.len()).rev() {
+            let (handler, finalizer) = match &self.jumps[i].unwind {
+                Some(unwind) => (unwind.handler, unwind.finalizer.clone()),
+                None => continue,
+            };
+            if handler {
+                self.append(TRY_LEAVE);
+            }
+            if let Some(finalizer) = finalizer {
+                // the jumps of the finally block are the ones around the try
+                let inner = self.jumps.split_off(i);
+                let result = self.visit_block_statement(&finalizer);
+                self.jumps.extend(inner);
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `try` registers a handler the runtime jumps back to on `throw`, the catch
+    /// block is protected too when there is a finally block to run :
+    ///
+    /// ```c
+    /// {try_handler rjsc_try_0;try_enter(&rjsc_try_0);
+    /// if(!setjmp(rjsc_try_0.jump)){block try_leave();}
+    /// else if(!setjmp(rjsc_try_0.jump)){try_enter(&rjsc_try_0);databox e=caught_value();catch try_leave();}
+    /// else{databox rjsc_try_0_error=caught_value();finally throw_value(rjsc_try_0_error);}
+    /// finally}
+    /// ```
+    pub fn append_try(&mut self, t: &TryStmt) -> CompileResult<()> {
+        let handler = self.new_label(TRY);
+        let enter = format!("{}(&{});", TRY_ENTER, handler);
+        let setjmp = format!("{}(!{}({}.jump))", IF, SETJMP, handler);
+
+        self.append(BRACKET_LEFT);
+        self.append(TRY_HANDLER);
+        self.append(&handler);
+        self.append(SEMI_COL);
+        self.append(&enter);
+        self.append(NEW_LINE);
+        self.append(&setjmp);
+        self.append(BRACKET_LEFT);
+        self.append_protected(&t.block, &t.finalizer)?;
+        self.append(BRACKET_RIGHT);
+
+        if let Some(catch) = &t.handler {
+            self.append(ELSE);
+            self.append(" ");
+            if t.finalizer.is_some() {
+                self.append(&setjmp);
+                self.append(BRACKET_LEFT);
+                self.append(&enter);
+            } else {
+                self.append(BRACKET_LEFT);
+            }
+            if let Some(param) = &catch.param {
+                self.append_fresh_cells(std::slice::from_ref(&param.name));
+                let variable = self.variable(&param.name);
+                self.append(&variable);
+                self.append(EQ);
+                self.append(CAUGHT_VALUE);
+                self.append(SEMI_COL);
+            }
+            if t.finalizer.is_some() {
+                self.append_protected(&catch.body, &t.finalizer)?;
+            } else {
+                self.visit_block_statement(&catch.body)?;
+            }
+            self.append(BRACKET_RIGHT);
+        }
+
+        if let Some(finalizer) = &t.finalizer {
+            // an exception the try statement doesn't catch goes on after the finally block
+            let error = format!("{}_error", handler);
+            self.append(ELSE);
+            self.append(BRACKET_LEFT);
+            self.append(&format!("{}{}={};", DATABOX, error, CAUGHT_VALUE));
+            self.visit_block_statement(finalizer)?;
+            self.append(&format!("{}({});", THROW_VALUE, error));
+            self.append(BRACKET_RIGHT);
+            self.append(NEW_LINE);
+            self.visit_block_statement(finalizer)?;
+        }
+        self.append(BRACKET_RIGHT);
+        Ok(())
+    }
+
+    /// A block run while the handler of its `try` is registered
+    fn append_protected(&mut self, block: &BlockStmt, finalizer: &Option<BlockStmt>) -> CompileResult<()> {
+        let unwind = Unwind { handler: true, finalizer: finalizer.clone() };
+        self.jumps.push(JumpTarget { unwind: Some(unwind), ..JumpTarget::default() });
+        let result = self.visit_block_statement(block);
+        self.jumps.pop();
+        result?;
+        self.append(TRY_LEAVE);
+        Ok(())
+    }
+    /// `for(;test;update){body}`, the init is already written
+    pub fn append_for(&mut self, f: &ForStmt) -> CompileResult<()> {
+        self.append(FOR);
+        self.append(PARENTHESIS_LEFT);
+        self.append(SEMI_COL);
+        if let Some(test) = &f.test {
+            self.visit_test(test)?;
+        }
+        self.append(SEMI_COL);
+        // each iteration gets its own loop variables before the update
+        let loop_variables = match &f.init {
+            Some(ForInit::Declaration(declaration)) => closure::lexicals(&[Box::new(VariableDeclaration(declaration.clone()))]),
+            _ => vec![],
+        };
+        for cell in self.fresh_cells(&loop_variables, true) {
+            self.append(&cell);
+            if f.update.is_some() {
+                self.append(COMA);
+            }
+        }
+        self.visit_option_expression(&f.update)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(BRACKET_LEFT);
+        self.append(NEW_LINE);
+        self.visit_loop_body(&f.body)?;
+        self.append(BRACKET_RIGHT);
+        Ok(())
+    }
+
+    /// `for..in` and `for..of` iterate an array the runtime builds, the keys
+    /// or the items of `right` :
+    ///
+    /// ```c
+    /// {databox rjsc_for_of_0=for_of_items(right);
+    /// for(int rjsc_for_of_0_index=0;rjsc_for_of_0_index<array_length(rjsc_for_of_0);rjsc_for_of_0_index++){
+    /// databox rjsc_for_of_0_item=get_index(rjsc_for_of_0,new(rjsc_for_of_0_index));x=rjsc_for_of_0_item;
+    /// body}}
+    /// ```
+    pub fn append_for_each(&mut self, f: &ForEachStmt, prefix: &str, iterated: &str) -> CompileResult<()> {
+        let array = self.new_label(prefix);
+        let index = format!("{}_index", array);
+
+        self.append(BRACKET_LEFT);
+        self.append(DATABOX);
+        self.append(&array);
+        self.append(EQ);
+        self.append(iterated);
+        self.append(PARENTHESIS_LEFT);
+        self.visit_value(&f.right)?;
+        self.append(PARENTHESIS_RIGHT);
+        self.append(SEMI_COL);
+        self.append(NEW_LINE);
+        self.append(&format!("{}(int {}=0;{}<{}({});{}++)", FOR, index, index, ARRAY_LENGTH, array, index));
+        self.append(BRACKET_LEFT);
+        let item = format!("{}_item", array);
+        self.append(DATABOX);
+        self.append(&item);
+        self.append(EQ);
+        self.append(&format!("{}({},{}({}))", GET_INDEX, array, NEW, index));
+        self.append(SEMI_COL);
+
+        // the item is assigned to the loop variable like a javascript variable would be
+        let item = Box::new(Identifier(Id { name: item, loc: f.loc.clone(), binding: None }));
+        match &f.left {
+            ForInit::Declaration(declaration) => {
+                self.append_fresh_cells(&closure::lexicals(&[Box::new(VariableDeclaration(declaration.clone()))]));
+                let mut declaration = declaration.clone();
+                for declarator in declaration.declarations.iter_mut() {
+                    if let box VariableDeclarator(v) = declarator {
+                        v.init = Some(item.clone());
+                    }
+                }
+                self.visit_variable_declaration(&declaration)?;
+            }
+            ForInit::Expression(left) => {
+                let assignment = AssignmentExp { operator: EQ.to_string(), left: left.clone(), right: item, loc: f.loc.clone() };
+                self.visit_assign(&assignment)?;
+                self.append(SEMI_COL);
+            }
+        }
+
+        self.visit_loop_body(&f.body)?;
+        self.append(BRACKET_RIGHT);
+        self.append(BRACKET_RIGHT);
+        Ok(())
+    }
+
+    /// The builtins are values of the runtime rather than C variables, and a top
+    /// level function used as a value is the global `main` creates it in
+    pub fn append_identifier(&mut self, id: &Id) {
+        let print = format!("{}(\"{}\",{},{})", NEW_FUNCTION, STD_PRINT, BUILTIN_PRINT, NULL);
+        let builtin = match id.name.as_str() {
+            "undefined" => Some(NEW_UNDEFINED),
+            "NaN" => Some(NEW_NAN),
+            "Infinity" => Some(NEW_INFINITY),
+            STD_PRINT => Some(print.as_str()),
+            "console" => Some(NEW_CONSOLE),
+            _ => None,
         };
+        if let (Some(Binding::Builtin), Some(value)) = (id.binding, builtin) {
+            self.append(value);
+        } else if self.is_top_level_function(id) {
+            self.append(&format!("{}{}", FUNCTION_VALUE, id.name));
+        } else {
+            let variable = self.variable(&id.name);
+            self.append(&variable);
+        }
     }
 
+    /// Write `word` at the end of the generated source
     pub fn append(&mut self, word: &str) {
         self.out.push_str(word)
     }
@@ -116,6 +893,56 @@ impl Expression {
             None
         }
     }
+
+    pub fn is_numeric_literal(&self) -> bool {
+        matches!(self, NumericLiteral(_))
+    }
+
+    /// True if the generated C expression is a C number rather than a databox,
+    /// numeric literals, their negation and the `+`, `-` and `*` of them
+    pub fn is_c_number(&self) -> bool {
+        match self {
+            NumericLiteral(_) => true,
+            UnaryExpression(u) => u.operator == MINUS && u.argument.is_numeric_literal(),
+            BinaryExpression(b) => ["+", "-", "*"].contains(&b.operator.as_str()) && b.left.is_c_number() && b.right.is_c_number(),
+            _ => false,
+        }
+    }
+
+    /// True if the generated C expression is already a C truth value (an int)
+    /// rather than a databox
+    pub fn is_c_condition(&self) -> bool {
+        match self {
+            BinaryExpression(b) => {
+                ["<", ">", "<=", ">=", "==", "!=", "===", "!==", "in", "instanceof"].contains(&b.operator.as_str())
+            }
+            UnaryExpression(u) => u.operator == NOT,
+            _ => false,
+        }
+    }
+}
+
+impl MemberExp {
+    /// `console.log` is the runtime `print`, unless `console` is a variable
+    pub fn is_console_log(&self) -> bool {
+        let is_console = match &self.object {
+            box Identifier(id) => id.is_builtin("console"),
+            _ => false,
+        };
+        is_console && !self.computed
+            && self.property.try_as_string_from_identifier().as_deref() == Some("log")
+    }
+}
+
+impl CallExp {
+    /// A call of `print` or `console.log`
+    pub fn is_print(&self) -> bool {
+        match &self.callee {
+            box MemberExpression(m) => m.is_console_log(),
+            box Identifier(id) => id.is_builtin(STD_PRINT),
+            _ => false,
+        }
+    }
 }
 
 trait OutlinePrint: Display {
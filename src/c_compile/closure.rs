@@ -0,0 +1,499 @@
+use crate::ast::expression::*;
+use crate::ast::expression::Expression::*;
+use crate::ast::function::Function;
+use crate::ast::function::FunctionKind;
+use crate::ast::statement::*;
+use crate::c_compile::ENV;
+use crate::c_compile::GENERATED;
+use crate::c_compile::THIS;
+use crate::c_compile::THIS_CELL;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+use crate::visitor::Visitor;
+
+/// A function compiled to C, with what it shares with the functions around it.
+///
+/// Variables captured by a nested function live in heap cells (`databox *`) so
+/// that every closure sees the same value, a closure gets the cells it uses from
+/// its enclosing functions through its environment (`databox **env`).
+#[derive(Debug)]
+pub struct Closure {
+    /// Name of the generated C function, see `c_name`
+    pub c_name: String,
+    /// `this`, parameters, variables and nested function names
+    pub declared: Vec<String>,
+    /// Declared variables that a nested function uses
+    pub captured: Vec<String>,
+    /// Variables of the enclosing functions, in the order of the environment
+    pub env: Vec<String>,
+    /// Function declarations of the body, hoisted as closures
+    pub nested: Vec<Function>,
+    /// Function expressions and arrows of the body, created where they are evaluated
+    pub expressions: Vec<Function>,
+}
+
+impl Closure {
+    pub fn new(f: &Function, c_name: String, parent: Option<&Closure>) -> CompileResult<Closure> {
+        let scan = Scan::function(f)?;
+
+        let mut captured = vec![];
+        for nested in scan.nested.iter().chain(scan.expressions.iter()) {
+            for name in free_variables(nested)? {
+                if scan.declared.contains(&name) && !captured.contains(&name) {
+                    captured.push(name);
+                }
+            }
+        }
+
+        // anything else comes from the globals
+        let env = match parent {
+            Some(parent) => free_variables(f)?.into_iter().filter(|name| parent.sees(name)).collect(),
+            None => vec![],
+        };
+
+        Ok(Closure { c_name, declared: scan.declared, captured, env, nested: scan.nested, expressions: scan.expressions })
+    }
+
+    /// The top level code as the parent of the functions it creates, its
+    /// variables in `cells` are the ones they capture, see `global_cells`
+    pub fn top_level(cells: &[String]) -> Closure {
+        Closure {
+            c_name: String::new(),
+            declared: cells.to_vec(),
+            captured: cells.to_vec(),
+            env: vec![],
+            nested: vec![],
+            expressions: vec![],
+        }
+    }
+
+    /// The functions created by the top level code, which has no variables of its own
+    pub fn program(statements: &[Box<Statement>]) -> CompileResult<Vec<Function>> {
+        let mut scan = Scan::default();
+        scan.visit_statements(statements)?;
+        Ok(scan.expressions)
+    }
+
+    /// The function declarations of the top level code, including the ones in
+    /// its blocks, they are C functions. One C function can't be two javascript
+    /// functions, a name is declared once
+    pub fn declarations(statements: &[Box<Statement>]) -> CompileResult<Vec<Function>> {
+        let mut scan = Scan::default();
+        scan.visit_statements(statements)?;
+        for (i, f) in scan.nested.iter().enumerate() {
+            if scan.nested[..i].iter().any(|declared| declared.name == f.name) {
+                let feature = format!("`{}` declared twice at the top level in the C backend", f.name);
+                return Err(CompileError::unsupported(&feature, &f.loc));
+            }
+        }
+        Ok(scan.nested)
+    }
+
+    /// Variables of the top level code, its function declarations are C functions
+    pub fn globals(statements: &[Box<Statement>]) -> CompileResult<Vec<String>> {
+        let mut scan = Scan::default();
+        scan.visit_statements(statements)?;
+        let is_function = |name: &str| scan.nested.iter().any(|f| f.name == name);
+        Ok(scan.declared.iter().filter(|name| !is_function(name)).cloned().collect())
+    }
+
+    /// The `let` and `const` of the top level code that functions use, including
+    /// the ones of its blocks and loops. Like captured variables they live in
+    /// heap cells, so that each iteration of a loop gives the functions it
+    /// creates their own variable
+    pub fn global_cells(statements: &[Box<Statement>]) -> CompileResult<Vec<String>> {
+        let mut scan = Scan::default();
+        scan.visit_statements(statements)?;
+        let mut used = vec![];
+        for function in scan.nested.iter().chain(scan.expressions.iter()) {
+            used.extend(free_variables(function)?);
+        }
+        Ok(scan.lexical.into_iter().filter(|name| used.contains(name)).collect())
+    }
+
+    /// True if `name` is a variable of this function or of the enclosing ones
+    pub fn sees(&self, name: &str) -> bool {
+        self.declared.iter().chain(self.env.iter()).any(|declared| declared == name)
+    }
+
+    pub fn is_captured(&self, name: &str) -> bool {
+        self.captured.iter().any(|captured| captured == name)
+    }
+
+    /// The C expression of a variable, `None` if it's a global
+    pub fn reference(&self, name: &str) -> Option<String> {
+        if self.is_captured(name) {
+            Some(format!("(*{})", cell_name(name)))
+        } else if self.declared.iter().any(|declared| declared == name) {
+            Some(name.to_string())
+        } else {
+            self.env.iter().position(|env| env == name).map(|i| format!("(*{}[{}])", ENV, i))
+        }
+    }
+
+    /// The C expression of the cell holding `name` for the environment of a nested function
+    pub fn cell(&self, name: &str) -> String {
+        match self.env.iter().position(|env| env == name) {
+            Some(i) if !self.declared.iter().any(|declared| declared == name) => format!("{}[{}]", ENV, i),
+            _ => cell_name(name),
+        }
+    }
+}
+
+/// The cell of a captured variable has the name of the variable, except for
+/// `this` which is already the name of a C parameter
+pub fn cell_name(name: &str) -> String {
+    if name == THIS {
+        THIS_CELL.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Name of the C function compiled from `f`, a top level declaration keeps its
+/// name, the other functions are named after their location with a generated name
+pub fn c_name(f: &Function, parent: Option<&Closure>) -> String {
+    let name = match f.kind {
+        FunctionKind::Declaration if parent.is_none() => return f.name.clone(),
+        FunctionKind::Declaration => f.name.as_str(),
+        _ => "function",
+    };
+    format!("{}{}_{}_{}", GENERATED, name, f.loc.start.line, f.loc.start.column)
+}
+
+/// Variables a function uses without declaring them, including the ones its
+/// nested functions use
+pub fn free_variables(f: &Function) -> CompileResult<Vec<String>> {
+    let scan = Scan::function(f)?;
+    let mut used = scan.referenced;
+    for nested in scan.nested.iter().chain(scan.expressions.iter()) {
+        used.extend(free_variables(nested)?);
+    }
+
+    let mut free: Vec<String> = vec![];
+    for name in used.into_iter() {
+        if !scan.declared.contains(&name) && !free.contains(&name) && name != "undefined" {
+            free.push(name);
+        }
+    }
+    Ok(free)
+}
+
+/// The `let` and `const` declared by `statements` themselves, not by the
+/// blocks in them
+pub fn lexicals(statements: &[Box<Statement>]) -> Vec<String> {
+    let mut names = vec![];
+    for statement in statements.iter() {
+        if let box Statement::VariableDeclaration(v) = statement {
+            for declarator in v.declarations.iter().filter(|_| v.kind != "var") {
+                if let box Statement::VariableDeclarator(declared) = declarator {
+                    names.push(declared.id.name.clone());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Functions created by a statement, not counting the ones nested in them
+pub fn functions_in(statement: &Statement) -> CompileResult<Vec<Function>> {
+    let mut scan = Scan::default();
+    scan.visit_statement(statement)?;
+    scan.nested.extend(scan.expressions);
+    Ok(scan.nested)
+}
+
+/// Collects the declarations and references of one function body, nested
+/// function bodies are scanned on their own
+#[derive(Default)]
+struct Scan {
+    declared: Vec<String>,
+    /// The declared `let` and `const`
+    lexical: Vec<String>,
+    referenced: Vec<String>,
+    nested: Vec<Function>,
+    expressions: Vec<Function>,
+}
+
+impl Scan {
+    fn function(f: &Function) -> CompileResult<Scan> {
+        let mut scan = Scan::default();
+        if f.has_own_this() {
+            scan.declare(THIS);
+        }
+        for param in f.params.iter() {
+            scan.declare(&param.name);
+        }
+        if f.binds_own_name() {
+            scan.declare(&f.name);
+        }
+        scan.visit_block_statement(&f.body)?;
+        Ok(scan)
+    }
+
+    fn declare(&mut self, name: &str) {
+        if !self.declared.iter().any(|declared| declared == name) {
+            self.declared.push(name.to_string());
+        }
+    }
+
+    fn visit_statements(&mut self, statements: &[Box<Statement>]) -> CompileResult<()> {
+        for statement in statements.iter() {
+            self.visit_statement(statement)?;
+        }
+        Ok(())
+    }
+}
+
+impl Visitor for Scan {
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()> {
+        self.visit_statements(&s.body)
+    }
+
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()> {
+        self.declare(&v.id.name);
+        self.visit_option_expression(&v.init)
+    }
+
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()> {
+        self.visit_expression(&w.test)?;
+        self.visit_statement(&w.body)
+    }
+
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()> {
+        for declarator in v.declarations.iter().filter(|_| v.kind != "var") {
+            if let box Statement::VariableDeclarator(declared) = declarator {
+                self.lexical.push(declared.id.name.clone());
+            }
+        }
+        self.visit_statements(&v.declarations)
+    }
+
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()> {
+        self.visit_expression(&i.test)?;
+        self.visit_statement(&i.consequent)?;
+        match &i.alternate {
+            Some(alternate) => self.visit_statement(alternate),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()> {
+        self.visit_expression(&s.discriminant)?;
+        for case in s.cases.iter() {
+            self.visit_case(case)?;
+        }
+        Ok(())
+    }
+
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()> {
+        self.visit_option_expression(&case.test)?;
+        self.visit_statements(&case.consequent)
+    }
+
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()> {
+        if let Some(init) = &f.init {
+            self.visit_for_init(init)?;
+        }
+        self.visit_option_expression(&f.test)?;
+        self.visit_option_expression(&f.update)?;
+        self.visit_statement(&f.body)
+    }
+
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()> {
+        self.visit_statement(&d.body)?;
+        self.visit_expression(&d.test)
+    }
+
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        self.visit_for_init(&f.left)?;
+        self.visit_expression(&f.right)?;
+        self.visit_statement(&f.body)
+    }
+
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        self.visit_for_in_statement(f)
+    }
+
+    fn visit_break_statement(&mut self, _f: &BreakStmt) -> CompileResult<()> {
+        Ok(())
+    }
+
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()> {
+        self.visit_option_expression(&r.argument)
+    }
+
+    fn visit_continue_statement(&mut self, _c: &ContinueStmt) -> CompileResult<()> {
+        Ok(())
+    }
+
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()> {
+        self.visit_statement(&l.body)
+    }
+
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()> {
+        self.visit_expression(&t.argument)
+    }
+
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()> {
+        self.visit_block_statement(&t.block)?;
+        if let Some(handler) = &t.handler {
+            if let Some(param) = &handler.param {
+                self.declare(&param.name);
+            }
+            self.visit_block_statement(&handler.body)?;
+        }
+        match &t.finalizer {
+            Some(finalizer) => self.visit_block_statement(finalizer),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_function_declaration(&mut self, f: &FunctionDec) -> CompileResult<()> {
+        self.declare(&f.id.name);
+        self.nested.push(Function::from(f));
+        Ok(())
+    }
+
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()> {
+        match exp {
+            Some(expression) => self.visit_expression(expression),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()> {
+        match exp {
+            Identifier(id) => {
+                self.referenced.push(id.name.clone());
+                Ok(())
+            }
+            BinaryExpression(b) => self.visit_binary_expression(b),
+            UnaryExpression(u) => self.visit_unary_expression(u),
+            UpdateExpression(u) => self.visit_update_expression(u),
+            CallExpression(c) => self.visit_call_expression(c),
+            AssignmentExpression(a) => self.visit_assign(a),
+            LogicalExpression(l) => self.visit_logical_expression(l),
+            MemberExpression(m) => self.visit_member_expression(m),
+            ObjectExpression(o) => self.visit_object_expression(o, String::new()),
+            NewExpression(n) => self.visit_new_expression(n),
+            FunctionExpression(f) => self.visit_function_expression(f),
+            ArrowFunctionExpression(f) => self.visit_arrow_function_expression(f),
+            ArrayExpression(a) => self.visit_array_expression(a),
+            ThisExpression(_) => {
+                self.referenced.push(THIS.to_string());
+                Ok(())
+            }
+            NumericLiteral(_) | StringLiteral(_) | BooleanLiteral(_) | NullLiteral(_) => Ok(()),
+            Expression::Unsupported(_) => Ok(()),
+        }
+    }
+
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()> {
+        self.visit_expression(&s.expression)
+    }
+
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()> {
+        self.visit_expression(&b.left)?;
+        self.visit_expression(&b.right)
+    }
+
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()> {
+        self.visit_expression(&a.left)?;
+        self.visit_expression(&a.right)
+    }
+
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()> {
+        self.visit_expression(&u.argument)
+    }
+
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()> {
+        self.visit_expression(&u.argument)
+    }
+
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()> {
+        self.visit_expression(&m.object)?;
+        if m.computed {
+            self.visit_expression(&m.property)?;
+        }
+        Ok(())
+    }
+
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()> {
+        self.visit_expression(&l.left)?;
+        self.visit_expression(&l.right)
+    }
+
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()> {
+        self.visit_expression(&e.callee)?;
+        for argument in e.arguments.iter() {
+            self.visit_expression(argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()> {
+        self.visit_expression(&n.callee)?;
+        for argument in n.arguments.iter() {
+            self.visit_expression(argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()> {
+        self.expressions.push(Function::from(f));
+        Ok(())
+    }
+
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()> {
+        self.expressions.push(Function::from(f));
+        Ok(())
+    }
+
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()> {
+        for element in a.elements.iter() {
+            self.visit_option_expression(element)?;
+        }
+        Ok(())
+    }
+
+    fn visit_object_expression(&mut self, o: &ObjectExp, id: String) -> CompileResult<()> {
+        for property in o.properties.iter() {
+            self.visit_property_expression(&id, property)?;
+        }
+        Ok(())
+    }
+
+    fn visit_property_expression(&mut self, _id: &str, p: &Property) -> CompileResult<()> {
+        self.visit_expression(&p.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::function::Function;
+    use crate::ast::statement::Statement::FunctionDeclaration;
+    use crate::c_compile::closure::Closure;
+    use crate::c_compile::closure::free_variables;
+    use crate::parser::parse;
+
+    #[test]
+    fn should_share_captured_variables_with_nested_functions() {
+        let source = "function externe(a) {\n  var unused = 1;\n  function interne(b) { return a + b + global; }\n  return interne;\n}";
+        let root = parse(source).unwrap().get_program_root().unwrap();
+        let externe = match &root[0] {
+            box FunctionDeclaration(f) => f,
+            other => panic!("unexpected statement {:?}", other),
+        };
+
+        let closure = Closure::new(&Function::from(externe), "externe".to_string(), None).unwrap();
+        assert_eq!(closure.declared, vec!["this", "a", "unused", "interne"]);
+        assert_eq!(closure.captured, vec!["a"]);
+        assert_eq!(free_variables(&closure.nested[0]).unwrap(), vec!["a", "global"]);
+
+        let interne = Closure::new(&closure.nested[0], "externe_interne".to_string(), Some(&closure)).unwrap();
+        assert_eq!(interne.env, vec!["a"]);
+        assert_eq!(interne.reference("a"), Some("(*rjsc_env[0])".to_string()));
+        assert_eq!(interne.reference("global"), None);
+    }
+}
@@ -1,34 +1,440 @@
 pub mod c_write_utils;
 pub mod c_writer;
 pub mod c_visitor;
+pub mod closure;
 
 pub const STD_ADD: &'static str = "add";
 pub const STD_SUB: &'static str = "sub";
 pub const STD_MUL: &'static str = "mul";
-pub const STD_DIV: &'static str = "div";
-pub const STD_EQ: &'static str = "eq";
-pub const STD_NEQ: &'static str = "neq";
+pub const STD_DIV: &'static str = "data_div_data";
+pub const STD_EQ: &'static str = "data_eq_data";
+pub const STD_NEQ: &'static str = "data_neq_data";
 pub const STD_GT: &'static str = "gt";
 pub const STD_LT: &'static str = "lt";
+pub const STD_STRICT_EQ: &'static str = "data_strict_eq_data";
+pub const STD_STRICT_NEQ: &'static str = "data_strict_neq_data";
 pub const STD_PRINT: &'static str = "print";
+pub const STD_PRINT_ALL: &'static str = "print_all";
+/// The runtime `print` as a function value, which `console.log` also is
+pub const BUILTIN_PRINT: &'static str = "builtin_print";
+pub const NEW_CONSOLE: &'static str = "new_console()";
+pub const STD_MOD: &'static str = "data_mod_data";
+pub const STD_EXP: &'static str = "data_exp_data";
+pub const STD_BIT_AND: &'static str = "data_bit_and_data";
+pub const STD_BIT_OR: &'static str = "data_bit_or_data";
+pub const STD_BIT_XOR: &'static str = "data_bit_xor_data";
+pub const STD_SHIFT_LEFT: &'static str = "data_shift_left_data";
+pub const STD_SHIFT_RIGHT: &'static str = "data_shift_right_data";
+pub const STD_UNSIGNED_SHIFT_RIGHT: &'static str = "data_unsigned_shift_right_data";
+pub const STD_LESS_EQ: &'static str = "data_less_eq_data";
+pub const STD_GREATER_EQ: &'static str = "data_greater_eq_data";
+pub const STD_IN: &'static str = "data_in_data";
+pub const STD_INSTANCEOF: &'static str = "data_instanceof_data";
+pub const STD_BIT_NOT: &'static str = "data_bit_not";
+pub const TO_NUMBER: &'static str = "data_to_number";
+pub const TYPE_OF: &'static str = "type_of";
+
+/// Estree node types the C backend compiles, see `check`
+pub const SUPPORTED_NODES: &[&'static str] = &[
+    "BlockStatement", "ExpressionStatement", "WhileStatement", "VariableDeclaration",
+    "VariableDeclarator", "FunctionDeclaration", "IfStatement", "SwitchStatement", "SwitchCase",
+    "ForStatement", "BreakStatement", "ContinueStatement", "ReturnStatement",
+    "BinaryExpression", "UnaryExpression", "NumericLiteral", "StringLiteral", "BooleanLiteral",
+    "NullLiteral", "Identifier",
+    "UpdateExpression", "CallExpression", "AssignmentExpression", "LogicalExpression",
+    "MemberExpression", "ObjectExpression", "NewExpression", "ThisExpression",
+    "FunctionExpression", "ArrowFunctionExpression", "ArrayExpression",
+    "DoWhileStatement", "ForInStatement", "ForOfStatement", "LabeledStatement",
+    "ThrowStatement", "TryStatement",
+];
+
+/// Prefix of the names the generated code declares on its own, no javascript
+/// variable is generated with it, see `is_reserved`
+pub const GENERATED: &'static str = "rjsc_";
 
-pub const STD_LIB: &[&'static str] = &["add", "mull", "div", "eq", "print", "sub"];
 pub const INCLUDES: &'static str = "   \n#include \"print.h\"\n
                                          #include \"databox.h\"\n";
 pub const MAIN: &'static str = "\nint main() {\n";
 pub const END: &'static str = "\nreturn 0;";
 
 pub const WHILE: &'static str = "while";
+pub const DO: &'static str = "do";
 pub const FOR: &'static str = "for";
+/// Prefixes of the labels naming the iterated array of `for..in` and `for..of`
+pub const FOR_IN: &'static str = "for_in";
+pub const FOR_OF: &'static str = "for_of";
+pub const FOR_IN_KEYS: &'static str = "for_in_keys";
+pub const FOR_OF_ITEMS: &'static str = "for_of_items";
+pub const ARRAY_LENGTH: &'static str = "array_length";
 pub const IF: &'static str = "if";
 pub const ELSE: &'static str = "else";
 
 pub const NEW: &'static str = "new";
+pub const NEW_FROM_DOUBLE: &'static str = "new_from_double";
 pub const NEW_DICT: &'static str = "new_object()";
-pub const SWITCH: &'static str = "switch ";
+pub const THIS: &'static str = "this";
+/// The cell of `this` when a nested arrow function captures it
+pub const THIS_CELL: &'static str = "rjsc_this_cell";
+/// Holds the object a constructor is called on
+pub const NEW_THIS: &'static str = "rjsc_new_this";
+/// Holds the constructor of `new (expression)()` so it is only evaluated once
+pub const NEW_CALLEE: &'static str = "rjsc_new_callee";
+pub const NEW_INSTANCE: &'static str = "new_instance";
+pub const CONSTRUCT_RESULT: &'static str = "construct_result";
+pub const SET_PROPERTY: &'static str = "set_property";
+pub const NEW_ARRAY: &'static str = "new_array";
+/// Reads `object[key]`, also used for the properties of arrays like `length`
+pub const GET_INDEX: &'static str = "get_index";
+pub const SET_INDEX: &'static str = "set_index";
+/// Calls `object.name(...)` with `object` as `this`, arrays methods are built in
+pub const CALL_METHOD: &'static str = "call_method";
+/// Hold the object, key and value of `object[key] += value` and `object[key]++`
+/// so the object and key are only evaluated once
+pub const MEMBER_OBJECT: &'static str = "rjsc_member_object";
+pub const MEMBER_KEY: &'static str = "rjsc_member_key";
+pub const MEMBER_VALUE: &'static str = "rjsc_member_value";
+/// Holds the value of `x++` and `object[key]++` before the update, as a number
+pub const UPDATE_OLD: &'static str = "rjsc_update_old";
+/// Holds the object an object literal builds
+pub const OBJECT_LITERAL: &'static str = "rjsc_object_literal";
+/// Every javascript function compiles to a C function with this signature
+pub const FUNCTION_PARAMS: &'static str = "(databox **rjsc_env,databox this,int rjsc_argc,databox *rjsc_argv)";
+pub const NEW_FUNCTION: &'static str = "new_function";
+pub const NEW_NAMED_FUNCTION: &'static str = "new_named_function";
+/// Prefix of the global holding the function value of a top level function
+pub const FUNCTION_VALUE: &'static str = "rjsc_value_";
+pub const ENV: &'static str = "rjsc_env";
+pub const NEW_ENV: &'static str = "new_env";
+pub const NEW_CELL: &'static str = "new_cell";
+pub const NTH_ARG: &'static str = "nth_arg";
+pub const CALL_FUNCTION: &'static str = "call_function";
+pub const ARGV: &'static str = "(databox[])";
+pub const ARGC: &'static str = "rjsc_argc";
+pub const ARGV_PARAM: &'static str = "rjsc_argv";
+pub const NULL: &'static str = "NULL";
+pub const TO_BOOL: &'static str = "to_bool";
+pub const NOT: &'static str = "!";
+pub const MINUS: &'static str = "-";
+pub const NEGATE: &'static str = "negate";
+pub const IS_NULLISH: &'static str = "is_nullish";
+/// Holds the left operand of `&&`, `||` and `??` so it is only evaluated once
+pub const LOGICAL_OPERAND: &'static str = "rjsc_logical_operand";
+pub const STATEMENT_EXPRESSION_LEFT: &'static str = "({";
+pub const STATEMENT_EXPRESSION_RIGHT: &'static str = "})";
+pub const NEW_BOOL: &'static str = "new_bool";
+pub const NEW_TRUE: &'static str = "new_bool(1)";
+pub const NEW_FALSE: &'static str = "new_bool(0)";
+pub const NEW_NULL: &'static str = "new_null()";
+pub const NEW_UNDEFINED: &'static str = "new_undefined()";
+/// `NaN` and `Infinity`, the C library has no constants for them without `math.h`
+pub const NEW_NAN: &'static str = "new_from_double(0.0/0.0)";
+pub const NEW_INFINITY: &'static str = "new_from_double(1.0/0.0)";
+/// Prefix of the handler a `try` registers, see `try_enter` in the runtime
+pub const TRY: &'static str = "try";
+pub const TRY_HANDLER: &'static str = "try_handler ";
+pub const TRY_ENTER: &'static str = "try_enter";
+pub const TRY_LEAVE: &'static str = "try_leave();";
+pub const SETJMP: &'static str = "setjmp";
+pub const CAUGHT_VALUE: &'static str = "caught_value()";
+pub const THROW_VALUE: &'static str = "throw_value";
+/// Holds the value of a `return` while the `finally` blocks it leaves are run
+pub const RETURNED: &'static str = "rjsc_returned";
+pub const SWITCH: &'static str = "switch";
+pub const GOTO: &'static str = "goto ";
 pub const CASE: &'static str = "case ";
 pub const BREAK: &'static str = "break ";
 pub const CONTINUE: &'static str = "continue ";
 pub const DEFAULT: &'static str = "default ";
 pub const RETURN: &'static str = "return ";
-pub const DATABOX: &'static str = "databox ";
\ No newline at end of file
+pub const DATABOX: &'static str = "databox ";
+/// The functions and globals of the generated code, they don't clash with the
+/// symbols of the runtime and the C library when linking
+pub const STATIC: &'static str = "static ";
+
+/// C keywords, some of them are javascript identifiers
+pub const C_KEYWORDS: &[&'static str] = &[
+    "asm", "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+    "extern", "float", "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return", "short",
+    "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while",
+];
+
+/// Names the generated C sees besides its own: `main`, the predefined macros and
+/// what `string.h` and `setjmp.h` declare
+pub const C_LIBRARY: &[&'static str] = &[
+    "main", "linux", "unix", "NULL", "size_t", "locale_t", "jmp_buf", "sigjmp_buf", "setjmp", "longjmp",
+    "sigsetjmp", "siglongjmp", "bcmp", "bcopy", "bzero", "explicit_bzero", "ffs", "ffsl", "ffsll", "index",
+    "rindex", "memccpy", "memchr", "memcmp", "memcpy", "memmove", "memset", "stpcpy", "stpncpy", "strcasecmp",
+    "strcasecmp_l", "strcat", "strchr", "strcmp", "strcoll", "strcoll_l", "strcpy", "strcspn", "strdup",
+    "strerror", "strerror_l", "strerror_r", "strlen", "strncasecmp", "strncasecmp_l", "strncat", "strncmp",
+    "strncpy", "strndup", "strnlen", "strpbrk", "strrchr", "strsep", "strsignal", "strspn", "strstr", "strtok",
+    "strtok_r", "strxfrm", "strxfrm_l",
+];
+
+/// Types, constants, functions and macros of the runtime headers
+pub const RUNTIME: &[&'static str] = &[
+    // databox.h
+    "DATABOX_H", "type_e", "NUM", "STR", "DICT", "BOOL", "NIL", "UNDEFINED", "FUNC", "ARRAY", "array",
+    "function", "string", "data_u", "databox", "try_handler",
+    "copy", "new_from_int", "new_from_double", "new_from_str", "new_from_string", "new_object", "new_instance",
+    "set_property", "construct_result", "new_bool", "new_null", "new_undefined", "new_function", "new_array",
+    "new_named_function", "array_push", "array_pop", "get_index", "set_index", "call_method", "array_length", "for_in_keys",
+    "for_of_items", "new_cell", "new_env", "nth_arg", "call_function", "try_enter", "try_leave", "caught_value",
+    "throw_value", "new_error", "throw_error", "decrement", "increment",
+    "data_add_data", "double_add_data", "data_add_double", "int_add_data", "data_add_int",
+    "data_sub_data", "double_sub_data", "data_sub_double", "int_sub_data", "data_sub_int",
+    "data_greater_than_data", "double_greater_than_data", "data_greater_than_double", "double_greater_than_double",
+    "int_greater_than_data", "data_greater_than_int",
+    "data_less_than_data", "double_less_than_data", "data_less_than_double", "double_less_than_double",
+    "int_less_than_data", "data_less_than_int",
+    "data_eq_data", "double_eq_data", "data_eq_double", "int_eq_data", "data_eq_int",
+    "data_strict_eq_data", "data_strict_neq_data",
+    "data_neq_data", "double_neq_data", "data_neq_double", "int_neq_data", "data_neq_int",
+    "data_less_eq_data", "data_greater_eq_data", "number_to_string", "new_string", "string_concat",
+    "string_slice", "string_compare", "data_to_string", "data_to_number", "data_div_data", "data_mod_data",
+    "data_exp_data", "data_bit_and_data", "data_bit_or_data", "data_bit_xor_data", "data_shift_left_data",
+    "data_shift_right_data", "data_unsigned_shift_right_data", "data_bit_not", "data_in_data",
+    "data_instanceof_data", "type_of", "reverse", "data_to_bool", "double_to_bool", "int_to_bool", "is_nullish",
+    "negate", "double_mul_data", "data_mul_double", "int_mul_data", "data_mul_int", "data_mul_data", "def", "def_2",
+    "new", "to_bool", "mul", "mul_given_double", "mul_given_int", "mul_given_data", "add", "add_given_double",
+    "add_given_int", "add_given_data", "sub", "sub_given_double", "sub_given_int", "sub_given_data", "gt",
+    "greater_than_given_double", "greater_than_given_int", "greater_than_given_data", "lt",
+    "less_than_given_double", "less_than_given_int", "less_than_given_data", "eq", "eq_given_double",
+    "eq_given_int", "eq_given_data", "neq", "neq_given_double", "neq_given_int", "neq_given_data",
+    // print.h
+    "print_data", "inspect", "print_all", "print_int", "print_double", "print_str", "eprint", "print",
+    "builtin_print", "new_console",
+    // dict.h and keyval.h
+    "DICT_H", "dictionary_not_found", "dictionary", "dictionary_new", "dictionary_copy", "dictionary_free",
+    "dictionary_add", "dictionary_find", "keyval", "keyval_new", "keyval_copy", "keyval_free", "keyval_matches",
+];
+
+/// True if a javascript variable can't be named `name` in the generated C,
+/// `scope::resolve` gives such variables another name. C reserves the names
+/// starting with two underscores or an underscore and a capital letter.
+pub fn is_reserved(name: &str) -> bool {
+    let mut chars = name.chars();
+    let implementation = match (chars.next(), chars.next()) {
+        (Some('_'), Some(second)) => second == '_' || second.is_ascii_uppercase(),
+        _ => false,
+    };
+    implementation || name.starts_with(GENERATED) || C_KEYWORDS.contains(&name) || C_LIBRARY.contains(&name)
+        || RUNTIME.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Generated;
+    use crate::Options;
+
+    fn compiled(source: &str) -> Generated {
+        crate::compile_to_c(&crate::parse(source).unwrap(), &Options::default()).unwrap()
+    }
+
+    #[test]
+    fn should_convert_databox_tests_with_to_bool() {
+        let generated = compiled("var x = 1;\nwhile (x) { if (!x || x < 2) { x = 0; } }");
+        assert!(generated.source.contains("while(to_bool(x))"));
+        assert!(generated.source.contains("if(to_bool(({databox rjsc_logical_operand=new_bool(!to_bool(x));"));
+    }
+
+    #[test]
+    fn should_lower_switch_to_jumps() {
+        let generated = compiled("var x = 3;\nswitch (x) { case 1: print(1); default: print(2); case 3: break; }");
+        assert!(generated.source.contains("if(data_strict_eq_data(rjsc_switch_0,new(3.0)))goto rjsc_switch_0_case_2;"));
+        // default is the second case, the break leaves the switch
+        assert!(generated.source.contains("goto rjsc_switch_0_case_1;\nrjsc_switch_0_case_0:;"));
+        assert!(generated.source.contains("rjsc_switch_0_case_2:;\ngoto rjsc_switch_0_end;"));
+    }
+
+    #[test]
+    fn should_bind_this_to_the_constructed_object() {
+        let generated = compiled("function P(nom) { this.nom = nom; }\nvar p = new P(\"Rand\");");
+        assert!(generated.source.contains("static databox P(databox **rjsc_env,databox this,int rjsc_argc,databox *rjsc_argv){"));
+        assert!(generated.source.contains("set_property(this,\"nom\",nom);return new_undefined();}"));
+    }
+
+    #[test]
+    fn should_hoist_the_functions_declared_in_top_level_blocks() {
+        let generated = compiled("if (true) { function h() { return 3; } print(h()); }");
+        assert!(generated.source.contains("static databox h(databox **rjsc_env,databox this,int rjsc_argc,databox *rjsc_argv){"));
+        assert!(generated.source.contains("rjsc_value_h=new_function(\"h\",h,NULL);"));
+        assert!(generated.source.contains("if(to_bool(new_bool(1))){print_all(1,(databox[]){h(NULL,new_undefined(),0,NULL)});}"));
+
+        let program = crate::parse("function f() {}\n{ function f() {} }").unwrap();
+        let error = crate::compile_to_c(&program, &Options::default()).err().unwrap();
+        assert_eq!(error.to_string(), "2:3: unsupported `f` declared twice at the top level in the C backend");
+    }
+
+    #[test]
+    fn should_compile_nested_functions_to_closures() {
+        let generated = compiled("function f(a) {\n  function g(b) { return a + b; }\n  return g;\n}\nprint(f(1)(2));");
+        assert!(generated.source.contains("databox *a=new_cell(nth_arg(rjsc_argc,rjsc_argv,0));"));
+        assert!(generated.source.contains("databox g=new_function(\"g\",rjsc_g_2_2,new_env(1,a));"));
+        assert!(generated.source.contains("return data_add_data((*rjsc_env[0]),b);"));
+    }
+
+    #[test]
+    fn should_call_function_values_with_their_this() {
+        let source = "function Box(v) {\n  var set = () => { this.v = v; };\n  set();\n}\n\
+                      var b = new Box(4);\nb.get = function () { return this.v; };\nvar twice = x => x + x;\n\
+                      print(b.get(), twice(b.v), twice);";
+        let generated = compiled(source);
+        // the arrow gets the cell holding `this` from `Box`
+        assert!(generated.source.contains("databox *rjsc_this_cell=new_cell(this);"));
+        assert!(generated.source.contains("databox set=new_undefined();\nset=new_function(\"\",rjsc_function_2_12,new_env(2,rjsc_this_cell,v));"));
+        assert!(generated.source.contains("twice=new_function(\"\",rjsc_function_7_12,NULL);"));
+    }
+
+    #[test]
+    fn should_index_arrays_through_the_runtime() {
+        let generated = compiled("var a = [1, 'b', , 3];\na[5] = a.push(4);\nprint(a.length);");
+        assert!(generated.source.contains("new_array(4,(databox[]){new(1.0),new(\"b\"),new_undefined(),new(3.0)})"));
+        assert!(generated.source.contains("set_index(a,new(5.0),call_method(a,\"push\",1,(databox[]){new(4.0)}))"));
+        assert!(generated.source.contains("get_index(a,new(\"length\"))"));
+    }
+
+    #[test]
+    fn should_read_and_update_object_properties() {
+        let generated = compiled("var o = { nom: \"Ken\" };\nprint(o.nom);\no[\"age\"] += 1;\no.age++;");
+        assert!(generated.source.contains("o=({databox rjsc_object_literal=new_object();set_property(rjsc_object_literal,\"nom\",new(\"Ken\"));rjsc_object_literal;})"));
+        assert!(generated.source.contains("print_all(1,(databox[]){get_index(o,new(\"nom\"))})"));
+        // the object and the key are evaluated once
+        assert!(generated.source.contains("({databox rjsc_member_object=o;databox rjsc_member_key=new(\"age\");\
+                                           databox rjsc_member_value=get_index(rjsc_member_object,rjsc_member_key);\
+                                           set_index(rjsc_member_object,rjsc_member_key,data_add_double(rjsc_member_value,1.0));})"));
+        assert!(generated.source.contains("databox rjsc_update_old=new_from_double(data_to_number(rjsc_member_value));increment(&rjsc_member_value);\
+                                           set_index(rjsc_member_object,rjsc_member_key,rjsc_member_value);rjsc_update_old;})"));
+    }
+
+    #[test]
+    fn should_give_updates_of_variables_a_value() {
+        let generated = compiled("var i = 0;\nvar j = i++;\nvar a = [];\na[--i] = 5;");
+        assert!(generated.source.contains("j=({databox rjsc_update_old=new_from_double(data_to_number(i));increment(&i);rjsc_update_old;});"));
+        assert!(generated.source.contains("set_index(a,decrement(&i),new(5.0))"));
+    }
+
+    #[test]
+    fn should_lower_every_kind_of_loop() {
+        let source = "var s = 0;\nfor (var i = 0; i < 4; i = i + 1) { s = s + i; }\ndo { s = s + 10; } while (false);\n\
+                      var o = { a: 1 };\nfor (var k in o) { s = s + o[k]; }\nfor (var x of [7, 8]) { s = s + x; }";
+        let generated = compiled(source);
+        // every variable of the top level code is a global, undefined until its declaration
+        assert!(generated.source.contains("databox i;"));
+        assert!(generated.source.contains("i=new(0.0);\nfor(;"));
+        assert!(generated.source.contains("do{"));
+        assert!(generated.source.contains("{databox rjsc_for_in_0=for_in_keys(o);"));
+        assert!(generated.source.contains("databox rjsc_for_of_1_item=get_index(rjsc_for_of_1,new(rjsc_for_of_1_index));x=rjsc_for_of_1_item;"));
+    }
+
+    #[test]
+    fn should_jump_to_labels() {
+        let source = "var n = 0;\nouter: for (var i = 0; i < 4; i = i + 1) {\n\
+                      for (var j = 0; j < 4; j = j + 1) { if (j == 2) { continue outer; } if (i == 3) { break outer; } }\n}\n\
+                      block: { n = n + 10; break block; }";
+        let generated = compiled(source);
+        assert!(generated.source.contains("goto rjsc_label_outer_0_next;"));
+        assert!(generated.source.contains("rjsc_label_outer_0_next:;\n}rjsc_label_outer_0_end:;"));
+        assert!(generated.source.contains("goto rjsc_label_block_1_end;"));
+    }
+
+    #[test]
+    fn should_run_finally_blocks_when_leaving_a_try() {
+        let generated = compiled("function f(x) {\n  try { if (x) { return 1; } throw 2; }\n  finally { print(0); }\n}");
+        assert!(generated.source.contains("{try_handler rjsc_try_0;try_enter(&rjsc_try_0);\nif(!setjmp(rjsc_try_0.jump)){"));
+        assert!(generated.source.contains("{databox rjsc_returned=new(1.0);try_leave();print_all(1,(databox[]){new(0.0)});return rjsc_returned;}"));
+        assert!(generated.source.contains("else{databox rjsc_try_0_error=caught_value();print_all(1,(databox[]){new(0.0)});throw_value(rjsc_try_0_error);}"));
+    }
+
+    #[test]
+    fn should_rename_shadowing_block_declarations() {
+        let source = "let x = 1;\nfunction f() {\n  var v = x;\n  if (v) { let x = 2; v = v + x; }\n  return v;\n}\n\
+                      { const x = 3; print(x); }\nvar fs = [];\nfor (let i = 0; i < 2; i = i + 1) { fs.push(() => i); }\n\
+                      try { throw 4; } catch (x) { print(x); }";
+        let generated = compiled(source);
+        // `v` is hoisted out of the if, the shadowing `x`s are renamed
+        assert!(generated.source.contains("databox v=new_undefined();\ndatabox x_1=new_undefined();"));
+        assert!(generated.source.contains("v=(*x);\nif(to_bool(v)){x_1=new(2.0);"));
+        assert!(generated.source.contains("x_2=new(3.0);\nprint_all(1,(databox[]){x_2});"));
+        assert!(generated.source.contains("x_3=caught_value();print_all(1,(databox[]){x_3});"));
+        // each iteration has its own `i`, starting from the value of the previous one
+        assert!(generated.source.contains("i=new_cell(new_undefined());(*i)=new(0.0);"));
+        assert!(generated.source.contains("i=new_cell(*i),(*i)=data_add_double((*i),1.0)"));
+        assert!(generated.warnings.is_empty());
+    }
+
+    #[test]
+    fn should_print_several_values_with_print_all() {
+        // the runtime `print` macro takes one value
+        let generated = compiled("var t = true;\nprint(t, null, undefined);");
+        assert!(generated.source.contains("print_all(3,(databox[]){t,new_null(),new_undefined()})"));
+    }
+
+    #[test]
+    fn should_compile_print_and_console_log_to_function_values() {
+        let generated = compiled("var log = console.log;\nlog(typeof print);\nconsole.log(1);\nvar c = console;");
+        assert!(generated.source.contains("log=new_function(\"log\",builtin_print,NULL);"));
+        assert!(generated.source.contains("type_of(new_function(\"print\",builtin_print,NULL))"));
+        assert!(generated.source.contains("print_all(1,(databox[]){new(1.0)});"));
+        assert!(generated.source.contains("c=new_console();"));
+    }
+
+    #[test]
+    fn should_box_the_operands_of_equality() {
+        // literals are boxed so the runtime compares their types
+        let generated = compiled("print(1 == \"1\", \"1\" === 1);");
+        assert!(generated.source.contains("data_eq_data(new(1.0),new(\"1\"))"));
+        assert!(generated.source.contains("data_strict_eq_data(new(\"1\"),new(1.0))"));
+    }
+
+    #[test]
+    fn should_call_the_runtime_operators() {
+        let generated = compiled("function P() {}\nvar p = new P();\nvar o = {a: 1};\nprint(-7 % 3, p instanceof P, typeof o);");
+        assert!(generated.source.contains("data_mod_data(new_from_double(-7.0),new(3.0))"));
+        // a top level function has one function value
+        assert!(generated.source.contains("static databox rjsc_value_P;"));
+        assert!(generated.source.contains("rjsc_value_P=new_function(\"P\",P,NULL);"));
+        assert!(generated.source.contains("new_instance(rjsc_value_P)"));
+        assert!(generated.source.contains("data_instanceof_data(p,rjsc_value_P)"));
+        assert!(generated.source.contains("type_of(o)"));
+    }
+
+    #[test]
+    fn should_box_and_escape_string_literals() {
+        let generated = compiled("var s = \"a\" + 1;\nprint(s > \"b\\\"\");\nvar o = { \"a\\\"b\": 1 };\no[\"c\\\\\"] = o.x;");
+        assert!(generated.source.contains("s=data_add_double(new(\"a\"),1.0);"));
+        assert!(generated.source.contains("print_all(1,(databox[]){new_bool(data_greater_than_data(s,new(\"b\\\"\")))});"));
+        assert!(generated.source.contains("set_property(rjsc_object_literal,\"a\\\"b\",new(1.0))"));
+        assert!(generated.source.contains("new(\"c\\\\\")"));
+        assert!(generated.source.contains("new(\"x\")"));
+    }
+
+    #[test]
+    fn should_reserve_what_the_runtime_headers_declare() {
+        use crate::runtime::*;
+        for header in [DATABOX_H, PRINT_H, DICT_H, KEYVAL_H].iter() {
+            let code: String = header.lines().map(|line| line.split("//").next().unwrap()).collect::<Vec<_>>().join("\n");
+            let words = code.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '('));
+            for called in words.filter_map(|word| word.split('(').next().filter(|_| word.contains('('))) {
+                let name = called.trim();
+                assert!(name.is_empty() || super::is_reserved(name), "{} isn't reserved", name);
+            }
+        }
+    }
+
+    #[test]
+    fn should_keep_the_generated_names_apart_from_the_javascript_ones() {
+        let generated = compiled("function f_g() {}
+function f(argc) {
+  function g() { return argc; }
+  return g;
+}
+\
+                                  function main(int) { return int; }\nvar index = 1;");
+        assert!(generated.source.contains("static databox f_g(databox **rjsc_env,"));
+        assert!(generated.source.contains("static databox rjsc_g_3_2(databox **rjsc_env,"));
+        assert!(generated.source.contains("databox *argc=new_cell(nth_arg(rjsc_argc,rjsc_argv,0));"));
+        assert!(generated.source.contains("static databox main_1(databox **rjsc_env,"));
+        assert!(generated.source.contains("databox int_1=nth_arg(rjsc_argc,rjsc_argv,0);"));
+        assert!(generated.source.contains("static databox index_1;"));
+    }
+}
@@ -0,0 +1,490 @@
+use crate::ast::expression::*;
+use crate::ast::expression::Expression::*;
+use crate::ast::statement::*;
+use crate::ast::statement::Statement::*;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+use crate::visitor::Visitor;
+
+/// The code generator a program is checked against
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Backend {
+    C,
+    Asm,
+    Interpreter,
+}
+
+impl Backend {
+    /// Estree node types the backend generates code for
+    fn supported_nodes(&self) -> &'static [&'static str] {
+        match self {
+            Backend::C => crate::c_compile::SUPPORTED_NODES,
+            Backend::Asm => crate::asm_compile::SUPPORTED_NODES,
+            Backend::Interpreter => crate::interpret::SUPPORTED_NODES,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Backend::C => "the C backend",
+            Backend::Asm => "the asm backend",
+            Backend::Interpreter => "the interpreter",
+        }
+    }
+}
+
+/// Walks the whole program before any code is generated and collects every
+/// construct the backend can't handle, so that they are all reported at once
+pub struct Checker {
+    backend: Backend,
+    errors: Vec<CompileError>,
+    /// Labels of the enclosing statements in the current function, with whether
+    /// they name a loop
+    labels: Vec<(String, bool)>,
+    /// Loops around the statement in the current function, a `continue` needs one
+    loops: usize,
+    /// Loops and switches around the statement in the current function, an
+    /// unlabeled `break` needs one
+    breakables: usize,
+    in_function: bool,
+}
+
+pub fn check(program: &[Box<Statement>], backend: Backend) -> CompileResult<()> {
+    let mut checker = Checker { backend, errors: vec![], labels: vec![], loops: 0, breakables: 0, in_function: false };
+    for statement in program.iter() {
+        checker.visit_statement(statement)?;
+    }
+
+    match checker.errors.len() {
+        0 => Ok(()),
+        1 => Err(checker.errors.remove(0)),
+        _ => Err(CompileError::Multiple(checker.errors)),
+    }
+}
+
+impl Checker {
+    /// Record the node if the backend can't handle it, its children are only
+    /// worth checking when it is supported
+    fn supports(&mut self, node_type: &str, loc: &Loc) -> bool {
+        if self.backend.supported_nodes().contains(&node_type) {
+            true
+        } else {
+            let feature = format!("`{}` in {}", node_type, self.backend.name());
+            self.errors.push(CompileError::unsupported(&feature, loc));
+            false
+        }
+    }
+
+    fn visit_statements(&mut self, statements: &[Box<Statement>]) -> CompileResult<()> {
+        for statement in statements.iter() {
+            self.visit_statement(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Labels and loops don't cross function boundaries
+    fn visit_function_body<F: FnOnce(&mut Self) -> CompileResult<()>>(&mut self, visit: F) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.labels);
+        let loops = std::mem::replace(&mut self.loops, 0);
+        let breakables = std::mem::replace(&mut self.breakables, 0);
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        let result = visit(self);
+        self.labels = labels;
+        self.loops = loops;
+        self.breakables = breakables;
+        self.in_function = in_function;
+        result
+    }
+
+    fn visit_loop_body(&mut self, body: &Statement) -> CompileResult<()> {
+        self.loops += 1;
+        self.breakables += 1;
+        let result = self.visit_statement(body);
+        self.loops -= 1;
+        self.breakables -= 1;
+        result
+    }
+
+    fn check_label(&mut self, label: &Option<Id>, is_continue: bool, loc: &Loc) {
+        match label {
+            Some(label) => {
+                let defined = self.labels.iter().any(|(name, is_loop)| *name == label.name && (*is_loop || !is_continue));
+                if !defined {
+                    self.errors.push(CompileError::undefined_label(label, is_continue));
+                }
+            }
+            None if is_continue && self.loops == 0 => self.errors.push(CompileError::misplaced("continue", loc)),
+            None if !is_continue && self.breakables == 0 => self.errors.push(CompileError::misplaced("break", loc)),
+            None => {}
+        }
+    }
+
+    fn visit_for_each(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        self.visit_for_init(&f.left)?;
+        self.visit_expression(&f.right)?;
+        self.visit_loop_body(&f.body)
+    }
+}
+
+impl Visitor for Checker {
+    fn visit_statement(&mut self, s: &Statement) -> CompileResult<()> {
+        match s {
+            BlockStatement(b) => self.visit_block_statement(b),
+            ExpressionStatement(e) => self.visit_expression_statement(e),
+            WhileStatement(w) => self.visit_while_statement(w),
+            VariableDeclaration(v) => self.visit_variable_declaration(v),
+            VariableDeclarator(v) => self.visit_variable_declarator(v),
+            FunctionDeclaration(f) => self.visit_function_declaration(f),
+            IfStatement(i) => self.visit_if_statement(i),
+            SwitchStatement(s) => self.visit_switch_statement(s),
+            SwitchCase(case) => self.visit_case(case),
+            ForStatement(f) => self.visit_for_statement(f),
+            DoWhileStatement(d) => self.visit_do_while_statement(d),
+            ForInStatement(f) => self.visit_for_in_statement(f),
+            ForOfStatement(f) => self.visit_for_of_statement(f),
+            BreakStatement(b) => self.visit_break_statement(b),
+            ContinueStatement(c) => self.visit_continue_statement(c),
+            LabeledStatement(l) => self.visit_labeled_statement(l),
+            ThrowStatement(t) => self.visit_throw_statement(t),
+            TryStatement(t) => self.visit_try_statement(t),
+            ReturnStatement(r) => self.visit_return_statement(r),
+            Statement::Unsupported(node) => {
+                self.supports(&node.node_type, &node.loc);
+                Ok(())
+            }
+            EmptyStatement | Root(_) => Ok(()),
+        }
+    }
+
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()> {
+        if self.supports("BlockStatement", &s.loc) {
+            self.visit_statements(&s.body)?;
+        }
+        Ok(())
+    }
+
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()> {
+        if self.supports("VariableDeclarator", &v.loc) {
+            self.visit_option_expression(&v.init)?;
+        }
+        Ok(())
+    }
+
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()> {
+        if self.supports("WhileStatement", &w.loc) {
+            self.visit_expression(&w.test)?;
+            self.visit_loop_body(&w.body)?;
+        }
+        Ok(())
+    }
+
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()> {
+        if self.supports("VariableDeclaration", &v.loc) {
+            self.visit_statements(&v.declarations)?;
+        }
+        Ok(())
+    }
+
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()> {
+        if self.supports("IfStatement", &i.loc) {
+            self.visit_expression(&i.test)?;
+            self.visit_statement(&i.consequent)?;
+            if let Some(alternate) = &i.alternate {
+                self.visit_statement(alternate)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()> {
+        if self.supports("SwitchStatement", &s.loc) {
+            self.visit_expression(&s.discriminant)?;
+            self.breakables += 1;
+            let result = s.cases.iter().try_for_each(|case| self.visit_case(case));
+            self.breakables -= 1;
+            result?;
+        }
+        Ok(())
+    }
+
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()> {
+        if self.supports("SwitchCase", &case.loc) {
+            self.visit_option_expression(&case.test)?;
+            self.visit_statements(&case.consequent)?;
+        }
+        Ok(())
+    }
+
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()> {
+        if self.supports("ForStatement", &f.loc) {
+            if let Some(init) = &f.init {
+                self.visit_for_init(init)?;
+            }
+            self.visit_option_expression(&f.test)?;
+            self.visit_option_expression(&f.update)?;
+            self.visit_loop_body(&f.body)?;
+        }
+        Ok(())
+    }
+
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()> {
+        if self.supports("DoWhileStatement", &d.loc) {
+            self.visit_loop_body(&d.body)?;
+            self.visit_expression(&d.test)?;
+        }
+        Ok(())
+    }
+
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        if self.supports("ForInStatement", &f.loc) {
+            self.visit_for_each(f)?;
+        }
+        Ok(())
+    }
+
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        if self.supports("ForOfStatement", &f.loc) {
+            self.visit_for_each(f)?;
+        }
+        Ok(())
+    }
+
+    fn visit_break_statement(&mut self, f: &BreakStmt) -> CompileResult<()> {
+        if self.supports("BreakStatement", &f.loc) {
+            self.check_label(&f.label, false, &f.loc);
+        }
+        Ok(())
+    }
+
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()> {
+        if self.supports("ReturnStatement", &r.loc) {
+            if !self.in_function {
+                self.errors.push(CompileError::misplaced("return", &r.loc));
+            }
+            self.visit_option_expression(&r.argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_continue_statement(&mut self, c: &ContinueStmt) -> CompileResult<()> {
+        if self.supports("ContinueStatement", &c.loc) {
+            self.check_label(&c.label, true, &c.loc);
+        }
+        Ok(())
+    }
+
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()> {
+        if self.supports("LabeledStatement", &l.loc) {
+            // `a: b: while (..)` makes both labels name the loop
+            let mut body = &l.body;
+            while let box LabeledStatement(inner) = body {
+                body = &inner.body;
+            }
+            self.labels.push((l.label.name.clone(), body.is_loop()));
+            let result = self.visit_statement(&l.body);
+            self.labels.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()> {
+        if self.supports("ThrowStatement", &t.loc) {
+            self.visit_expression(&t.argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()> {
+        if self.supports("TryStatement", &t.loc) {
+            self.visit_statements(&t.block.body)?;
+            if let Some(handler) = &t.handler {
+                self.visit_statements(&handler.body.body)?;
+            }
+            if let Some(finalizer) = &t.finalizer {
+                self.visit_statements(&finalizer.body)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_function_declaration(&mut self, f: &FunctionDec) -> CompileResult<()> {
+        if self.supports("FunctionDeclaration", &f.loc) {
+            self.visit_function_body(|checker| checker.visit_statements(&f.body.body))?;
+        }
+        Ok(())
+    }
+
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()> {
+        match exp {
+            Some(expression) => self.visit_expression(expression),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()> {
+        if !self.supports(exp.type_name(), &exp.get_loc()) {
+            return Ok(());
+        }
+
+        match exp {
+            BinaryExpression(b) => self.visit_binary_expression(b),
+            UnaryExpression(u) => self.visit_unary_expression(u),
+            UpdateExpression(u) => self.visit_update_expression(u),
+            CallExpression(c) => self.visit_call_expression(c),
+            AssignmentExpression(a) => self.visit_assign(a),
+            LogicalExpression(l) => self.visit_logical_expression(l),
+            MemberExpression(m) => self.visit_member_expression(m),
+            ObjectExpression(o) => self.visit_object_expression(o, String::new()),
+            NewExpression(n) => self.visit_new_expression(n),
+            FunctionExpression(f) => self.visit_function_expression(f),
+            ArrowFunctionExpression(f) => self.visit_arrow_function_expression(f),
+            ArrayExpression(a) => self.visit_array_expression(a),
+            NumericLiteral(_) | StringLiteral(_) | BooleanLiteral(_) | NullLiteral(_) | Identifier(_) => Ok(()),
+            ThisExpression(_) => Ok(()),
+            Expression::Unsupported(_) => Ok(()),
+        }
+    }
+
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()> {
+        if self.supports("ExpressionStatement", &s.loc) {
+            self.visit_expression(&s.expression)?;
+        }
+        Ok(())
+    }
+
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()> {
+        self.visit_expression(&b.left)?;
+        self.visit_expression(&b.right)
+    }
+
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()> {
+        self.visit_expression(&a.left)?;
+        self.visit_expression(&a.right)
+    }
+
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()> {
+        self.visit_expression(&u.argument)
+    }
+
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()> {
+        self.visit_expression(&u.argument)
+    }
+
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()> {
+        self.visit_expression(&m.object)?;
+        self.visit_expression(&m.property)
+    }
+
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()> {
+        self.visit_expression(&l.left)?;
+        self.visit_expression(&l.right)
+    }
+
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()> {
+        self.visit_expression(&e.callee)?;
+        for argument in e.arguments.iter() {
+            self.visit_expression(argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()> {
+        self.visit_expression(&n.callee)?;
+        for argument in n.arguments.iter() {
+            self.visit_expression(argument)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()> {
+        self.visit_function_body(|checker| checker.visit_block_statement(&f.body))
+    }
+
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()> {
+        self.visit_function_body(|checker| match &f.body {
+            ArrowBody::Block(block) => checker.visit_block_statement(block),
+            ArrowBody::Expression(expression) => checker.visit_expression(expression),
+        })
+    }
+
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()> {
+        for element in a.elements.iter() {
+            self.visit_option_expression(element)?;
+        }
+        Ok(())
+    }
+
+    fn visit_object_expression(&mut self, o: &ObjectExp, id: String) -> CompileResult<()> {
+        for property in o.properties.iter() {
+            self.visit_property_expression(&id, property)?;
+        }
+        Ok(())
+    }
+
+    fn visit_property_expression(&mut self, _id: &str, p: &Property) -> CompileResult<()> {
+        self.visit_expression(&p.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::check;
+    use crate::check::Backend;
+    use crate::error::CompileError;
+    use crate::parser::parse;
+
+    #[test]
+    fn should_report_every_unsupported_construct() {
+        let source = "var a = (1, 2);\nfunction f() { return a ? 1 : 2; }\nwhile (a) { a = (a, 2); }";
+        let program = parse(source).unwrap().get_program_root().unwrap();
+
+        match check(&program, Backend::C) {
+            Err(CompileError::Multiple(errors)) => {
+                let lines: Vec<f64> = errors.iter().map(|err| err.loc().unwrap().start.line).collect();
+                assert_eq!(lines, vec![1.0, 2.0, 3.0]);
+                assert_eq!(errors[1].message(), "unsupported `ConditionalExpression` in the C backend");
+            }
+            other => panic!("expected three errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_undefined_labels() {
+        let source = "a: { while (true) { continue a; } }\nb: while (true) { break c; }\n\
+                      d: while (true) { var f = function () { break d; }; }";
+        let program = parse(source).unwrap().get_program_root().unwrap();
+
+        match check(&program, Backend::C) {
+            Err(CompileError::Multiple(errors)) => {
+                let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                assert_eq!(messages, vec!["1:30: undefined loop label `a`", "2:25: undefined label `c`", "3:47: undefined label `d`"]);
+            }
+            other => panic!("expected three errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_misplaced_jumps() {
+        let source = "break;\ncontinue;\nreturn 1;\nfunction f() { break; }\n\
+                      while (true) { switch (1) { case 1: continue; default: break; } var g = () => { continue; }; }";
+        let program = parse(source).unwrap().get_program_root().unwrap();
+
+        match check(&program, Backend::C) {
+            Err(CompileError::Multiple(errors)) => {
+                let messages: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                assert_eq!(messages, vec!["1:1: `break` outside of a loop or a switch", "2:1: `continue` outside of a loop",
+                                          "3:1: `return` outside of a function", "4:16: `break` outside of a loop or a switch",
+                                          "5:81: `continue` outside of a loop"]);
+            }
+            other => panic!("expected five errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_check_against_the_selected_backend() {
+        let program = parse("var x = 1;\nswitch (x) { case 1: x = 2; }").unwrap().get_program_root().unwrap();
+        assert!(check(&program, Backend::Interpreter).is_ok());
+        assert!(check(&program, Backend::Asm).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::ast::expression::Loc;
+use crate::error::CompileError;
+
+const RED: &'static str = "\x1b[1;31m";
+const YELLOW: &'static str = "\x1b[1;33m";
+const BLUE: &'static str = "\x1b[1;34m";
+const BOLD: &'static str = "\x1b[1m";
+const RESET: &'static str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// A message about the javascript source, rendered like rustc does :
+///
+/// ```text
+/// error: object property key must be a string literal
+///  --> exemples/14-obj.js:1:13
+///   |
+/// 1 | var ken0 = { 1: "Ken Jones" };
+///   |              ^
+///   |
+///   = help: use an identifier or a string as key
+/// ```
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub loc: Option<Loc>,
+    pub help: Option<String>,
+}
+
+/// Renders diagnostics for one source file, errors and warnings alike
+pub struct Renderer<'src> {
+    pub filename: &'src str,
+    /// Without the javascript source (estree input) only the location is printed
+    pub source: Option<&'src str>,
+    pub color: bool,
+}
+
+impl Diagnostic {
+    pub fn error(message: &str, loc: Option<Loc>) -> Self {
+        Diagnostic { level: Level::Error, message: message.to_string(), loc, help: None }
+    }
+
+    pub fn warning(message: &str, loc: Option<Loc>) -> Self {
+        Diagnostic { level: Level::Warning, message: message.to_string(), loc, help: None }
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(err: &CompileError) -> Self {
+        let diagnostic = Diagnostic::error(&err.message(), err.loc().cloned());
+        match err.help() {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }
+    }
+}
+
+impl<'src> Renderer<'src> {
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        let (label, label_color) = match diagnostic.level {
+            Level::Error => ("error", RED),
+            Level::Warning => ("warning", YELLOW),
+        };
+        out.push_str(&format!("{}{}{}: {}{}\n",
+                              self.paint(label_color), label, self.paint(BOLD), diagnostic.message, self.paint(RESET)));
+
+        let loc = match &diagnostic.loc {
+            Some(loc) => loc,
+            None => {
+                out.push_str(&format!(" {}-->{} {}\n", self.paint(BLUE), self.paint(RESET), self.filename));
+                return out;
+            }
+        };
+
+        let line = loc.start.line as usize;
+        let column = loc.start.column as usize;
+        let gutter = " ".repeat(line.to_string().len());
+        out.push_str(&format!("{}{}-->{} {}:{}:{}\n",
+                              gutter, self.paint(BLUE), self.paint(RESET), self.filename, line, column + 1));
+
+        let source_line = self.source.and_then(|source| source.lines().nth(line - 1));
+        if let Some(source_line) = source_line {
+            // a span over several lines is underlined up to the end of its first line
+            let end = if loc.end.line as usize == line {
+                loc.end.column as usize
+            } else {
+                source_line.chars().count()
+            };
+            let underline = "^".repeat(end.saturating_sub(column).max(1));
+
+            out.push_str(&format!("{} {}|{}\n", gutter, self.paint(BLUE), self.paint(RESET)));
+            out.push_str(&format!("{}{} |{} {}\n", self.paint(BLUE), line, self.paint(RESET), source_line));
+            out.push_str(&format!("{} {}|{} {}{}{}{}\n",
+                                  gutter, self.paint(BLUE), self.paint(RESET),
+                                  " ".repeat(column), self.paint(label_color), underline, self.paint(RESET)));
+        }
+
+        if let Some(help) = &diagnostic.help {
+            out.push_str(&format!("{} {}|{}\n", gutter, self.paint(BLUE), self.paint(RESET)));
+            out.push_str(&format!("{} {}={} {}help{}: {}\n",
+                                  gutter, self.paint(BLUE), self.paint(RESET), self.paint(BOLD), self.paint(RESET), help));
+        }
+        out
+    }
+
+    fn paint(&self, color: &'static str) -> &'static str {
+        if self.color { color } else { "" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::expression::Loc;
+    use crate::ast::expression::Pos;
+    use crate::diagnostic::Diagnostic;
+    use crate::diagnostic::Renderer;
+
+    #[test]
+    fn should_underline_the_offending_span() {
+        let source = "var x = 1;\nvar ken = { 1: \"Ken\" };\n";
+        let loc = Loc { start: Pos { line: 2.0, column: 12.0 }, end: Pos { line: 2.0, column: 13.0 } };
+        let diagnostic = Diagnostic::error("object property key must be a string literal", Some(loc))
+            .with_help("use an identifier or a string as key");
+        let renderer = Renderer { filename: "obj.js", source: Some(source), color: false };
+
+        assert_eq!(renderer.render(&diagnostic), "\
+error: object property key must be a string literal
+ --> obj.js:2:13
+  |
+2 | var ken = { 1: \"Ken\" };
+  |             ^
+  |
+  = help: use an identifier or a string as key
+");
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::Display;
+use std::fmt::Error;
+use std::fmt::Formatter;
+use std::io;
+
+use crate::ast::expression::Id;
+use crate::ast::expression::Loc;
+use crate::parser::ParseError;
+use crate::token::token::Literal;
+
+/// Every way a compilation can fail, errors coming from the javascript source carry
+/// the location of the offending node
+#[derive(Debug)]
+pub enum CompileError {
+    /// The javascript source could not be parsed by the built-in parser
+    Syntax { message: String, loc: Loc },
+    /// The estree json could not be deserialized
+    Estree(String),
+    UnknownOperator { operator: String, loc: Loc },
+    Unsupported { feature: String, loc: Loc },
+    InvalidPropertyKey { loc: Loc },
+    /// A `break` or `continue` to a label which doesn't name an enclosing statement,
+    /// or a `continue` to a label which doesn't name a loop
+    UndefinedLabel { label: String, of_loop: bool, loc: Loc },
+    /// A `break` outside of a loop or a switch, a `continue` outside of a loop or
+    /// a `return` outside of a function
+    Misplaced { statement: &'static str, loc: Loc },
+    /// An identifier no scope declares, and which isn't a builtin
+    Undeclared { name: String, loc: Loc },
+    /// A second declaration of a name a scope already declares, only `var`s and
+    /// functions can be redeclared
+    Duplicate { name: String, loc: Loc },
+    /// An assignment or an update of a variable declared with `const`
+    ConstAssignment { name: String, loc: Loc },
+    /// A `let` or `const` variable used before the declaration making it usable
+    UsedBeforeDeclaration { name: String, loc: Loc },
+    /// The interpreted program failed, like a javascript exception would
+    Runtime { message: String, loc: Loc },
+    /// A value the interpreted program threw and never caught
+    Thrown { value: Literal, loc: Loc },
+    /// An external program (babylon, gcc, indent) is missing or failed
+    Tool { name: String, message: String },
+    Io(io::Error),
+    /// Several errors reported together, by the checking pass
+    Multiple(Vec<CompileError>),
+}
+
+pub type CompileResult<T> = Result<T, CompileError>;
+
+impl CompileError {
+    pub fn unsupported(feature: &str, loc: &Loc) -> Self {
+        CompileError::Unsupported { feature: feature.to_string(), loc: loc.clone() }
+    }
+
+    pub fn unknown_operator(operator: &str, loc: &Loc) -> Self {
+        CompileError::UnknownOperator { operator: operator.to_string(), loc: loc.clone() }
+    }
+
+    pub fn runtime(message: &str, loc: &Loc) -> Self {
+        CompileError::Runtime { message: message.to_string(), loc: loc.clone() }
+    }
+
+    /// `of_loop` for the label of a `continue`, which has to name a loop
+    pub fn undefined_label(label: &Id, of_loop: bool) -> Self {
+        CompileError::UndefinedLabel { label: label.name.clone(), of_loop, loc: label.loc.clone() }
+    }
+
+    /// `statement` is the keyword, `break`, `continue` or `return`
+    pub fn misplaced(statement: &'static str, loc: &Loc) -> Self {
+        CompileError::Misplaced { statement, loc: loc.clone() }
+    }
+
+    pub fn undeclared(id: &Id) -> Self {
+        CompileError::Undeclared { name: id.name.clone(), loc: id.loc.clone() }
+    }
+
+    pub fn duplicate(id: &Id) -> Self {
+        CompileError::Duplicate { name: id.name.clone(), loc: id.loc.clone() }
+    }
+
+    pub fn const_assignment(id: &Id) -> Self {
+        CompileError::ConstAssignment { name: id.name.clone(), loc: id.loc.clone() }
+    }
+
+    pub fn used_before_declaration(id: &Id) -> Self {
+        CompileError::UsedBeforeDeclaration { name: id.name.clone(), loc: id.loc.clone() }
+    }
+
+    pub fn tool(name: &str, message: &str) -> Self {
+        CompileError::Tool { name: name.to_string(), message: message.to_string() }
+    }
+
+    pub fn loc(&self) -> Option<&Loc> {
+        match self {
+            CompileError::Syntax { loc, .. } => Some(loc),
+            CompileError::UnknownOperator { loc, .. } => Some(loc),
+            CompileError::Unsupported { loc, .. } => Some(loc),
+            CompileError::InvalidPropertyKey { loc } => Some(loc),
+            CompileError::UndefinedLabel { loc, .. } => Some(loc),
+            CompileError::Misplaced { loc, .. } => Some(loc),
+            CompileError::Undeclared { loc, .. } => Some(loc),
+            CompileError::Duplicate { loc, .. } => Some(loc),
+            CompileError::ConstAssignment { loc, .. } => Some(loc),
+            CompileError::UsedBeforeDeclaration { loc, .. } => Some(loc),
+            CompileError::Runtime { loc, .. } => Some(loc),
+            CompileError::Thrown { loc, .. } => Some(loc),
+            _ => None,
+        }
+    }
+
+    /// The error message without its location
+    pub fn message(&self) -> String {
+        match self {
+            CompileError::Syntax { message, .. } => message.clone(),
+            CompileError::Estree(message) => format!("invalid estree: {}", message),
+            CompileError::UnknownOperator { operator, .. } => format!("unknown operator `{}`", operator),
+            CompileError::Unsupported { feature, .. } => format!("unsupported {}", feature),
+            CompileError::InvalidPropertyKey { .. } => "object property key must be a string literal".to_string(),
+            CompileError::UndefinedLabel { label, of_loop: false, .. } => format!("undefined label `{}`", label),
+            CompileError::UndefinedLabel { label, of_loop: true, .. } => format!("undefined loop label `{}`", label),
+            CompileError::Misplaced { statement: "break", .. } => "`break` outside of a loop or a switch".to_string(),
+            CompileError::Misplaced { statement: "continue", .. } => "`continue` outside of a loop".to_string(),
+            CompileError::Misplaced { statement, .. } => format!("`{}` outside of a function", statement),
+            CompileError::Undeclared { name, .. } => format!("`{}` is not declared", name),
+            CompileError::Duplicate { name, .. } => format!("`{}` is already declared", name),
+            CompileError::ConstAssignment { name, .. } => format!("assignment to constant `{}`", name),
+            CompileError::UsedBeforeDeclaration { name, .. } => format!("`{}` is used before its declaration", name),
+            CompileError::Runtime { message, .. } => message.clone(),
+            CompileError::Thrown { value, .. } => format!("Uncaught {}", describe_thrown(value)),
+            CompileError::Tool { name, message } => format!("{}: {}", name, message),
+            CompileError::Io(err) => err.to_string(),
+            CompileError::Multiple(errors) => errors.iter()
+                .map(|err| err.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+
+    /// The errors to report one by one
+    pub fn errors(&self) -> Vec<&CompileError> {
+        match self {
+            CompileError::Multiple(errors) => errors.iter().collect(),
+            err => vec![err],
+        }
+    }
+
+    /// A hint on how to get around the error, shown below the source snippet
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            CompileError::InvalidPropertyKey { .. } => Some("use an identifier or a string as key"),
+            CompileError::UndefinedLabel { .. } => Some("a label is only visible inside the statement it names"),
+            CompileError::Undeclared { .. } => Some("declare it with `var`, `let` or `const`"),
+            CompileError::Duplicate { .. } => Some("rename one of the declarations"),
+            CompileError::ConstAssignment { .. } => Some("declare it with `let` to change its value"),
+            CompileError::UsedBeforeDeclaration { .. } => Some("move the declaration before its first use"),
+            _ => None,
+        }
+    }
+}
+
+/// Errors are shown as `name: message` like node does, other values as they print
+fn describe_thrown(value: &Literal) -> String {
+    if let Literal::ObjectLiteral(object) = value {
+        let object = object.borrow();
+        if let (Some(Literal::StringLiteral(name)), Some(Literal::StringLiteral(message))) = (object.get("name"), object.get("message")) {
+            return format!("{}: {}", name, message);
+        }
+    }
+    value.inspect()
+}
+
+/// `line:col: message`, columns are displayed starting from 1 like most compilers do
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self.loc() {
+            Some(loc) => write!(f, "{}:{}: {}", loc.start.line, loc.start.column + 1.0, self.message()),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl From<ParseError> for CompileError {
+    fn from(err: ParseError) -> Self {
+        CompileError::Syntax { message: err.message, loc: err.loc }
+    }
+}
+
+impl From<serde_json::Error> for CompileError {
+    fn from(err: serde_json::Error) -> Self {
+        CompileError::Estree(err.to_string())
+    }
+}
+
+impl From<io::Error> for CompileError {
+    fn from(err: io::Error) -> Self {
+        CompileError::Io(err)
+    }
+}
@@ -1,23 +1,63 @@
 use std::fs;
+use std::io;
+use std::io::Read;
 use crate::ast::statement::RootStatement;
+use crate::error::CompileResult;
 
+/// Source path used to read the estree from stdin
+pub const STDIN_PATH: &'static str = "-";
 
-fn read_file(path: &str) -> String {
-    let filename = path;
-    fs::read_to_string(filename)
-        .expect(format!("Failed to read file with path : {}", path).as_str())
-        .clone()
+
+pub fn deserialize_json_file(path: &str) -> CompileResult<RootStatement> {
+    let file = fs::read_to_string(path)?;
+    deserialize_json(file.as_str())
+}
+
+pub fn deserialize_json(json: &str) -> CompileResult<RootStatement> {
+    let program: RootStatement = serde_json::from_str(json)?;
+    Ok(program)
 }
 
-pub fn deserialize_json_file(path: &str) -> RootStatement {
-    let file = read_file(path);
-    let program: RootStatement = serde_json::from_str(file.as_str())
-        .expect("Unable to parse json file");
-    program
+/// Read a pre-generated estree json from a file, or from stdin if path is "-"
+pub fn read_estree(path: &str) -> Result<String, io::Error> {
+    if path == STDIN_PATH {
+        let mut json = String::new();
+        io::stdin().read_to_string(&mut json)?;
+        Ok(json)
+    } else {
+        fs::read_to_string(path)
+    }
 }
 
-pub fn deserialize_json(json: &str) -> RootStatement {
-    let program: RootStatement = serde_json::from_str(json)
-        .expect("Unable to parse json file");
-    program
+/// True if the source should be read as an estree json rather than given to babylon
+pub fn is_estree_path(path: &str) -> bool {
+    path == STDIN_PATH || path.ends_with(".json")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::statement::ForInit;
+    use crate::ast::statement::Statement::*;
+    use crate::file_util::deserialize_json;
+
+    #[test]
+    fn should_deserialize_declarations_in_loop_heads() {
+        let loc = r#""loc":{"start":{"line":1,"column":0},"end":{"line":1,"column":1}}"#;
+        let id = |name: &str| format!(r#"{{"type":"Identifier","name":"{}",{}}}"#, name, loc);
+        let declaration = format!(r#"{{"type":"VariableDeclaration","kind":"var",{},"declarations":[
+                                      {{"type":"VariableDeclarator","id":{},"init":null,{}}}]}}"#, loc, id("i"), loc);
+        let json = format!(r#"{{"type":"Program","body":[
+                                {{"type":"ForStatement","init":{},"test":null,"update":null,"body":{{"type":"EmptyStatement"}},{}}},
+                                {{"type":"ForInStatement","left":{},"right":{},"body":{{"type":"EmptyStatement"}},{}}}]}}"#,
+                           declaration, loc, id("k"), id("o"), loc);
+
+        let program = deserialize_json(&json).unwrap().get_program_root().unwrap();
+        match (&program[0], &program[1]) {
+            (box ForStatement(f), box ForInStatement(f_in)) => {
+                assert!(matches!(f.init, Some(ForInit::Declaration(_))));
+                assert!(matches!(f_in.left, ForInit::Expression(_)));
+            }
+            other => panic!("unexpected tree {:?}", other),
+        }
+    }
 }
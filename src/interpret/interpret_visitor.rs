@@ -1,123 +1,291 @@
 use crate::visitor::Visitor;
 use crate::interpret::interpreter::Interpreter;
-use crate::ast::statement::Statement::*;
+use crate::interpret::interpreter::Jump;
+use crate::interpret::interpreter::caught;
 use crate::ast::statement::*;
-use crate::ast::expression::Expression::*;
+use crate::ast::statement::Statement::*;
 use crate::ast::expression::*;
-use crate::token::token::Token;
-use crate::token::to_token::ToToken;
-use crate::token::to_token::postfix;
-use crate::interpret::solve_postfix;
+use crate::ast::expression::Expression::*;
+use crate::token::token::Literal;
+use crate::error::CompileError;
+use crate::error::CompileResult;
 
 impl <'ast> Visitor for Interpreter {
-    fn visit_block_statement(&mut self, s: &BlockStmt) {
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()> {
+        self.scoped(|interpreter| interpreter.run_block(&s.body))
+    }
 
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()> {
+        let value = match &v.init {
+            Some(init) => self.evaluate(init)?,
+            None => Literal::Undefined,
+        };
+        self.declare(&v.id.name, value);
+        Ok(())
     }
 
-    fn visit_variable_declarator(&mut self, v: &Variable) {
-        unimplemented!("Declarator TODO");
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.loop_labels);
+        while self.evaluate(&w.test)?.to_bool() {
+            if !self.loop_body(&w.body, &labels)? {
+                break;
+            }
+        }
+        Ok(())
     }
 
-    fn visit_while_statement(&mut self, w: &WhileStmt) {
-        unimplemented!("WHILE TODO");
+    /// `var x;` keeps the value `x` already has
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()> {
+        for declarator in v.declarations.iter() {
+            if let box VariableDeclarator(declarator) = declarator {
+                let value = match &declarator.init {
+                    Some(init) => self.evaluate(init)?,
+                    None if v.kind == "var" => continue,
+                    None => Literal::Undefined,
+                };
+                self.declare_variable(v, declarator, value);
+            }
+        }
+        Ok(())
     }
 
-    fn visit_variable_declaration(&mut self, v: &VariableDec) {
-        unimplemented!("Declaration TODO");
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()> {
+        if self.evaluate(&i.test)?.to_bool() {
+            self.visit_statement(&i.consequent)
+        } else {
+            match &i.alternate {
+                Some(alternate) => self.visit_statement(alternate),
+                None => Ok(()),
+            }
+        }
     }
 
-    fn visit_if_statement(&mut self, i: &IfStmt) {
-        unimplemented!("If TODO");
+    /// The cases run from the first one whose test is strictly equal to the
+    /// discriminant, or from `default`, until a `break`. They share one block scope
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()> {
+        let discriminant = self.evaluate(&s.discriminant)?;
+        let mut start = None;
+        for (i, case) in s.cases.iter().enumerate() {
+            if let Some(test) = &case.test {
+                if self.evaluate(test)? == discriminant {
+                    start = Some(i);
+                    break;
+                }
+            }
+        }
+        let start = match start.or_else(|| s.cases.iter().position(|case| case.test.is_none())) {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+
+        self.scoped(|interpreter| {
+            for case in s.cases.iter() {
+                interpreter.hoist(&case.consequent);
+            }
+            for case in s.cases[start..].iter() {
+                interpreter.visit_case(case)?;
+                if interpreter.returned.is_some() || interpreter.jump.is_some() {
+                    break;
+                }
+            }
+            if let Some(Jump::Break(None)) = interpreter.jump {
+                interpreter.jump = None;
+            }
+            Ok(())
+        })
     }
 
-    fn visit_switch_statement(&mut self, s: &SwitchStmt) {
-        unimplemented!("Swithc TODO");
+    /// Its functions are hoisted by the switch, its test already matched
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()> {
+        self.run_statements(&case.consequent)
     }
 
-    fn visit_case(&mut self, case: &CaseStmt) {
-        unimplemented!("Case TODO");
+    /// `let` and `const` loop variables are copied for each iteration
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.loop_labels);
+        let per_iteration = match &f.init {
+            Some(ForInit::Declaration(declaration)) => declaration.kind != "var",
+            _ => false,
+        };
+        self.scoped(|interpreter| {
+            if let Some(init) = &f.init {
+                interpreter.visit_for_init(init)?;
+            }
+            loop {
+                if let Some(test) = &f.test {
+                    if !interpreter.evaluate(test)?.to_bool() {
+                        break;
+                    }
+                }
+                if !interpreter.loop_body(&f.body, &labels)? {
+                    break;
+                }
+                if per_iteration {
+                    interpreter.next_iteration();
+                }
+                interpreter.visit_option_expression(&f.update)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.loop_labels);
+        while self.loop_body(&d.body, &labels)? && self.evaluate(&d.test)?.to_bool() {}
+        Ok(())
     }
 
-    fn visit_for_statement(&mut self, f: &ForStmt) {
-        unimplemented!("For TODO");
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.loop_labels);
+        self.for_in(f, &labels)
     }
 
-    fn visit_break_statement(&mut self, f: &BreakStmt) {
-        unimplemented!("Break TODO");
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()> {
+        let labels = std::mem::take(&mut self.loop_labels);
+        self.for_of(f, &labels)
     }
 
-    fn visit_return_statement(&mut self, r: &ReturnStmt) {
-        unimplemented!("Return TODO");
+    fn visit_break_statement(&mut self, b: &BreakStmt) -> CompileResult<()> {
+        self.jump = Some(Jump::Break(b.label.as_ref().map(|label| label.name.clone())));
+        Ok(())
     }
 
-    fn visit_continue_statement(&mut self, c: &ContinueStmt) {
-        unimplemented!("Continue TODO");
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()> {
+        let value = match &r.argument {
+            Some(argument) => self.evaluate(argument)?,
+            None => Literal::Undefined,
+        };
+        self.returned = Some(value);
+        Ok(())
     }
 
-    fn visit_function_declaration(&mut self, f: &FunctionDec) {
-        unimplemented!("Function TODO");
+    fn visit_continue_statement(&mut self, c: &ContinueStmt) -> CompileResult<()> {
+        self.jump = Some(Jump::Continue(c.label.as_ref().map(|label| label.name.clone())));
+        Ok(())
     }
 
-    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) {
-        unimplemented!("Opt exp TODO");
+    /// A labeled loop handles its labeled `continue`, a labeled `break` ends here
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()> {
+        let mut labels = vec![l.label.name.clone()];
+        let mut body = &l.body;
+        while let box LabeledStatement(inner) = body {
+            labels.push(inner.label.name.clone());
+            body = &inner.body;
+        }
+        if body.is_loop() {
+            self.loop_labels = labels.clone();
+        }
+        self.visit_statement(body)?;
+        if let Some(Jump::Break(Some(label))) = &self.jump {
+            if labels.contains(label) {
+                self.jump = None;
+            }
+        }
+        Ok(())
     }
 
-    fn visit_expression(&mut self, exp: &Expression) {
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()> {
+        let value = self.evaluate(&t.argument)?;
+        Err(CompileError::Thrown { value, loc: t.loc.clone() })
+    }
+
+    /// The finally block runs however the statement is left, a `return` or a jump
+    /// in it replaces the way the try or catch block was left
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()> {
+        let mut result = self.visit_block_statement(&t.block);
+        if let (Err(err), Some(handler)) = (result.as_ref(), &t.handler) {
+            if let Some(value) = caught(err) {
+                result = self.scoped(|interpreter| {
+                    if let Some(param) = &handler.param {
+                        interpreter.declare(&param.name, value);
+                    }
+                    interpreter.visit_block_statement(&handler.body)
+                });
+            }
+        }
+        if let Some(finalizer) = &t.finalizer {
+            let returned = self.returned.take();
+            let jump = self.jump.take();
+            self.visit_block_statement(finalizer)?;
+            if self.returned.is_some() || self.jump.is_some() {
+                return Ok(());
+            }
+            self.returned = returned;
+            self.jump = jump;
+        }
+        result
+    }
+
+    /// Already declared when entering the scope, see `Interpreter::hoist`
+    fn visit_function_declaration(&mut self, _f: &FunctionDec) -> CompileResult<()> {
+        Ok(())
+    }
+
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()> {
         match exp {
-            NumericLiteral(ref n) => self.main.extend(n.to_token()),
-            StringLiteral(ref s) => self.main.extend(s.to_token()),
-            UpdateExpression(ref u) => self.visit_update_expression(u),
-            BinaryExpression(ref b) => self.visit_binary_expression(b),
-            UnaryExpression(ref u) => self.visit_unary_expression(u),
-            MemberExpression(ref m) => self.visit_member_expression(m),
-            CallExpression(ref c) => self.visit_call_expression(c),
-            AssignmentExpression(ref e) => self.visit_assign(e),
-            LogicalExpression(ref l) => self.visit_logical_expression(l),
-            _ => (),
-        };
+            Some(expression) => self.visit_expression(expression),
+            None => Ok(()),
+        }
+    }
+
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()> {
+        self.evaluate(exp).map(|_| ())
+    }
+
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()> {
+        self.visit_expression(&s.expression)
+    }
+
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()> {
+        self.binary(b).map(|_| ())
+    }
+
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()> {
+        self.assign(a).map(|_| ())
+    }
+
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()> {
+        self.unary(u).map(|_| ())
     }
 
-    fn visit_expression_statement(&mut self, s: &ExpressionStmt) {
-        self.visit_expression(&s.expression);
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()> {
+        self.update(u).map(|_| ())
     }
 
-    fn visit_binary_expression(&mut self, b: &BinaryExp) {
-        let tokens = &mut b.to_token();
-        unimplemented!("evaluate expression {:?}",tokens);
-        let mut postfix_exp  = postfix(tokens);
-        let result = solve_postfix(&mut postfix_exp);
-        unimplemented!("result : {}", result);
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()> {
+        self.evaluate(&MemberExpression(m.clone())).map(|_| ())
     }
 
-    fn visit_assign(&mut self, a: &AssignmentExp) {
-        unimplemented!("Assign TODO");
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()> {
+        self.logical(l).map(|_| ())
     }
 
-    fn visit_unary_expression(&mut self, u: &UnaryExp) {
-        unimplemented!("UnaryExp TODO");
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()> {
+        self.call(e).map(|_| ())
     }
 
-    fn visit_update_expression(&mut self, u: &UpdateExp) {
-        unimplemented!("UpdateExp TODO");
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()> {
+        self.construct(n).map(|_| ())
     }
 
-    fn visit_member_expression(&mut self, m: &MemberExp) {
-        unimplemented!("MemberExp TODO");
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()> {
+        self.evaluate(&FunctionExpression(f.clone())).map(|_| ())
     }
 
-    fn visit_logical_expression(&mut self, l: &LogicalExp) {
-        unimplemented!("LogExp TODO");
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()> {
+        self.evaluate(&ArrowFunctionExpression(f.clone())).map(|_| ())
     }
 
-    fn visit_call_expression(&mut self, e: &CallExp) {
-        unimplemented!("Call TODO");
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()> {
+        self.evaluate(&ArrayExpression(a.clone())).map(|_| ())
     }
 
-    fn visit_object_expression(&mut self, o: &ObjectExp, id: String) {
-        unimplemented!("Object TODO");
+    fn visit_object_expression(&mut self, o: &ObjectExp, _id: String) -> CompileResult<()> {
+        self.evaluate(&ObjectExpression(o.clone())).map(|_| ())
     }
 
-    fn visit_property_expression(&mut self, id: &str, p: &Property) {
-        unimplemented!("Prop TODO");
+    /// Evaluated with their object
+    fn visit_property_expression(&mut self, _id: &str, _p: &Property) -> CompileResult<()> {
+        Ok(())
     }
-}
\ No newline at end of file
+}
@@ -1,50 +1,699 @@
-use crate::ast::statement::Statement;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use crate::token::token::Node;
-use crate::token::token::Token;
-use crate::ast::statement::Statement::*;
+use std::fmt::Display;
+use std::fmt::Error;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use crate::ast::expression::*;
 use crate::ast::expression::Expression::*;
+use crate::ast::function::Function;
+use crate::ast::function::FunctionKind;
+use crate::ast::statement::*;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+use crate::scope;
+use crate::token::operator;
+use crate::token::token::BinaryOperator;
+use crate::token::token::Literal;
+use crate::token::token::LogicalOperator;
+use crate::token::token::Operator;
+use crate::token::token::Properties;
+use crate::token::token::UnaryOperator;
 use crate::visitor::Visitor;
 
+/// Tree walking interpreter, javascript values are represented by token literals
 pub struct Interpreter {
-    pub functions: HashMap<String, Vec<Node>>,
-    pub vars: HashMap<String, Vec<Token>>,
-    pub main: Vec<Token>,
+    /// What the program printed
+    pub out: String,
+    /// Global variables
+    pub vars: HashMap<String, Literal>,
+    /// Scopes of the functions being called and of the blocks being run, innermost last
+    pub frames: Vec<Rc<Scope>>,
+    /// Set by a `return` until the function call it leaves gets the value
+    pub returned: Option<Literal>,
+    /// Set by a `break` or a `continue` until the loop it targets handles it
+    pub jump: Option<Jump>,
+    /// Labels of the loop about to run, set by the labeled statement around it
+    pub loop_labels: Vec<String>,
+}
+
+/// A `break` or a `continue`, with the label it targets if any
+pub enum Jump {
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+/// Parameters, `var`s and `this` of a function call, or `let`s and `const`s of
+/// a block, the closures created while it runs keep it alive
+pub struct Scope {
+    pub vars: RefCell<HashMap<String, Literal>>,
+    /// Scope the called function was created in or the one around the block,
+    /// `None` for the globals
+    pub parent: Option<Rc<Scope>>,
+}
+
+/// A function value, with the scope it was created in
+pub struct Closure {
+    pub function: Function,
+    pub scope: Option<Rc<Scope>>,
+    /// Set for `print` and `console.log`, which print their arguments instead
+    /// of running the empty body of their function
+    pub prints: bool,
+}
+
+/// Printed like node's `console.log` does
+impl Display for Closure {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        if self.function.name.is_empty() {
+            write!(f, "[Function (anonymous)]")
+        } else {
+            write!(f, "[Function: {}]", self.function.name)
+        }
+    }
 }
 
 impl Interpreter {
-    pub fn run(ast: Vec<Box<Statement>>) -> Interpreter {
-        let mut ast_mut = ast.clone();
-        let mut token_root = Interpreter {
-            functions: HashMap::new(),
+    pub fn run(ast: Vec<Box<Statement>>) -> CompileResult<Interpreter> {
+        let mut interpreter = Interpreter {
+            out: String::new(),
             vars: HashMap::new(),
-            main: vec![],
+            frames: vec![],
+            returned: None,
+            jump: None,
+            loop_labels: vec![],
         };
 
-        let mut indexes_to_remove = vec![];
-        ast_mut.iter_mut().enumerate().for_each(|(i, statement)| {
-            if let box VariableDeclaration(var) = statement {
-                token_root.visit_variable_declaration(var);
-                indexes_to_remove.push(i);
+        interpreter.hoist_vars(&ast);
+        interpreter.run_block(&ast)?;
+        Ok(interpreter)
+    }
+
+    /// `var`s are undefined until their declaration runs, an existing
+    /// parameter keeps its value
+    fn hoist_vars(&mut self, statements: &[Box<Statement>]) {
+        for (id, _) in scope::hoisted(statements) {
+            let declared = match self.frames.last() {
+                Some(scope) => scope.vars.borrow().contains_key(&id.name),
+                None => self.vars.contains_key(&id.name),
             };
-        });
+            if !declared {
+                self.declare(&id.name, Literal::Undefined);
+            }
+        }
+    }
+
+    /// Function declarations are usable before the statement declaring them,
+    /// they are variables of the enclosing function even in a block
+    pub fn hoist(&mut self, statements: &[Box<Statement>]) {
+        for statement in statements.iter() {
+            if let box Statement::FunctionDeclaration(f) = statement {
+                let function = self.closure(Function::from(f));
+                self.store(&f.id.name, function);
+            }
+        }
+    }
+
+    /// Run statements until one of them returns or jumps
+    pub fn run_block(&mut self, statements: &[Box<Statement>]) -> CompileResult<()> {
+        self.hoist(statements);
+        self.run_statements(statements)
+    }
+
+    /// Run statements whose functions are already hoisted
+    pub fn run_statements(&mut self, statements: &[Box<Statement>]) -> CompileResult<()> {
+        for statement in statements.iter() {
+            self.visit_statement(statement)?;
+            if self.returned.is_some() || self.jump.is_some() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `run` in a new block scope, left even when `run` fails
+    pub fn scoped<T, F>(&mut self, run: F) -> CompileResult<T>
+        where F: FnOnce(&mut Self) -> CompileResult<T> {
+        let parent = self.frames.last().cloned();
+        self.frames.push(Rc::new(Scope { vars: RefCell::new(HashMap::new()), parent }));
+        let result = run(self);
+        self.frames.pop();
+        result
+    }
+
+    /// Give the next iteration of a loop its own copy of the loop variables,
+    /// the closures created by the body keep the ones of their iteration
+    pub fn next_iteration(&mut self) {
+        if let Some(scope) = self.frames.pop() {
+            let vars = scope.vars.borrow().clone();
+            self.frames.push(Rc::new(Scope { vars: RefCell::new(vars), parent: scope.parent.clone() }));
+        }
+    }
+
+    /// `var x = 1` assigns the hoisted variable, `let` and `const` declare it
+    /// in the innermost scope
+    pub fn declare_variable(&mut self, declaration: &VariableDec, v: &Variable, value: Literal) {
+        if declaration.kind == "var" {
+            self.store(&v.id.name, value);
+        } else {
+            self.declare(&v.id.name, value);
+        }
+    }
+
+    /// Declare a variable in the innermost scope, or a global one
+    pub fn declare(&mut self, name: &str, value: Literal) {
+        match self.frames.last() {
+            Some(scope) => scope.vars.borrow_mut().insert(name.to_string(), value),
+            None => self.vars.insert(name.to_string(), value),
+        };
+    }
+
+    /// The scope declaring `name`, `None` for a global or undeclared variable
+    fn scope_of(&self, name: &str) -> Option<Rc<Scope>> {
+        let mut scope = self.frames.last().cloned();
+        while let Some(current) = scope {
+            if current.vars.borrow().contains_key(name) {
+                return Some(current);
+            }
+            scope = current.parent.clone();
+        }
+        None
+    }
+
+    fn closure(&self, function: Function) -> Literal {
+        Literal::FunctionLiteral(Rc::new(Closure { function, scope: self.frames.last().cloned(), prints: false }))
+    }
+
+    /// Evaluate an expression to its javascript value
+    pub fn evaluate(&mut self, exp: &Expression) -> CompileResult<Literal> {
+        match exp {
+            NumericLiteral(n) => Ok(Literal::NumericLiteral(n.value)),
+            StringLiteral(s) => Ok(Literal::StringLiteral(s.value.clone())),
+            BooleanLiteral(b) => Ok(Literal::BooleanLiteral(b.value)),
+            NullLiteral(_) => Ok(Literal::NullLiteral),
+            Identifier(id) => self.lookup(id),
+            BinaryExpression(b) => self.binary(b),
+            UnaryExpression(u) => self.unary(u),
+            LogicalExpression(l) => self.logical(l),
+            AssignmentExpression(a) => self.assign(a),
+            UpdateExpression(u) => self.update(u),
+            CallExpression(c) => self.call(c),
+            NewExpression(n) => self.construct(n),
+            MemberExpression(m) => {
+                let object = self.evaluate(&m.object)?;
+                self.member(object, m)
+            }
+            // arrows have no `this`, the one of the function they were created in is found
+            ThisExpression(_) => Ok(self.scope_of(THIS)
+                .and_then(|scope| scope.vars.borrow().get(THIS).cloned())
+                .unwrap_or(Literal::Undefined)),
+            FunctionExpression(f) => Ok(self.closure(Function::from(f))),
+            ArrowFunctionExpression(f) => Ok(self.closure(Function::from(f))),
+            ObjectExpression(o) => {
+                let mut properties = Properties::default();
+                for property in o.properties.iter() {
+                    let key = property.key.try_as_string_from_identifier().or(property.key.try_as_string_from_lit());
+                    let key = key.ok_or_else(|| CompileError::InvalidPropertyKey { loc: property.key.get_loc() })?;
+                    properties.insert(key, self.evaluate(&property.value)?);
+                }
+                Ok(Literal::ObjectLiteral(Rc::new(RefCell::new(properties))))
+            }
+            ArrayExpression(a) => {
+                let mut items = vec![];
+                for element in a.elements.iter() {
+                    items.push(match element {
+                        Some(element) => self.evaluate(element)?,
+                        None => Literal::Undefined,
+                    });
+                }
+                Ok(Literal::ArrayLiteral(Rc::new(RefCell::new(items))))
+            }
+            other => {
+                let feature = format!("`{}` in the interpreter", other.type_name());
+                Err(CompileError::unsupported(&feature, &other.get_loc()))
+            }
+        }
+    }
 
-        indexes_to_remove.iter().for_each(|i| { ast_mut.remove(*i); });
-        let mut indexes_to_remove = vec![];
+    /// The binding `scope::resolve` found tells globals and builtins apart, the
+    /// other variables are in the frames of the scope chain
+    fn lookup(&self, id: &Id) -> CompileResult<Literal> {
+        let value = match id.binding {
+            Some(Binding::Global) => self.vars.get(&id.name).cloned(),
+            Some(Binding::Builtin) => None,
+            _ => {
+                let local = self.scope_of(&id.name).and_then(|scope| scope.vars.borrow().get(&id.name).cloned());
+                local.or_else(|| self.vars.get(&id.name).cloned())
+            }
+        };
+        match value {
+            Some(value) => Ok(value),
+            None if id.name == "undefined" => Ok(Literal::Undefined),
+            None if id.name == "NaN" => Ok(Literal::NumericLiteral(f64::NAN)),
+            None if id.name == "Infinity" => Ok(Literal::Infinity),
+            None if id.name == "print" => Ok(print_function("print", &id.loc)),
+            None if id.name == "console" => {
+                let mut console = Properties::default();
+                console.insert("log".to_string(), print_function("log", &id.loc));
+                Ok(Literal::ObjectLiteral(Rc::new(RefCell::new(console))))
+            }
+            None => Err(CompileError::runtime(&format!("ReferenceError: {} is not defined", id.name), &id.loc)),
+        }
+    }
+
+    pub fn binary(&mut self, b: &BinaryExp) -> CompileResult<Literal> {
+        let op: BinaryOperator = operator(b.operator.as_str(), &b.loc)?;
+        let left = self.evaluate(&b.left)?;
+        let right = self.evaluate(&b.right)?;
+        Operator::BinOp(op).solve(&left, &right, &b.loc)
+    }
+
+    pub fn unary(&mut self, u: &UnaryExp) -> CompileResult<Literal> {
+        let op: UnaryOperator = operator(u.operator.as_str(), &u.loc)?;
+        let argument = match (op, &u.argument) {
+            // the identifiers `scope::resolve` found no declaration for are undefined
+            (UnaryOperator::TypeOf, box Identifier(id)) if id.binding.is_none() => Literal::Undefined,
+            _ => self.evaluate(&u.argument)?,
+        };
+        Ok(match op {
+            UnaryOperator::Plus => Literal::NumericLiteral(argument.to_number()),
+            UnaryOperator::Minus => Literal::NumericLiteral(-argument.to_number()),
+            UnaryOperator::ExPoint => Literal::BooleanLiteral(!argument.to_bool()),
+            UnaryOperator::Tilde => Literal::NumericLiteral(!argument.to_int32() as f64),
+            UnaryOperator::TypeOf => Literal::StringLiteral(argument.type_of().to_string()),
+            UnaryOperator::Void => Literal::Undefined,
+            UnaryOperator::Delete => return Err(CompileError::unsupported("`delete` in the interpreter", &u.loc)),
+        })
+    }
 
-        ast.iter().enumerate().for_each(|(i, statement)| {
-            if let box FunctionDeclaration(function) = statement {
-                token_root.visit_function_declaration(function);
-                indexes_to_remove.push(i);
+    /// The right operand is only evaluated when the left one doesn't decide the result
+    pub fn logical(&mut self, l: &LogicalExp) -> CompileResult<Literal> {
+        let op: LogicalOperator = operator(l.operator.as_str(), &l.loc)?;
+        let left = self.evaluate(&l.left)?;
+        if op.short_circuits(&left) {
+            Ok(left)
+        } else {
+            self.evaluate(&l.right)
+        }
+    }
+
+    /// `x op= y` computes `x op y` from the value `x` has before `y` is evaluated
+    pub fn assign(&mut self, a: &AssignmentExp) -> CompileResult<Literal> {
+        if a.operator == "=" {
+            return match &a.left {
+                box Identifier(id) => {
+                    let value = self.evaluate(&a.right)?;
+                    self.store(&id.name, value.clone());
+                    Ok(value)
+                }
+                box MemberExpression(m) => {
+                    let object = self.evaluate(&m.object)?;
+                    let key = self.property_key(m)?;
+                    let value = self.evaluate(&a.right)?;
+                    set_property(&object, &key, value.clone(), &m.loc)?;
+                    Ok(value)
+                }
+                _ => Err(self.unsupported_target(&a.left, &a.operator, &a.loc)),
             };
-        });
+        }
+
+        let op: BinaryOperator = operator(&a.operator[..a.operator.len() - 1], &a.loc)?;
+        self.modify(&a.left, &a.operator, &a.loc, |interpreter, current| {
+            let right = interpreter.evaluate(&a.right)?;
+            let value = Operator::BinOp(op).solve(&current, &right, &a.loc)?;
+            Ok((value.clone(), value))
+        })
+    }
+
+    /// `x++` evaluates to the number `x` had, `++x` to the one it gets
+    pub fn update(&mut self, u: &UpdateExp) -> CompileResult<Literal> {
+        let step = match u.operator.as_str() {
+            "++" => 1.0,
+            "--" => -1.0,
+            other => return Err(CompileError::unknown_operator(other, &u.loc)),
+        };
+        self.modify(&u.argument, &u.operator, &u.loc, |_, current| {
+            let old = current.to_number();
+            let new = Literal::NumericLiteral(old + step);
+            Ok((new.clone(), if u.prefix { new } else { Literal::NumericLiteral(old) }))
+        })
+    }
+
+    /// Store in `target` the first value `compute` gives from its current value
+    /// and evaluate to the second one, the object and key of a member are only
+    /// evaluated once
+    fn modify<F>(&mut self, target: &Expression, operator: &str, loc: &Loc, compute: F) -> CompileResult<Literal>
+        where F: FnOnce(&mut Self, Literal) -> CompileResult<(Literal, Literal)> {
+        match target {
+            Identifier(id) => {
+                let current = self.lookup(id)?;
+                let (value, result) = compute(self, current)?;
+                self.store(&id.name, value);
+                Ok(result)
+            }
+            MemberExpression(m) => {
+                let object = self.evaluate(&m.object)?;
+                let key = self.property_key(m)?;
+                let current = self.property(object.clone(), &key, &m.loc)?;
+                let (value, result) = compute(self, current)?;
+                set_property(&object, &key, value, &m.loc)?;
+                Ok(result)
+            }
+            _ => Err(self.unsupported_target(target, operator, loc)),
+        }
+    }
+
+    fn unsupported_target(&self, target: &Expression, operator: &str, loc: &Loc) -> CompileError {
+        let feature = format!("`{}` of `{}` in the interpreter", operator, target.type_name());
+        CompileError::unsupported(&feature, loc)
+    }
+
+    /// Assign the variable `name` where it is declared, or a global
+    fn store(&mut self, name: &str, value: Literal) {
+        match self.scope_of(name) {
+            Some(scope) => scope.vars.borrow_mut().insert(name.to_string(), value),
+            None => self.vars.insert(name.to_string(), value),
+        };
+    }
+
+    /// A method call `object.f()` binds `this` to the object
+    pub fn call(&mut self, c: &CallExp) -> CompileResult<Literal> {
+        let (callee, this) = match &c.callee {
+            box MemberExpression(m) => {
+                let object = self.evaluate(&m.object)?;
+                if let Literal::ArrayLiteral(array) = &object {
+                    let key = self.property_key(m)?;
+                    if key == "push" || key == "pop" {
+                        let arguments = self.evaluate_all(&c.arguments)?;
+                        return array_method(&mut array.borrow_mut(), &key, arguments, &c.loc);
+                    }
+                }
+                (self.member(object.clone(), m)?, object)
+            }
+            callee => (self.evaluate(callee)?, Literal::Undefined),
+        };
+
+        let arguments = self.evaluate_all(&c.arguments)?;
+        let function = function(callee, &c.callee, "a function")?;
+        self.call_function(&function, this, arguments)
+    }
+
+    /// `new F(args)` calls `F` with a fresh object as `this`, which is the result
+    /// unless `F` returns another object
+    pub fn construct(&mut self, n: &NewExp) -> CompileResult<Literal> {
+        let callee = self.evaluate(&n.callee)?;
+        let arguments = self.evaluate_all(&n.arguments)?;
+        let function = function(callee, &n.callee, "a constructor")?;
+        if !function.function.has_own_this() {
+            return Err(not_a(&n.callee, "a constructor"));
+        }
+
+        let mut properties = Properties::default();
+        properties.constructor = Some(function.clone());
+        let this = Literal::ObjectLiteral(Rc::new(RefCell::new(properties)));
+        match self.call_function(&function, this.clone(), arguments)? {
+            object @ Literal::ObjectLiteral(_) => Ok(object),
+            _ => Ok(this),
+        }
+    }
+
+    /// Property `m.property` of the already evaluated `m.object`
+    pub fn member(&mut self, object: Literal, m: &MemberExp) -> CompileResult<Literal> {
+        let key = self.property_key(m)?;
+        self.property(object, &key, &m.loc)
+    }
+
+    /// Property `key` of `object`, read at `loc`
+    fn property(&self, object: Literal, key: &str, loc: &Loc) -> CompileResult<Literal> {
+        match object {
+            Literal::ObjectLiteral(object) => Ok(object.borrow().get(key).cloned().unwrap_or(Literal::Undefined)),
+            Literal::ArrayLiteral(array) => {
+                let array = array.borrow();
+                match (key, array_index(key)) {
+                    ("length", _) => Ok(Literal::NumericLiteral(array.len() as f64)),
+                    (_, Some(i)) => Ok(array.get(i).cloned().unwrap_or(Literal::Undefined)),
+                    _ => Ok(Literal::Undefined),
+                }
+            }
+            Literal::StringLiteral(s) => match (key, array_index(key)) {
+                ("length", _) => Ok(Literal::NumericLiteral(s.encode_utf16().count() as f64)),
+                (_, Some(i)) => Ok(string_at(&s, i).map_or(Literal::Undefined, Literal::StringLiteral)),
+                _ => Ok(Literal::Undefined),
+            },
+            Literal::NullLiteral | Literal::Undefined => {
+                let message = format!("TypeError: Cannot read properties of {} (reading '{}')", object, key);
+                Err(CompileError::runtime(&message, loc))
+            }
+            _ => Ok(Literal::Undefined),
+        }
+    }
+
+    /// The key of `object.key`, or the string value of `key` in `object[key]`
+    fn property_key(&mut self, m: &MemberExp) -> CompileResult<String> {
+        match (&m.property, m.computed) {
+            (box Identifier(id), false) => Ok(id.name.clone()),
+            (property, true) => Ok(self.evaluate(property)?.to_string()),
+            (property, false) => Err(CompileError::InvalidPropertyKey { loc: property.get_loc() }),
+        }
+    }
+
+    /// Run the body of a loop named by `labels`, false when the loop is left by a
+    /// `break`, a `return` or a jump to an outer statement which is kept for it
+    pub fn loop_body(&mut self, body: &Statement, labels: &[String]) -> CompileResult<bool> {
+        self.visit_statement(body)?;
+        match self.jump.take() {
+            Some(Jump::Break(None)) => Ok(false),
+            Some(Jump::Continue(None)) => Ok(true),
+            Some(Jump::Continue(Some(ref label))) if labels.contains(label) => Ok(true),
+            Some(jump) => {
+                self.jump = Some(jump);
+                Ok(false)
+            }
+            None => Ok(self.returned.is_none()),
+        }
+    }
+
+    /// Run a `for..in` or `for..of` body once per value `nth` gives, assigned
+    /// to `left` first
+    pub fn for_each<F>(&mut self, f: &ForEachStmt, labels: &[String], nth: F) -> CompileResult<()>
+        where F: Fn(usize) -> Option<Literal> {
+        let mut i = 0;
+        while let Some(value) = nth(i) {
+            i += 1;
+            match &f.left {
+                ForInit::Expression(box Identifier(id)) => self.store(&id.name, value.clone()),
+                ForInit::Expression(left) => {
+                    let feature = format!("`{}` as a loop variable in the interpreter", left.type_name());
+                    return Err(CompileError::unsupported(&feature, &left.get_loc()));
+                }
+                ForInit::Declaration(_) => {}
+            }
+            // each iteration has its own `let` or `const` variable
+            let next = self.scoped(|interpreter| {
+                if let ForInit::Declaration(declaration) = &f.left {
+                    for declarator in declaration.declarations.iter() {
+                        if let box Statement::VariableDeclarator(v) = declarator {
+                            interpreter.declare_variable(declaration, v, value.clone());
+                        }
+                    }
+                }
+                interpreter.loop_body(&f.body, labels)
+            })?;
+            if !next {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// `for..in` visits the indexes of arrays and strings and the property names of objects
+    pub fn for_in(&mut self, f: &ForEachStmt, labels: &[String]) -> CompileResult<()> {
+        let keys = match self.evaluate(&f.right)? {
+            Literal::ObjectLiteral(object) => object.borrow().keys(),
+            Literal::ArrayLiteral(array) => (0..array.borrow().len()).map(|i| i.to_string()).collect(),
+            Literal::StringLiteral(s) => (0..s.encode_utf16().count()).map(|i| i.to_string()).collect(),
+            _ => vec![],
+        };
+        self.for_each(f, labels, |i| keys.get(i).cloned().map(Literal::StringLiteral))
+    }
+
+    /// `for..of` visits the items of arrays, including the ones pushed while
+    /// iterating, and the characters of strings
+    pub fn for_of(&mut self, f: &ForEachStmt, labels: &[String]) -> CompileResult<()> {
+        match self.evaluate(&f.right)? {
+            Literal::ArrayLiteral(array) => self.for_each(f, labels, |i| array.borrow().get(i).cloned()),
+            Literal::StringLiteral(s) => {
+                let characters: Vec<char> = s.chars().collect();
+                self.for_each(f, labels, |i| characters.get(i).map(|c| Literal::StringLiteral(c.to_string())))
+            }
+            value => Err(CompileError::runtime(&format!("TypeError: {} is not iterable", value), &f.right.get_loc())),
+        }
+    }
+
+    /// Arguments are evaluated by the caller, from left to right
+    fn evaluate_all(&mut self, expressions: &[Box<Expression>]) -> CompileResult<Vec<Literal>> {
+        let mut values = vec![];
+        for expression in expressions.iter() {
+            values.push(self.evaluate(expression)?);
+        }
+        Ok(values)
+    }
+
+    /// Missing arguments are undefined, `print` and `console.log` separate several
+    /// values with spaces and end the line
+    fn call_function(&mut self, closure: &Rc<Closure>, this: Literal, arguments: Vec<Literal>) -> CompileResult<Literal> {
+        if closure.prints {
+            let values: Vec<String> = arguments.iter().map(Literal::inspect).collect();
+            self.out.push_str(&values.join(" "));
+            self.out.push('\n');
+            return Ok(Literal::Undefined);
+        }
+        let f = &closure.function;
+        let mut vars = HashMap::new();
+        if f.has_own_this() {
+            vars.insert(THIS.to_string(), this);
+        }
+        if f.binds_own_name() {
+            vars.insert(f.name.clone(), Literal::FunctionLiteral(closure.clone()));
+        }
+        for (i, param) in f.params.iter().enumerate() {
+            vars.insert(param.name.clone(), arguments.get(i).cloned().unwrap_or(Literal::Undefined));
+        }
+
+        self.frames.push(Rc::new(Scope { vars: RefCell::new(vars), parent: closure.scope.clone() }));
+        self.hoist_vars(&f.body.body);
+        let result = self.run_block(&f.body.body);
+        self.frames.pop();
+        result?;
+        Ok(self.returned.take().unwrap_or(Literal::Undefined))
+    }
+}
+
+/// `print` or `console.log` as a value, an arrow so it's not a constructor
+fn print_function(name: &str, loc: &Loc) -> Literal {
+    let body = BlockStmt { body: vec![], loc: loc.clone() };
+    let function = Function { name: name.to_string(), params: vec![], body, kind: FunctionKind::Arrow, loc: loc.clone() };
+    Literal::FunctionLiteral(Rc::new(Closure { function, scope: None, prints: true }))
+}
+
+/// The function `callee` evaluated to
+fn function(value: Literal, callee: &Expression, expected: &str) -> CompileResult<Rc<Closure>> {
+    match value {
+        Literal::FunctionLiteral(function) => Ok(function),
+        _ => Err(not_a(callee, expected)),
+    }
+}
 
-        indexes_to_remove.iter().for_each(|i| { ast_mut.remove(*i); });
+fn not_a(callee: &Expression, expected: &str) -> CompileError {
+    let name = match callee {
+        Identifier(id) => id.name.clone(),
+        MemberExpression(MemberExp { object: box Identifier(object), property: box Identifier(property), computed: false, .. }) => {
+            format!("{}.{}", object.name, property.name)
+        }
+        _ => "expression".to_string(),
+    };
+    CompileError::runtime(&format!("TypeError: {} is not {}", name, expected), &callee.get_loc())
+}
 
-        ast.iter().enumerate().for_each(|(i, statement)| {
-            token_root.visit_statement(statement);
-        });
+/// `{name, message}`, what `new TypeError(message)` would give
+pub fn new_error(name: &str, message: &str) -> Literal {
+    let mut properties = Properties::default();
+    properties.insert("name".to_string(), Literal::StringLiteral(name.to_string()));
+    properties.insert("message".to_string(), Literal::StringLiteral(message.to_string()));
+    Literal::ObjectLiteral(Rc::new(RefCell::new(properties)))
+}
 
-        token_root
+/// The value a `catch` gets for an error, runtime errors like `TypeError: x is not
+/// a function` become error objects, the other errors can't be caught
+pub fn caught(err: &CompileError) -> Option<Literal> {
+    match err {
+        CompileError::Thrown { value, .. } => Some(value.clone()),
+        CompileError::Runtime { message, .. } => Some(match message.find(": ") {
+            Some(i) => new_error(&message[..i], &message[i + 2..]),
+            None => new_error("Error", message),
+        }),
+        _ => None,
     }
-}
\ No newline at end of file
+}
+
+/// `this` can't be a variable name, the frames store it as one
+const THIS: &'static str = "this";
+
+/// The index a property key names, `a["1"]` is `a[1]`, javascript indexes are
+/// below 2^32 - 1
+fn array_index(key: &str) -> Option<usize> {
+    key.parse::<usize>().ok().filter(|i| i.to_string() == key && *i < MAX_INDEX)
+}
+
+/// 2^32 - 1, the largest javascript array length
+const MAX_INDEX: usize = 4294967295;
+
+/// Arrays store every item, they grow up to 2^24 items, longer ones throw a
+/// RangeError like `ARRAY_MAX_LENGTH` of the C runtime
+const MAX_ARRAY_LENGTH: usize = 1 << 24;
+
+/// The character at UTF-16 code unit `i` like javascript, a half of a character
+/// outside the BMP can't be kept in a `String` and reads as U+FFFD, as node prints it
+fn string_at(s: &str, i: usize) -> Option<String> {
+    let mut unit = 0;
+    for c in s.chars() {
+        unit += c.len_utf16();
+        if i < unit {
+            return Some(if c.len_utf16() == 1 { c.to_string() } else { '\u{FFFD}'.to_string() });
+        }
+    }
+    None
+}
+
+/// Set property `key` of `object` at `loc`, the properties of primitive values
+/// are silently dropped
+fn set_property(object: &Literal, key: &str, value: Literal, loc: &Loc) -> CompileResult<()> {
+    match object {
+        Literal::ObjectLiteral(object) => {
+            object.borrow_mut().insert(key.to_string(), value);
+        }
+        Literal::ArrayLiteral(array) => set_item(&mut array.borrow_mut(), key, value, loc)?,
+        Literal::NullLiteral | Literal::Undefined => {
+            let message = format!("TypeError: Cannot set properties of {} (setting '{}')", object, key);
+            return Err(CompileError::runtime(&message, loc));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Writing past the end fills the gap with undefined, `length` truncates or
+/// extends the array
+fn set_item(array: &mut Vec<Literal>, key: &str, value: Literal, loc: &Loc) -> CompileResult<()> {
+    let invalid_length = || CompileError::runtime("RangeError: Invalid array length", loc);
+    if let Some(i) = array_index(key) {
+        if i >= MAX_ARRAY_LENGTH {
+            return Err(invalid_length());
+        }
+        if i >= array.len() {
+            array.resize(i + 1, Literal::Undefined);
+        }
+        array[i] = value;
+    } else if key == "length" {
+        let n = value.to_number();
+        if n < 0.0 || n.fract() != 0.0 || n > MAX_ARRAY_LENGTH as f64 {
+            return Err(invalid_length());
+        }
+        array.resize(n as usize, Literal::Undefined);
+    }
+    Ok(())
+}
+
+/// `push` returns the new length, `pop` the removed item
+fn array_method(array: &mut Vec<Literal>, name: &str, arguments: Vec<Literal>, loc: &Loc) -> CompileResult<Literal> {
+    match name {
+        "push" => {
+            if array.len() + arguments.len() > MAX_ARRAY_LENGTH {
+                return Err(CompileError::runtime("RangeError: Invalid array length", loc));
+            }
+            array.extend(arguments);
+            Ok(Literal::NumericLiteral(array.len() as f64))
+        }
+        _ => Ok(array.pop().unwrap_or(Literal::Undefined)),
+    }
+}
@@ -1,32 +1,185 @@
-use crate::token::token::Token;
-use crate::token::token::Token::*;
-use crate::token::token::Operator::*;
-use crate::token::token::Operator;
-use crate::token::token::Literal;
-use std::error::Error;
-use crate::token::token::Literal::NullLiteral;
-
 pub mod interpreter;
 pub mod interpret_visitor;
 
-pub fn solve_postfix(postfix_expression: &mut Vec<Token>) -> Literal {
-    let mut postfix_expression = postfix_expression.clone();
-    let mut stack_lit:Vec<Literal> = vec![];
-    let mut stack_op:Vec<Operator> = vec![];
-    postfix_expression.reverse();
-
-    while !postfix_expression.is_empty() {
-        if let Some(LiteralToken(lit)) = postfix_expression.last() {
-            stack_lit.push(lit.clone());
-            postfix_expression.pop();
-        };
-
-        if let Some(OperatorToken(op)) = postfix_expression.last() {
-            let a = stack_lit.pop().expect("stack should not be empty");
-            let b = stack_lit.pop().expect("stack should not be empty");
-            stack_lit.push(op.solve(&a, &b));
-            postfix_expression.pop();
-        };
-    };
-    stack_lit.pop().expect("stack should not be empty")
-}
\ No newline at end of file
+/// Estree node types the interpreter evaluates, see `check`
+pub const SUPPORTED_NODES: &[&'static str] = &[
+    "BlockStatement", "ExpressionStatement", "VariableDeclaration", "VariableDeclarator",
+    "FunctionDeclaration", "ReturnStatement", "IfStatement", "SwitchStatement", "SwitchCase",
+    "WhileStatement", "ForStatement", "DoWhileStatement", "ForInStatement", "ForOfStatement",
+    "BreakStatement", "ContinueStatement", "LabeledStatement",
+    "ThrowStatement", "TryStatement",
+    "NumericLiteral", "StringLiteral", "BooleanLiteral", "NullLiteral", "Identifier",
+    "BinaryExpression", "UnaryExpression", "LogicalExpression", "AssignmentExpression", "UpdateExpression", "CallExpression",
+    "MemberExpression", "NewExpression", "ThisExpression", "FunctionExpression",
+    "ArrowFunctionExpression", "ArrayExpression", "ObjectExpression",
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::Options;
+
+    fn output(source: &str) -> String {
+        crate::interpret(&crate::parse(source).unwrap(), &Options::default()).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        crate::interpret(&crate::parse(source).unwrap(), &Options::default()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn should_bind_this_to_the_constructed_object() {
+        let source = "function P(nom) { this.nom = nom; }\nfunction Q() { return new P(\"q\"); }\n\
+                      var p = new P(\"Rand\");\nprint(p.nom, new Q().nom);";
+        assert_eq!(output(source), "Rand q\n");
+    }
+
+    #[test]
+    fn should_call_function_values_with_their_this() {
+        let source = "function Box(v) {\n  var set = () => { this.v = v; };\n  set();\n}\n\
+                      var b = new Box(4);\nb.get = function () { return this.v; };\nvar twice = x => x + x;\n\
+                      print(b.get(), twice(b.v), twice);";
+        assert_eq!(output(source), "4 8 [Function (anonymous)]\n");
+    }
+
+    #[test]
+    fn should_give_a_duplicate_parameter_the_last_argument() {
+        let source = "function f(a, a) { return a; }\nvar g = function (b, c, b) { return [b, c]; };\n\
+                      function h(x, x) { return () => x; }\nprint(f(1, 2), f(1), g(1, 2, 3), h(4, 5)());";
+        assert_eq!(output(source), "2 undefined [ 3, 2 ] 5\n");
+    }
+
+    #[test]
+    fn should_provide_the_builtins() {
+        let source = "console.log(Infinity + 1, -Infinity, NaN, typeof NaN, NaN == NaN, undefined);";
+        assert_eq!(output(source), "Infinity -Infinity NaN number false undefined\n");
+    }
+
+    #[test]
+    fn should_use_print_and_console_log_as_values() {
+        let source = "var log = console.log;\nlog(3);\nvar print = console.log;\nfunction f(p) { p(\"in f\"); }\nf(print);\n\
+                      print(typeof print, typeof console, log);";
+        assert_eq!(output(source), "3\nin f\nfunction object [Function: log]\n");
+    }
+
+    #[test]
+    fn should_print_objects_like_node() {
+        let source = "function P(nom) { this.nom = nom; }\nprint(new P(\"x\"), {}, { a: 1, \"b-c\": [\"s\", { d: null }] });";
+        assert_eq!(output(source), "P { nom: 'x' } {} { a: 1, 'b-c': [ 's', { d: null } ] }\n");
+    }
+
+    #[test]
+    fn should_print_cycles_like_node() {
+        let source = "var o = {};\no.self = o;\nvar a = [1];\na.push(a);\nvar q = { a: {} };\nq.a.b = q.a;\nq.c = q;\n\
+                      print(o, a, q, \"\" + a);";
+        assert_eq!(output(source), "<ref *1> { self: [Circular *1] } <ref *1> [ 1, [Circular *1] ] \
+                                    <ref *2> { a: <ref *1> { b: [Circular *1] }, c: [Circular *2] } 1,\n");
+    }
+
+    #[test]
+    fn should_quote_limit_and_break_like_node() {
+        let source = "var list = [];\nfor (var i = 1; i < 28; i++) { list.push(i); }\n\
+                      print({ s: \"it's\\n\", z: -0, deep: { a: { b: { c: 1 } } }, list: list });";
+        assert_eq!(output(source), "{\n  s: \"it's\\n\",\n  z: -0,\n  deep: { a: { b: [Object] } },\n  list: [\n\
+                                    \x20    1,  2,  3,  4,  5,  6,  7,  8,\n\
+                                    \x20    9, 10, 11, 12, 13, 14, 15, 16,\n\
+                                    \x20   17, 18, 19, 20, 21, 22, 23, 24,\n\
+                                    \x20   25, 26, 27\n  ]\n}\n");
+    }
+
+    #[test]
+    fn should_count_utf16_code_units_in_strings() {
+        let source = "var s = \"é😀a\";\nvar n = 0;\nfor (var c of s) { n = n + 1; }\n\
+                      print(s.length, s[0], s[1], s[3], s[4], n, \"abc\"[1]);";
+        assert_eq!(output(source), "4 é \u{FFFD} a undefined 3 b\n");
+    }
+
+    #[test]
+    fn should_index_arrays_and_print_them_like_node() {
+        let source = "var a = [1, 'b', , 3];\na[5] = a.push(4);\nprint(a, a.length, a.pop(), a[\"1\"], []);";
+        assert_eq!(output(source), "[ 1, 'b', undefined, 3, 4 ] 6 5 b []\n");
+    }
+
+    #[test]
+    fn should_throw_a_range_error_for_huge_arrays() {
+        let source = "var a = [1];\ntry { a[2000000000] = 1; } catch (e) { print(e.name, e.message); }\n\
+                      a[4294967295] = 2;\na.length = 3;\nprint(a);";
+        assert_eq!(output(source), "RangeError Invalid array length\n[ 1, undefined, undefined ]\n");
+        assert_eq!(error("var a = [];\na.length = -1;"), "2:1: RangeError: Invalid array length");
+    }
+
+    #[test]
+    fn should_run_every_kind_of_loop() {
+        let source = "var s = 0;\nfor (var i = 0; i < 4; i = i + 1) { if (i == 1) { continue; } s = s + i; }\n\
+                      do { s = s + 10; } while (false);\nvar o = { a: 1, b: 2 };\nfor (var k in o) { s = s + o[k]; }\n\
+                      for (var x of [7, 8, 9]) { if (x == 9) { break; } s = s + x; }\nprint(s);";
+        assert_eq!(output(source), "33\n");
+    }
+
+    #[test]
+    fn should_update_variables_and_properties() {
+        let source = "var o = {x: 1};\nvar a = [1];\nvar i = 0;\nvar s = \"5\";\no.x += 2;\na[i++] *= 10;\ns++;\n\
+                      var t = \"a\";\nt += 1;\nprint(o.x, a, i, s, t, i--, --i, o.x++, ++o.x);";
+        assert_eq!(output(source), "3 [ 10 ] 1 6 a1 1 -1 3 5\n");
+        assert_eq!(error("var n = null;\nn.x += 1;"), "2:1: TypeError: Cannot read properties of null (reading 'x')");
+    }
+
+    #[test]
+    fn should_jump_to_labels() {
+        let source = "var n = 0;\nouter: for (var i = 0; i < 4; i = i + 1) {\n\
+                      for (var j = 0; j < 4; j = j + 1) { if (j == 2) { continue outer; } if (i == 3) { break outer; } n = n + 1; }\n}\n\
+                      block: { n = n + 10; break block; n = 0; }\nprint(n);";
+        assert_eq!(output(source), "16\n");
+    }
+
+    #[test]
+    fn should_catch_exceptions_and_run_finally_blocks() {
+        let source = "var o = null;\nfunction f(x) {\n  try { if (x) { return 1; } throw 2; }\n  \
+                      catch (e) { return e; }\n  finally { print(0); }\n}\nprint(f(true));\nprint(f(false));\n\
+                      try { o.x; } catch (e) { print(e.name); }";
+        assert_eq!(output(source), "0\n1\n0\n2\nTypeError\n");
+        assert_eq!(error("throw 'oops';"), "1:1: Uncaught oops");
+    }
+
+    #[test]
+    fn should_scope_let_and_const_to_their_block() {
+        let source = "let x = 1;\nfunction f() {\n  var v = x;\n  if (v) { let x = 2; v = v + x; }\n  return v;\n}\n\
+                      { const x = 3; print(x); }\nvar fs = [];\nfor (let i = 0; i < 2; i = i + 1) { fs.push(() => i); }\n\
+                      try { throw 4; } catch (x) { print(x); }\nprint(x, f(), fs[0](), fs[1]());";
+        assert_eq!(output(source), "3\n4\n1 3 0 1\n");
+        assert_eq!(error("const k = 1;\nk = 2;"), "2:1: assignment to constant `k`");
+    }
+
+    #[test]
+    fn should_print_booleans_null_and_undefined_like_node() {
+        assert_eq!(output("var t = true;\nprint(t, null, undefined);"), "true null undefined\n");
+    }
+
+    #[test]
+    fn should_compare_with_loose_and_strict_equality() {
+        let source = "var o = {};\nprint(1 == \"1\", \"1\" === 1, null == undefined, null == 0, true == \"1\", \
+                      \" 0x10 \" == 16, \"1a\" != 1, o == o, o === {}, [1, null] == \"1,\");";
+        assert_eq!(output(source), "true false true false true true true true false true\n");
+    }
+
+    #[test]
+    fn should_run_the_cases_from_the_matching_one_until_a_break() {
+        let source = "function f(x) {\n  var s = \"\";\n  switch (x) {\n    case 1: s += \"a\";\n    case \"1\": s += \"b\"; break;\n\
+                      \x20   default: s += \"d\";\n    case 3: s += \"c\";\n  }\n  return s;\n}\n\
+                      print(f(1), f(\"1\"), f(2), f(3));\nfor (var i = 0; i < 3; i++) { switch (i) { case 1: continue; } print(i); }\n\
+                      switch (0) { case 1: print(1); }\nswitch (0) { case 0: print(g()); function g() { return \"g\"; } }";
+        assert_eq!(output(source), "ab b dc c\n0\n2\ng\n");
+    }
+
+    #[test]
+    fn should_apply_arithmetic_bitwise_and_type_operators() {
+        let source = "function P() {}\nvar p = new P();\nvar o = {a: 1};\nprint(-7 % 3, 2 ** 10, ~5, 1 << 31, -16 >>> 28, \
+                      null >= 0, \"a\" in o, p instanceof P, o instanceof P, typeof o, typeof missing, -\"3\");";
+        assert_eq!(output(source), "-1 1024 -6 -2147483648 15 true true true false object undefined -3\n");
+    }
+
+    #[test]
+    fn should_return_the_deciding_operand_of_logical_operators() {
+        let source = "var missing;\nprint(0 || \"a\", 1 && null, null ?? 2, 0 ?? 2, 0 && missing.x);";
+        assert_eq!(output(source), "a null 2 0 0\n");
+    }
+}
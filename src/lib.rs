@@ -0,0 +1,139 @@
+#![feature(box_patterns)]
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use std::collections::HashMap;
+
+use crate::asm_compile::asm_writer::ASMWriter;
+use crate::asm_compile::Register;
+use crate::ast::statement::Program;
+use crate::c_compile::c_writer::CWriter;
+use crate::check::Backend;
+use crate::diagnostic::Diagnostic;
+use crate::error::CompileResult;
+use crate::interpret::interpreter::Interpreter;
+
+pub mod ast;
+pub mod file_util;
+pub mod c_compile;
+pub mod visitor;
+pub mod writer;
+pub mod interpret;
+pub mod asm_compile;
+pub mod token;
+pub mod parser;
+pub mod error;
+pub mod diagnostic;
+pub mod check;
+pub mod scope;
+
+/// The C library the generated sources are compiled with, one constant per file
+pub mod runtime {
+    // this is the genrated rust code that contains c_datalib as rust const
+    include!(concat!(env!("OUT_DIR"), "/c_lib.rs"));
+}
+
+/// Compilation settings shared by every backend
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Reject unsupported constructs before generating anything, see `check`
+    pub check: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { check: true }
+    }
+}
+
+/// A generated source along with the warnings raised while generating it
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub source: String,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Parse a javascript source with the built-in parser
+pub fn parse(js_source: &str) -> CompileResult<Program> {
+    Ok(parser::parse(js_source)?.into_program())
+}
+
+/// Read an estree json, as produced by babylon
+pub fn parse_estree(json: &str) -> CompileResult<Program> {
+    Ok(file_util::deserialize_json(json)?.into_program())
+}
+
+/// Generate a C source, it has to be compiled along with the `runtime` files
+pub fn compile_to_c(program: &Program, options: &Options) -> CompileResult<Generated> {
+    if options.check {
+        check::check(&program.body, Backend::C)?;
+    }
+    let mut body = program.body.clone();
+    scope::resolve(&mut body, true)?;
+
+    let mut writer = CWriter {
+        out: &mut String::new(),
+        warnings: vec![],
+        labels: 0,
+        jumps: vec![],
+        labeled_loop: None,
+        closures: vec![],
+        functions: vec![],
+        global_cells: vec![],
+    };
+    writer.visit_program_root(body)?;
+
+    Ok(Generated {
+        source: writer.out.clone(),
+        warnings: writer.warnings,
+    })
+}
+
+/// Generate an x86_64 assembly source
+pub fn compile_to_asm(program: &Program, options: &Options) -> CompileResult<Generated> {
+    if options.check {
+        check::check(&program.body, Backend::Asm)?;
+    }
+    let mut body = program.body.clone();
+    scope::resolve(&mut body, false)?;
+
+    let mut writer = ASMWriter {
+        out: &mut String::new(),
+        reg: Register::RAX,
+        functions: HashMap::new(),
+        vars: HashMap::new(),
+        main: vec![],
+    };
+    writer.build(body)?;
+
+    Ok(Generated {
+        source: writer.out.clone(),
+        warnings: vec![],
+    })
+}
+
+/// Run the program and return what it printed
+pub fn interpret(program: &Program, options: &Options) -> CompileResult<String> {
+    if options.check {
+        check::check(&program.body, Backend::Interpreter)?;
+    }
+    let mut body = program.body.clone();
+    scope::resolve(&mut body, false)?;
+
+    Ok(Interpreter::run(body)?.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Options;
+
+    #[test]
+    fn should_generate_c_in_memory() {
+        let program = crate::parse("var x = 1;\nprint(x);").unwrap();
+        let generated = crate::compile_to_c(&program, &Options::default()).unwrap();
+        assert!(generated.source.contains("int main()"));
+        assert!(generated.warnings.is_empty());
+    }
+}
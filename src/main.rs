@@ -1,36 +1,22 @@
-#![feature(box_patterns)]
-#![feature(box_syntax)]
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_json;
-
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::Write;
+use std::process;
 use std::process::Command;
-use std::string::FromUtf8Error;
 
 use clap::App;
 use clap::Arg;
+use clap::ArgMatches;
 
-use crate::asm_compile::asm_writer::ASMWriter;
-use crate::asm_compile::Register;
-use crate::ast::statement::RootStatement;
-use crate::c_compile::c_writer::CWriter;
-use crate::interpret::interpreter::Interpreter;
-use std::collections::HashMap;
-
-pub mod ast;
-pub mod file_util;
-pub mod c_compile;
-pub mod visitor;
-pub mod writer;
-pub mod interpret;
-pub mod asm_compile;
-pub mod token;
-
+use rjsc::diagnostic::Diagnostic;
+use rjsc::diagnostic::Renderer;
+use rjsc::error::CompileError;
+use rjsc::error::CompileResult;
+use rjsc::file_util;
+use rjsc::runtime::*;
+use rjsc::Generated;
+use rjsc::Options;
 
 const DATABOX_H_PATH: &'static str = "databox.h";
 const DATABOX_C_PATH: &'static str = "databox.c";
@@ -41,16 +27,13 @@ const DICT_C_PATH: &'static str = "dict.c";
 const KEYVAL_H_PATH: &'static str = "keyval.h";
 const KEYVAL_C_PATH: &'static str = "keyval.c";
 
-/// this is the genrated rust code that contains c_datalib as rust const
-include!(concat!(env!("OUT_DIR"), "/c_lib.rs"));
-
 fn main() {
     let matches = App::new("Simple rust javascript to C compiler")
         .version("1.0")
         .author("Paul D. <paul.delafosse.etu@univ-lille.fr>")
         .about("A simple Javascipt to C compiler")
         .arg(Arg::with_name("SOURCE")
-            .help("Name of the target javascript file")
+            .help("Name of the target javascript file, or of an estree json file ('-' reads it from stdin)")
             .required(true)
             .index(1))
         .arg(Arg::with_name("out")
@@ -64,10 +47,20 @@ fn main() {
             .long("indent")
             .help("Indent the generated C source with GNU indent"))
         .arg(Arg::with_name("keep-ast")
-            .help("let the estree json file in place after compilation")
+            .help("let the estree json file babylon generates in place after compilation")
             .long("keep-ast")
             .short("t")
-            .long("keep-ast")
+            .requires("babylon")
+            .required(false))
+        .arg(Arg::with_name("from-ast")
+            .help("read a pre-generated estree json file instead of parsing the javascript source")
+            .long("from-ast")
+            .short("j")
+            .required(false))
+        .arg(Arg::with_name("babylon")
+            .help("parse the javascript source with babylon instead of the built-in parser")
+            .long("babylon")
+            .short("b")
             .required(false))
         .arg(Arg::with_name("verbose")
             .short("v")
@@ -94,8 +87,30 @@ fn main() {
             .short("p")
             .help("interpret source file")
             .required(false))
+        .arg(Arg::with_name("color")
+            .long("color")
+            .help("colorize warnings and errors")
+            .required(false))
         .get_matches();
 
+    let source = matches.value_of("SOURCE").unwrap();
+    let from_json = matches.is_present("from-ast") || file_util::is_estree_path(source);
+
+    // the javascript source is read again here so that diagnostics can quote it
+    let js_source = if from_json { None } else { fs::read_to_string(source).ok() };
+    let renderer = Renderer {
+        filename: source,
+        source: js_source.as_deref(),
+        color: matches.is_present("color"),
+    };
+
+    if let Err(err) = run(&matches, &renderer) {
+        err.errors().iter().for_each(|err| eprint!("{}", renderer.render(&Diagnostic::from(*err))));
+        process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches, renderer: &Renderer) -> CompileResult<()> {
     // Collect command line args
     let source = matches.value_of("SOURCE");
     let asm = matches.is_present("asm-gen");
@@ -104,119 +119,115 @@ fn main() {
     let indent = matches.is_present("indent");
     let keep_c = matches.is_present("keep-source");
     let keep_ast = matches.is_present("keep-ast");
+    let from_ast = matches.is_present("from-ast");
+    let babylon = matches.is_present("babylon");
     let filename = matches.value_of("out").unwrap_or("out");
     let debug = matches.is_present("debug");
+    let options = Options::default();
 
 
-    let json_estree = generate_estree(source.unwrap())
-        .expect("UTF-8 Error while reading json estree generated with babylon.");
+    let source = source.unwrap();
+    // the built-in parser has no estree json to keep
+    let (program, json_estree) = if from_ast || file_util::is_estree_path(source) {
+        let json_estree = file_util::read_estree(source)?;
+        (rjsc::parse_estree(json_estree.as_str())?, json_estree)
+    } else if babylon {
+        let json_estree = generate_estree(source)?;
+        (rjsc::parse_estree(json_estree.as_str())?, json_estree)
+    } else {
+        (rjsc::parse(fs::read_to_string(source)?.as_str())?, String::new())
+    };
 
 
-    // Create a babylon json file
+    // Create an estree json file
     if keep_ast {
         let estree_filename = format!("{}.json", filename);
-        let estree_file = File::create(estree_filename);
-        estree_file.unwrap().write_all(json_estree.clone().as_bytes())
-                   .expect("Error writing AST to file");
+        let mut estree_file = File::create(estree_filename)?;
+        estree_file.write_all(json_estree.as_bytes())?;
     }
 
 
-    let root_statement: RootStatement = file_util::deserialize_json(json_estree.as_str());
-    let program_root = root_statement.get_program_root();
-    let program_root = program_root.expect("Error parsing Json AST");
-
     if interpret {
-        Interpreter::run(program_root.clone());
-    }
-
-    if asm  && !interpret {
-        let mut writer = ASMWriter {
-            out: &mut String::new(),
-            reg: Register::RAX,
-            functions: HashMap::new(),
-            vars: HashMap::new(),
-            main: vec![],
-        };
-
-        writer.build(program_root);
-        write_asm_to_file(filename, writer).expect(format!("Error writing {}", filename).as_str());
-
-    } else if !interpret {
-        let mut writer = CWriter {
-            out: &mut "".to_string(),
-        };
-
-        copy_c_lib();
-
+        print!("{}", rjsc::interpret(&program, &options)?);
+    } else if asm {
+        let generated = rjsc::compile_to_asm(&program, &options)?;
+        report_warnings(renderer, &generated);
+        write_asm_to_file(filename, &generated.source)?;
+    } else {
         // build c source from estree
-        writer.visit_program_root(program_root);
-        write_to_file(filename, writer).expect(format!("Error writing {}", filename).as_str());
-        compile_libs(filename);
-        compile(filename, verbose, debug);
+        let generated = rjsc::compile_to_c(&program, &options)?;
+        report_warnings(renderer, &generated);
+
+        copy_c_lib()?;
+        write_to_file(filename, &generated.source)?;
+        compile_libs(filename)?;
+        compile(filename, verbose, debug)?;
 
         if indent {
             let mut gnu_indent = Command::new("indent");
             gnu_indent.arg(format!("{}.c", filename));
-            gnu_indent.status().expect("Error while indenting file, is GNU indent installed?");
+            run_tool("indent", &mut gnu_indent)?;
         };
 
 
-        clean_filesystem(keep_c, filename)
-            .expect("Something went wrong while removing generated sources");
+        clean_filesystem(keep_c, filename)?;
     }
+    Ok(())
+}
+
+fn report_warnings(renderer: &Renderer, generated: &Generated) {
+    generated.warnings.iter().for_each(|warning| eprint!("{}", renderer.render(warning)));
 }
 
 
 // create the c_library files in the current directory
-fn copy_c_lib() {
-    let c_lib_file_error = "Error writing the standard library";
-    let f_databox_h = File::create(DATABOX_H_PATH);
-    let f_databox_c = File::create(DATABOX_C_PATH);
-    let f_print_h = File::create(PRINT_H_PATH);
-    let f_print_c = File::create(PRINT_C_PATH);
-    let f_dict_h = File::create(DICT_H_PATH);
-    let f_dict_c = File::create(DICT_C_PATH);
-    let f_keyval_h = File::create(KEYVAL_H_PATH);
-    let f_keyval_c = File::create(KEYVAL_C_PATH);
-
-    f_databox_h.unwrap().write_all(DATABOX_H.as_bytes()).expect(c_lib_file_error);
-    f_databox_c.unwrap().write_all(DATABOX_C.as_bytes()).expect(c_lib_file_error);
-    f_print_h.unwrap().write_all(PRINT_H.as_bytes()).expect(c_lib_file_error);
-    f_print_c.unwrap().write_all(PRINT_C.as_bytes()).expect(c_lib_file_error);
-    f_dict_h.unwrap().write_all(DICT_H.as_bytes()).expect(c_lib_file_error);
-    f_dict_c.unwrap().write_all(DICT_C.as_bytes()).expect(c_lib_file_error);
-    f_keyval_h.unwrap().write_all(KEYVAL_H.as_bytes()).expect(c_lib_file_error);
-    f_keyval_c.unwrap().write_all(KEYVAL_C.as_bytes()).expect(c_lib_file_error);
+fn copy_c_lib() -> Result<(), io::Error> {
+    File::create(DATABOX_H_PATH)?.write_all(DATABOX_H.as_bytes())?;
+    File::create(DATABOX_C_PATH)?.write_all(DATABOX_C.as_bytes())?;
+    File::create(PRINT_H_PATH)?.write_all(PRINT_H.as_bytes())?;
+    File::create(PRINT_C_PATH)?.write_all(PRINT_C.as_bytes())?;
+    File::create(DICT_H_PATH)?.write_all(DICT_H.as_bytes())?;
+    File::create(DICT_C_PATH)?.write_all(DICT_C.as_bytes())?;
+    File::create(KEYVAL_H_PATH)?.write_all(KEYVAL_H.as_bytes())?;
+    File::create(KEYVAL_C_PATH)?.write_all(KEYVAL_C.as_bytes())?;
+    Ok(())
 }
 
 /// Write the generated source to file with an optional filename
-fn write_to_file(filename: &str, pretty: CWriter) -> Result<(), io::Error> {
+fn write_to_file(filename: &str, c_source: &str) -> Result<(), io::Error> {
     let filename_c = format!("{}.c", filename);
     let mut file = File::create(filename_c)?;
-    file.write_all(pretty.out.as_bytes())?;
+    file.write_all(c_source.as_bytes())?;
     Ok(())
 }
 
 /// Write the generated ASM source to file with an optional filename
-fn write_asm_to_file(filename: &str, pretty: ASMWriter) -> Result<(), io::Error> {
+fn write_asm_to_file(filename: &str, asm_source: &str) -> Result<(), io::Error> {
     let filename_s = format!("{}.s", filename);
     let mut file = File::create(filename_s)?;
-    file.write_all(pretty.out.as_bytes())?;
+    file.write_all(asm_source.as_bytes())?;
     Ok(())
 }
 
 
 /// Get a string from babylon stdout
-fn generate_estree(js_source: &str) -> Result<String, FromUtf8Error> {
+fn generate_estree(js_source: &str) -> CompileResult<String> {
     let mut babylon_cmd = Command::new("babylon");
     babylon_cmd.arg(js_source);
-    let estree = babylon_cmd.output().expect("Unable to parse JS source using babylon");
-    let out = String::from_utf8(estree.stdout);
-    out
+    let estree = babylon_cmd.output().map_err(|err| {
+        CompileError::tool("babylon", &format!("{}, is it installed? (use --from-ast to read a json estree instead)", err))
+    })?;
+
+    if !estree.status.success() {
+        return Err(CompileError::tool("babylon", &String::from_utf8_lossy(&estree.stderr)));
+    }
+
+    String::from_utf8(estree.stdout)
+        .map_err(|err| CompileError::tool("babylon", &err.to_string()))
 }
 
 /// Compile generated source with gcc, at last !
-fn compile(filename: &str, verbose: bool, debug: bool) {
+fn compile(filename: &str, verbose: bool, debug: bool) -> CompileResult<()> {
     let mut gcc_cmd = Command::new("gcc");
     gcc_cmd.arg("databox.o");
     gcc_cmd.arg("print.o");
@@ -227,11 +238,12 @@ fn compile(filename: &str, verbose: bool, debug: bool) {
     if debug { gcc_cmd.arg("-g"); };
     gcc_cmd.arg("-o");
     gcc_cmd.arg(filename);
-    gcc_cmd.status().expect("Failed to compile source");
+    gcc_cmd.arg("-lm");
+    run_tool("gcc", &mut gcc_cmd)
 }
 
 
-fn compile_libs(filename: &str) {
+fn compile_libs(filename: &str) -> CompileResult<()> {
     let mut gcc_cmd = Command::new("gcc");
     gcc_cmd.arg("-c");
     gcc_cmd.arg(DATABOX_C_PATH);
@@ -243,7 +255,17 @@ fn compile_libs(filename: &str) {
     gcc_cmd.arg(KEYVAL_C_PATH);
     gcc_cmd.arg(KEYVAL_H_PATH);
     gcc_cmd.arg(format!("{}.c", filename));
-    gcc_cmd.status().expect("Failed to compile libs");
+    run_tool("gcc", &mut gcc_cmd)
+}
+
+/// Run an external program, it must be installed and exit successfully
+fn run_tool(name: &str, cmd: &mut Command) -> CompileResult<()> {
+    let status = cmd.status().map_err(|err| CompileError::tool(name, &err.to_string()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(CompileError::tool(name, &format!("exited with {}", status)))
+    }
 }
 
 
@@ -0,0 +1,270 @@
+use crate::ast::expression::Loc;
+use crate::ast::expression::Pos;
+use crate::parser::ParseError;
+
+/// Longest punctuators first so that the lexer always takes the longest match
+const PUNCTUATORS: &[&'static str] = &[
+    ">>>=", "===", "!==", "**=", "<<=", ">>=", ">>>", "...",
+    "=>", "==", "!=", "<=", ">=", "&&", "||", "??", "++", "--",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "**", "<<", ">>",
+    "{", "}", "(", ")", "[", "]", ";", ",", "<", ">", "+", "-", "*",
+    "/", "%", "&", "|", "^", "!", "~", "?", ":", "=", ".",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexemeKind {
+    Number(f64),
+    Str(String),
+    /// Identifiers and keywords, the parser tells them apart
+    Word(String),
+    Punctuator(&'static str),
+    Eof,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lexeme {
+    pub kind: LexemeKind,
+    pub loc: Loc,
+    /// Needed for automatic semicolon insertion and postfix update operators
+    pub newline_before: bool,
+}
+
+pub struct Lexer {
+    chars: Vec<char>,
+    offset: usize,
+    line: f64,
+    column: f64,
+}
+
+impl Lexer {
+    pub fn new(source: &str) -> Self {
+        Lexer {
+            chars: source.chars().collect(),
+            offset: 0,
+            line: 1.0,
+            column: 0.0,
+        }
+    }
+
+    /// Split the whole source into lexemes, the last one is always `Eof`
+    pub fn tokenize(&mut self) -> Result<Vec<Lexeme>, ParseError> {
+        let mut lexemes = vec![];
+        loop {
+            let newline_before = self.skip_blanks()?;
+            let start = self.pos();
+            let kind = self.next_kind()?;
+            let eof = kind == LexemeKind::Eof;
+            lexemes.push(Lexeme { kind, loc: Loc { start, end: self.pos() }, newline_before });
+            if eof {
+                return Ok(lexemes);
+            }
+        }
+    }
+
+    fn next_kind(&mut self) -> Result<LexemeKind, ParseError> {
+        let c = match self.peek(0) {
+            Some(c) => c,
+            None => return Ok(LexemeKind::Eof),
+        };
+
+        if c.is_ascii_digit() || (c == '.' && self.peek(1).is_some_and(|n| n.is_ascii_digit())) {
+            self.number()
+        } else if c == '"' || c == '\'' {
+            self.string(c)
+        } else if is_word_start(c) {
+            let mut word = String::new();
+            while let Some(c) = self.peek(0).filter(|c| is_word_part(*c)) {
+                word.push(c);
+                self.bump();
+            }
+            Ok(LexemeKind::Word(word))
+        } else {
+            self.punctuator()
+        }
+    }
+
+    fn number(&mut self) -> Result<LexemeKind, ParseError> {
+        let start = self.pos();
+        let mut raw = String::new();
+
+        if self.peek(0) == Some('0') && self.peek(1).is_some_and(|x| x == 'x' || x == 'X') {
+            self.bump();
+            self.bump();
+            while let Some(c) = self.peek(0).filter(|c| c.is_ascii_hexdigit()) {
+                raw.push(c);
+                self.bump();
+            }
+            return u64::from_str_radix(&raw, 16)
+                .map(|n| LexemeKind::Number(n as f64))
+                .map_err(|_| self.error_from(start, "invalid hexadecimal number"));
+        }
+
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit() || *c == '.') {
+            raw.push(c);
+            self.bump();
+        }
+
+        if let Some(e) = self.peek(0).filter(|c| *c == 'e' || *c == 'E') {
+            raw.push(e);
+            self.bump();
+            if let Some(sign) = self.peek(0).filter(|c| *c == '+' || *c == '-') {
+                raw.push(sign);
+                self.bump();
+            }
+            while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+                raw.push(c);
+                self.bump();
+            }
+        }
+
+        if self.peek(0).is_some_and(is_word_start) {
+            return Err(self.error_from(start, "identifier starts immediately after numeric literal"));
+        }
+
+        raw.parse::<f64>()
+            .map(LexemeKind::Number)
+            .map_err(|_| self.error_from(start, &format!("invalid number `{}`", raw)))
+    }
+
+    fn string(&mut self, quote: char) -> Result<LexemeKind, ParseError> {
+        let start = self.pos();
+        let mut value = String::new();
+        self.bump();
+
+        loop {
+            match self.peek(0) {
+                None | Some('\n') => return Err(self.error_from(start, "unterminated string literal")),
+                Some(c) if c == quote => {
+                    self.bump();
+                    return Ok(LexemeKind::Str(value));
+                }
+                Some('\\') => {
+                    self.bump();
+                    let escaped = self.peek(0)
+                        .ok_or_else(|| self.error_from(start.clone(), "unterminated string literal"))?;
+                    self.bump();
+                    match escaped {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'v' => value.push('\u{b}'),
+                        '0' => value.push('\0'),
+                        // line continuation
+                        '\n' => (),
+                        'x' => value.push(self.code_point(2, &start)?),
+                        'u' => value.push(self.code_point(4, &start)?),
+                        other => value.push(other),
+                    }
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    fn code_point(&mut self, digits: usize, start: &Pos) -> Result<char, ParseError> {
+        let mut hex = String::new();
+        for _ in 0..digits {
+            if let Some(c) = self.peek(0).filter(|c| c.is_ascii_hexdigit()) {
+                hex.push(c);
+                self.bump();
+            }
+        }
+        u32::from_str_radix(&hex, 16).ok()
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.error_from(start.clone(), "invalid escape sequence"))
+    }
+
+    fn punctuator(&mut self) -> Result<LexemeKind, ParseError> {
+        let start = self.pos();
+        for punctuator in PUNCTUATORS {
+            let len = punctuator.chars().count();
+            let matches = punctuator.chars().enumerate().all(|(i, c)| self.peek(i) == Some(c));
+            if matches {
+                (0..len).for_each(|_| self.bump());
+                return Ok(LexemeKind::Punctuator(punctuator));
+            }
+        }
+        let unexpected = self.peek(0).unwrap_or(' ');
+        self.bump();
+        Err(self.error_from(start, &format!("unexpected character `{}`", unexpected)))
+    }
+
+    /// Skip whitespaces and comments, return true if a line terminator was crossed
+    fn skip_blanks(&mut self) -> Result<bool, ParseError> {
+        let mut newline = false;
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some('\n'), _) => {
+                    newline = true;
+                    self.bump();
+                }
+                (Some(c), _) if c.is_whitespace() => self.bump(),
+                (Some('/'), Some('/')) => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let start = self.pos();
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.peek(0), self.peek(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(c), _) => {
+                                newline |= c == '\n';
+                                self.bump();
+                            }
+                            (None, _) => return Err(self.error_from(start, "unterminated comment")),
+                        }
+                    }
+                }
+                _ => return Ok(newline),
+            }
+        }
+    }
+
+    fn peek(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.offset + ahead).cloned()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek(0) {
+            self.offset += 1;
+            if c == '\n' {
+                self.line += 1.0;
+                self.column = 0.0;
+            } else {
+                self.column += 1.0;
+            }
+        }
+    }
+
+    fn pos(&self) -> Pos {
+        Pos { line: self.line, column: self.column }
+    }
+
+    fn error_from(&self, start: Pos, message: &str) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            loc: Loc { start, end: self.pos() },
+        }
+    }
+}
+
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_word_part(c: char) -> bool {
+    is_word_start(c) || c.is_ascii_digit()
+}
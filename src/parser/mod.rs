@@ -0,0 +1,139 @@
+use crate::ast::expression::Loc;
+use crate::ast::statement::RootStatement;
+use crate::parser::lexer::Lexer;
+use crate::parser::parser::Parser;
+
+pub mod lexer;
+pub mod parser;
+
+/// Native javascript frontend, an alternative to babylon that builds the ast directly
+pub fn parse(source: &str) -> Result<RootStatement, ParseError> {
+    let lexemes = Lexer::new(source).tokenize()?;
+    let program = Parser::new(lexemes).parse_program()?;
+    Ok(RootStatement::Program(program))
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub loc: Loc,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::expression::ArrowBody;
+    use crate::ast::expression::Expression::*;
+    use crate::ast::statement::ForInit;
+    use crate::ast::statement::Statement::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn should_parse_with_precedence_and_locations() {
+        let root = parse("var x;\nx = 1 + (2 + 3) * 4;").unwrap().get_program_root().unwrap();
+
+        if let box ExpressionStatement(stmt) = &root[1] {
+            assert_eq!((stmt.loc.start.line, stmt.loc.start.column), (2.0, 0.0));
+            assert_eq!((stmt.loc.end.line, stmt.loc.end.column), (2.0, 20.0));
+            if let box AssignmentExpression(assign) = &stmt.expression {
+                if let box BinaryExpression(add) = &assign.right {
+                    assert_eq!(add.operator, "+");
+                    assert_eq!(add.loc.start.column, 4.0);
+                    if let box BinaryExpression(mul) = &add.right {
+                        assert_eq!(mul.operator, "*");
+                        assert!(mul.extra.is_none());
+                        if let box BinaryExpression(inner) = &mul.left {
+                            assert!(inner.has_parenthesis());
+                            assert_eq!(inner.loc.start.column, 9.0);
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+        panic!("unexpected tree {:?}", root);
+    }
+
+    #[test]
+    fn should_insert_semicolons_on_new_lines() {
+        let root = parse("var x = 1\nx++\nprint(x)").unwrap().get_program_root().unwrap();
+        assert_eq!(root.len(), 3);
+    }
+
+    #[test]
+    fn should_keep_unsupported_constructs_as_nodes() {
+        let root = parse("x = new Foo(this, [1, , 2]);").unwrap().get_program_root().unwrap();
+
+        if let box ExpressionStatement(stmt) = &root[0] {
+            if let box AssignmentExpression(assign) = &stmt.expression {
+                if let box NewExpression(new) = &assign.right {
+                    let loc = new.arguments[1].get_loc();
+                    assert_eq!(new.arguments[1].type_name(), "ArrayExpression");
+                    assert_eq!((loc.start.column, loc.end.column), (18.0, 26.0));
+                    return;
+                }
+            }
+        }
+        panic!("unexpected tree {:?}", root);
+    }
+
+    #[test]
+    fn should_parse_arrow_functions() {
+        let root = parse("f = (a, b) => (a) * b;\ng = x => { return x; };\nh = (1 + 2);").unwrap().get_program_root().unwrap();
+        let right = |i: usize| match &root[i] {
+            box ExpressionStatement(stmt) => match &stmt.expression {
+                box AssignmentExpression(assign) => assign.right.clone(),
+                other => panic!("unexpected expression {:?}", other),
+            },
+            other => panic!("unexpected statement {:?}", other),
+        };
+
+        match (*right(0), *right(1), *right(2)) {
+            (ArrowFunctionExpression(f), ArrowFunctionExpression(g), BinaryExpression(_)) => {
+                assert_eq!(f.params.len(), 2);
+                assert_eq!((f.loc.start.column, f.loc.end.column), (4.0, 21.0));
+                assert!(matches!(f.body, ArrowBody::Expression(box BinaryExpression(_))));
+                assert!(matches!(g.body, ArrowBody::Block(_)));
+            }
+            other => panic!("unexpected tree {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_parse_loop_heads() {
+        let root = parse("for (var i = 0, j; i < 2; i++) {}\nfor (k in o) {}\nfor (const x of a) x;\ndo x++; while (x < 3)")
+            .unwrap().get_program_root().unwrap();
+
+        match (&root[0], &root[1], &root[2], &root[3]) {
+            (box ForStatement(f), box ForInStatement(f_in), box ForOfStatement(f_of), box DoWhileStatement(d)) => {
+                assert!(match &f.init { Some(ForInit::Declaration(declaration)) => declaration.declarations.len() == 2, _ => false });
+                assert!(match &f_in.left { ForInit::Expression(box Identifier(id)) => id.name == "k", _ => false });
+                assert!(match &f_of.left { ForInit::Declaration(declaration) => declaration.kind == "const", _ => false });
+                assert_eq!((d.loc.start.line, d.loc.end.column), (4.0, 21.0));
+            }
+            other => panic!("unexpected tree {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_parse_try_statements() {
+        let root = parse("try { f(); } catch { g(); }\ntry {} catch (e) {} finally {}").unwrap().get_program_root().unwrap();
+        match (&root[0], &root[1]) {
+            (box TryStatement(t), box TryStatement(t_finally)) => {
+                assert!(t.handler.as_ref().unwrap().param.is_none() && t.finalizer.is_none());
+                assert_eq!(t_finally.handler.as_ref().unwrap().param.as_ref().unwrap().name, "e");
+                assert!(t_finally.finalizer.is_some());
+            }
+            other => panic!("unexpected tree {:?}", other),
+        }
+
+        assert_eq!(parse("try {}").unwrap_err().message, "expected `catch` or `finally`, found end of file");
+        assert_eq!(parse("throw\n1").unwrap_err().message, "illegal newline after `throw`");
+    }
+
+    #[test]
+    fn should_locate_syntax_errors() {
+        let err = parse("var x = 1;\nwhile (x < ) {}").unwrap_err();
+        assert_eq!((err.loc.start.line, err.loc.start.column), (2.0, 11.0));
+        assert_eq!(err.message, "unexpected `)`");
+    }
+}
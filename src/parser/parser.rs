@@ -0,0 +1,842 @@
+use crate::ast::expression::*;
+use crate::ast::statement::*;
+use crate::ast::UnsupportedNode;
+use crate::parser::lexer::Lexeme;
+use crate::parser::lexer::LexemeKind;
+use crate::parser::lexer::LexemeKind::*;
+use crate::parser::ParseError;
+
+const ASSIGNMENT_OPERATORS: &[&'static str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "**=", "<<=", ">>=", ">>>=", "&=", "|=", "^=",
+];
+
+const UNARY_OPERATORS: &[&'static str] = &["+", "-", "!", "~", "typeof", "void", "delete"];
+
+/// Recursive descent parser over the lexemes of a javascript source, the produced
+/// tree mirrors the estree babylon generates, locations included.
+pub struct Parser {
+    lexemes: Vec<Lexeme>,
+    cursor: usize,
+    /// `in` is not an operator in the head of a `for`, it starts a `for..in`
+    no_in: bool,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl Parser {
+    pub fn new(lexemes: Vec<Lexeme>) -> Self {
+        Parser { lexemes, cursor: 0, no_in: false }
+    }
+
+    pub fn parse_program(&mut self) -> ParseResult<Program> {
+        let mut body = vec![];
+        while !self.at_eof() {
+            body.push(Box::new(self.statement()?));
+        }
+        Ok(Program { body })
+    }
+
+    // statement
+
+    fn statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        match self.peek().kind.clone() {
+            Punctuator("{") => Ok(Statement::BlockStatement(self.block()?)),
+            Punctuator(";") => {
+                self.advance();
+                Ok(Statement::EmptyStatement)
+            }
+            Word(ref word) => match word.as_str() {
+                "var" | "let" | "const" => {
+                    let declaration = self.variable_declaration()?;
+                    self.consume_semicolon()?;
+                    Ok(Statement::VariableDeclaration(VariableDec { loc: self.loc_from(start), ..declaration }))
+                }
+                "function" => Ok(Statement::FunctionDeclaration(self.function_declaration()?)),
+                "if" => self.if_statement(),
+                "while" => self.while_statement(),
+                "do" => self.do_while_statement(),
+                "for" => self.for_statement(),
+                "switch" => self.switch_statement(),
+                "break" | "continue" => self.jump_statement(word),
+                "return" => self.return_statement(),
+                "throw" => self.throw_statement(),
+                "try" => self.try_statement(),
+                _ if self.peek_next().kind == Punctuator(":") => self.labeled_statement(),
+                _ => self.expression_statement(),
+            },
+            _ => self.expression_statement(),
+        }
+    }
+
+    fn block(&mut self) -> ParseResult<BlockStmt> {
+        let start = self.start();
+        self.expect("{")?;
+        let mut body = vec![];
+        while !self.check("}") {
+            if self.at_eof() {
+                return Err(self.unexpected());
+            }
+            body.push(Box::new(self.statement()?));
+        }
+        self.advance();
+        Ok(BlockStmt { body, loc: self.loc_from(start) })
+    }
+
+    /// Parse `var a = 1, b` without the trailing semicolon
+    fn variable_declaration(&mut self) -> ParseResult<VariableDec> {
+        let start = self.start();
+        let kind = self.word()?;
+        let mut declarations = vec![];
+        loop {
+            let id = self.identifier()?;
+            let init = if self.eat("=") {
+                Some(Box::new(self.assignment()?))
+            } else {
+                None
+            };
+            let loc = self.loc_from(id.loc.start.clone());
+            declarations.push(Box::new(Statement::VariableDeclarator(Variable { id, init, loc })));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        Ok(VariableDec { declarations, kind, loc: self.loc_from(start) })
+    }
+
+    fn function_declaration(&mut self) -> ParseResult<FunctionDec> {
+        let start = self.start();
+        self.expect_word("function")?;
+        let id = self.identifier()?;
+        let params = self.params()?;
+        let body = self.block()?;
+        Ok(FunctionDec { id, params, body, loc: self.loc_from(start) })
+    }
+
+    fn params(&mut self) -> ParseResult<Vec<Id>> {
+        self.expect("(")?;
+        let mut params = vec![];
+        while !self.eat(")") {
+            params.push(self.identifier()?);
+            if !self.check(")") {
+                self.expect(",")?;
+            }
+        }
+        Ok(params)
+    }
+
+    fn if_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("if")?;
+        let test = self.parenthesized()?;
+        let consequent = Box::new(self.statement()?);
+        let alternate = if self.eat_word("else") {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        Ok(Statement::IfStatement(IfStmt { test, consequent, alternate, loc: self.loc_from(start) }))
+    }
+
+    fn while_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("while")?;
+        let test = self.parenthesized()?;
+        let body = Box::new(self.statement()?);
+        Ok(Statement::WhileStatement(WhileStmt { test, body, loc: self.loc_from(start) }))
+    }
+
+    fn do_while_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("do")?;
+        let body = Box::new(self.statement()?);
+        self.expect_word("while")?;
+        let test = self.parenthesized()?;
+        // the semicolon after a `do..while` can always be left out
+        self.eat(";");
+        Ok(Statement::DoWhileStatement(DoWhileStmt { body, test, loc: self.loc_from(start) }))
+    }
+
+    fn for_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("for")?;
+        self.expect("(")?;
+        self.no_in = true;
+        let init = match self.peek().kind.clone() {
+            Word(ref kind) if kind == "var" || kind == "let" || kind == "const" => {
+                self.variable_declaration().map(|declaration| Some(ForInit::Declaration(declaration)))
+            }
+            _ => self.optional_expression(";").map(|expression| expression.map(ForInit::Expression)),
+        };
+        self.no_in = false;
+        let init = init?;
+
+        if let Some(left) = &init {
+            if self.eat_word("in") {
+                let right = Box::new(self.expression()?);
+                return Ok(Statement::ForInStatement(self.for_each_statement(start, left.clone(), right)?));
+            } else if self.eat_word("of") {
+                let right = Box::new(self.assignment()?);
+                return Ok(Statement::ForOfStatement(self.for_each_statement(start, left.clone(), right)?));
+            }
+        }
+        self.expect(";")?;
+        let test = self.optional_expression(";")?;
+        self.expect(";")?;
+        let update = self.optional_expression(")")?;
+        self.expect(")")?;
+        let body = Box::new(self.statement()?);
+        Ok(Statement::ForStatement(ForStmt { init, test, update, body, loc: self.loc_from(start) }))
+    }
+
+    /// The rest of `for (left in right)` and `for (left of right)`
+    fn for_each_statement(&mut self, start: Pos, left: ForInit, right: Box<Expression>) -> ParseResult<ForEachStmt> {
+        self.expect(")")?;
+        let body = Box::new(self.statement()?);
+        Ok(ForEachStmt { left, right, body, loc: self.loc_from(start) })
+    }
+
+    fn switch_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("switch")?;
+        let discriminant = self.parenthesized()?;
+        self.expect("{")?;
+        let mut cases = vec![];
+        while !self.eat("}") {
+            let case_start = self.start();
+            let test = if self.eat_word("default") {
+                None
+            } else {
+                self.expect_word("case")?;
+                Some(Box::new(self.expression()?))
+            };
+            self.expect(":")?;
+            let mut consequent = vec![];
+            while !self.check("}") && !self.check_word("case") && !self.check_word("default") {
+                if self.at_eof() {
+                    return Err(self.unexpected());
+                }
+                consequent.push(Box::new(self.statement()?));
+            }
+            cases.push(Box::new(CaseStmt { test, consequent, loc: self.loc_from(case_start) }));
+        }
+        Ok(Statement::SwitchStatement(SwitchStmt { discriminant, cases, loc: self.loc_from(start) }))
+    }
+
+    fn labeled_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        let label = self.identifier()?;
+        self.expect(":")?;
+        let body = Box::new(self.statement()?);
+        Ok(Statement::LabeledStatement(LabeledStmt { label, body, loc: self.loc_from(start) }))
+    }
+
+    fn jump_statement(&mut self, keyword: &str) -> ParseResult<Statement> {
+        let start = self.start();
+        self.advance();
+        let label = match self.peek() {
+            Lexeme { kind: Word(_), newline_before: false, .. } => Some(self.identifier()?),
+            _ => None,
+        };
+        self.consume_semicolon()?;
+        let loc = self.loc_from(start);
+        if keyword == "break" {
+            Ok(Statement::BreakStatement(BreakStmt { label, loc }))
+        } else {
+            Ok(Statement::ContinueStatement(ContinueStmt { label, loc }))
+        }
+    }
+
+    fn return_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("return")?;
+        let argument = if self.check(";") || self.check("}") || self.at_eof() || self.peek().newline_before {
+            None
+        } else {
+            Some(Box::new(self.expression()?))
+        };
+        self.consume_semicolon()?;
+        Ok(Statement::ReturnStatement(ReturnStmt { argument, loc: self.loc_from(start) }))
+    }
+
+    /// The thrown value has to start on the `throw` line
+    fn throw_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("throw")?;
+        if self.peek().newline_before {
+            return Err(ParseError { message: "illegal newline after `throw`".to_string(), loc: self.peek().loc.clone() });
+        }
+        let argument = Box::new(self.expression()?);
+        self.consume_semicolon()?;
+        Ok(Statement::ThrowStatement(ThrowStmt { argument, loc: self.loc_from(start) }))
+    }
+
+    fn try_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        self.expect_word("try")?;
+        let block = self.block()?;
+        let handler = if self.check_word("catch") {
+            let catch_start = self.start();
+            self.advance();
+            let param = if self.eat("(") {
+                let param = self.identifier()?;
+                self.expect(")")?;
+                Some(param)
+            } else {
+                None
+            };
+            let body = self.block()?;
+            Some(CatchClause { param, body, loc: self.loc_from(catch_start) })
+        } else {
+            None
+        };
+        let finalizer = if self.eat_word("finally") { Some(self.block()?) } else { None };
+        if handler.is_none() && finalizer.is_none() {
+            return Err(self.expected("`catch` or `finally`"));
+        }
+        Ok(Statement::TryStatement(TryStmt { block, handler, finalizer, loc: self.loc_from(start) }))
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.start();
+        let expression = Box::new(self.expression()?);
+        self.consume_semicolon()?;
+        Ok(Statement::ExpressionStatement(ExpressionStmt { expression, loc: self.loc_from(start) }))
+    }
+
+    // expression
+
+    fn parenthesized(&mut self) -> ParseResult<Box<Expression>> {
+        self.expect("(")?;
+        let expression = self.expression()?;
+        self.expect(")")?;
+        Ok(Box::new(expression))
+    }
+
+    fn optional_expression(&mut self, terminator: &str) -> ParseResult<Option<Box<Expression>>> {
+        if self.check(terminator) {
+            Ok(None)
+        } else {
+            Ok(Some(Box::new(self.expression()?)))
+        }
+    }
+
+    fn expression(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let expression = self.assignment()?;
+        if !self.check(",") {
+            return Ok(expression);
+        }
+        while self.eat(",") {
+            self.assignment()?;
+        }
+        Ok(self.unsupported("SequenceExpression", start))
+    }
+
+    fn assignment(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        if self.at_arrow() {
+            return self.arrow_function();
+        }
+        let left = self.conditional()?;
+        let operator = match self.peek().kind {
+            Punctuator(op) if ASSIGNMENT_OPERATORS.contains(&op) => op,
+            _ => return Ok(left),
+        };
+
+        match left {
+            Expression::Identifier(_) | Expression::MemberExpression(_) => (),
+            _ => return Err(ParseError {
+                message: "invalid left-hand side in assignment".to_string(),
+                loc: left.get_loc(),
+            }),
+        }
+
+        self.advance();
+        let right = self.assignment()?;
+        Ok(Expression::AssignmentExpression(AssignmentExp {
+            operator: operator.to_string(),
+            left: Box::new(left),
+            right: Box::new(right),
+            loc: self.loc_from(start),
+        }))
+    }
+
+    /// True if the next lexemes are the parameters of an arrow function, `x =>`
+    /// or a parenthesized list followed by `=>`
+    fn at_arrow(&self) -> bool {
+        let arrow_at = |cursor: usize| {
+            matches!(self.lexemes.get(cursor), Some(Lexeme { kind: Punctuator("=>"), newline_before: false, .. }))
+        };
+        match &self.peek().kind {
+            Word(_) => arrow_at(self.cursor + 1),
+            Punctuator("(") => {
+                let mut depth = 0;
+                for (i, lexeme) in self.lexemes[self.cursor..].iter().enumerate() {
+                    match lexeme.kind {
+                        Punctuator("(") => depth += 1,
+                        Punctuator(")") => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return arrow_at(self.cursor + i + 1);
+                            }
+                        }
+                        Eof => return false,
+                        _ => {}
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn arrow_function(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let params = if self.check("(") { self.params()? } else { vec![self.identifier()?] };
+        self.expect("=>")?;
+        let body = if self.check("{") {
+            ArrowBody::Block(self.block()?)
+        } else {
+            ArrowBody::Expression(Box::new(self.assignment()?))
+        };
+        Ok(Expression::ArrowFunctionExpression(ArrowFunctionExp { params, body, loc: self.loc_from(start) }))
+    }
+
+    fn conditional(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let test = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(test);
+        }
+        self.assignment()?;
+        self.expect(":")?;
+        self.assignment()?;
+        Ok(self.unsupported("ConditionalExpression", start))
+    }
+
+    /// Precedence climbing over binary and logical operators
+    fn binary(&mut self, min_precedence: u8) -> ParseResult<Expression> {
+        let start = self.start();
+        let mut left = self.unary()?;
+
+        loop {
+            let operator = match &self.peek().kind {
+                Punctuator(op) => op.to_string(),
+                Word(word) if word == "instanceof" || (word == "in" && !self.no_in) => word.clone(),
+                _ => break,
+            };
+            let precedence = match binary_precedence(&operator) {
+                Some(precedence) if precedence >= min_precedence => precedence,
+                _ => break,
+            };
+            self.advance();
+
+            // exponentiation is the only right associative binary operator
+            let next_precedence = if operator == "**" { precedence } else { precedence + 1 };
+            let right = Box::new(self.binary(next_precedence)?);
+            let loc = self.loc_from(start.clone());
+            let left_box = Box::new(left);
+
+            left = if operator == "||" || operator == "&&" || operator == "??" {
+                Expression::LogicalExpression(LogicalExp { operator, left: left_box, right, loc })
+            } else {
+                Expression::BinaryExpression(BinaryExp { left: left_box, operator, right, extra: None, loc })
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let operator = match &self.peek().kind {
+            Punctuator(op) if UNARY_OPERATORS.contains(op) => op.to_string(),
+            Word(word) if UNARY_OPERATORS.contains(&word.as_str()) => word.clone(),
+            Punctuator(op) if *op == "++" || *op == "--" => {
+                let operator = op.to_string();
+                self.advance();
+                let argument = Box::new(self.unary()?);
+                return Ok(Expression::UpdateExpression(UpdateExp {
+                    operator,
+                    argument,
+                    prefix: true,
+                    loc: self.loc_from(start),
+                }));
+            }
+            _ => return self.postfix(),
+        };
+
+        self.advance();
+        let argument = Box::new(self.unary()?);
+        Ok(Expression::UnaryExpression(UnaryExp { operator, prefix: true, argument, loc: self.loc_from(start) }))
+    }
+
+    fn postfix(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let argument = self.call_or_member()?;
+        match self.peek() {
+            Lexeme { kind: Punctuator(op), newline_before: false, .. } if *op == "++" || *op == "--" => {
+                let operator = op.to_string();
+                self.advance();
+                Ok(Expression::UpdateExpression(UpdateExp {
+                    operator,
+                    argument: Box::new(argument),
+                    prefix: false,
+                    loc: self.loc_from(start),
+                }))
+            }
+            _ => Ok(argument),
+        }
+    }
+
+    fn call_or_member(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        let expression = self.primary()?;
+        self.member_suffixes(expression, start, true)
+    }
+
+    /// Property accesses and, if `calls` is set, calls following `expression`
+    fn member_suffixes(&mut self, mut expression: Expression, start: Pos, calls: bool) -> ParseResult<Expression> {
+        loop {
+            if self.eat(".") {
+                let property = Box::new(Expression::Identifier(self.identifier_name()?));
+                expression = Expression::MemberExpression(MemberExp {
+                    object: Box::new(expression),
+                    property,
+                    computed: false,
+                    loc: self.loc_from(start.clone()),
+                });
+            } else if self.eat("[") {
+                let property = Box::new(self.expression()?);
+                self.expect("]")?;
+                expression = Expression::MemberExpression(MemberExp {
+                    object: Box::new(expression),
+                    property,
+                    computed: true,
+                    loc: self.loc_from(start.clone()),
+                });
+            } else if calls && self.check("(") {
+                let arguments = self.arguments()?;
+                expression = Expression::CallExpression(CallExp {
+                    callee: Box::new(expression),
+                    arguments,
+                    loc: self.loc_from(start.clone()),
+                });
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> ParseResult<Vec<Box<Expression>>> {
+        self.expect("(")?;
+        let mut arguments = vec![];
+        while !self.eat(")") {
+            arguments.push(Box::new(self.assignment()?));
+            if !self.check(")") {
+                self.expect(",")?;
+            }
+        }
+        Ok(arguments)
+    }
+
+    /// `new F(args)`, the arguments are optional and the callee can't be a call
+    fn new_expression(&mut self, start: Pos) -> ParseResult<Expression> {
+        let callee_start = self.start();
+        let callee = match self.peek().kind {
+            Word(ref word) if word == "new" => {
+                self.advance();
+                self.new_expression(callee_start)?
+            }
+            _ => {
+                let primary = self.primary()?;
+                self.member_suffixes(primary, callee_start, false)?
+            }
+        };
+        let arguments = if self.check("(") { self.arguments()? } else { vec![] };
+        Ok(Expression::NewExpression(NewExp { callee: Box::new(callee), arguments, loc: self.loc_from(start) }))
+    }
+
+    fn primary(&mut self) -> ParseResult<Expression> {
+        let lexeme = self.peek().clone();
+        match lexeme.kind {
+            Number(value) => {
+                self.advance();
+                Ok(Expression::NumericLiteral(NumericLit { value, loc: lexeme.loc }))
+            }
+            Str(value) => {
+                self.advance();
+                Ok(Expression::StringLiteral(StringLit { value, loc: lexeme.loc }))
+            }
+            Punctuator("(") => {
+                self.advance();
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(match expression {
+                    Expression::BinaryExpression(binary) => Expression::BinaryExpression(BinaryExp {
+                        extra: Some(Extra { parenthesized: true }),
+                        ..binary
+                    }),
+                    other => other,
+                })
+            }
+            Punctuator("{") => self.object(),
+            Word(ref word) => match word.as_str() {
+                "true" | "false" => {
+                    self.advance();
+                    Ok(Expression::BooleanLiteral(BooleanLit { value: word == "true", loc: lexeme.loc }))
+                }
+                "null" => {
+                    self.advance();
+                    Ok(Expression::NullLiteral(NullLit { loc: lexeme.loc }))
+                }
+                "this" => {
+                    self.advance();
+                    Ok(Expression::ThisExpression(ThisExp { loc: lexeme.loc }))
+                }
+                "new" => {
+                    self.advance();
+                    self.new_expression(lexeme.loc.start)
+                }
+                "function" => {
+                    self.advance();
+                    let id = if self.check("(") { None } else { Some(self.identifier()?) };
+                    let params = self.params()?;
+                    let body = self.block()?;
+                    let loc = self.loc_from(lexeme.loc.start);
+                    Ok(Expression::FunctionExpression(FunctionExp { id, params, body, loc }))
+                }
+                _ => Ok(Expression::Identifier(self.identifier()?)),
+            },
+            Punctuator("[") => self.array(),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn array(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        self.expect("[")?;
+        let mut elements = vec![];
+        while !self.eat("]") {
+            // holes are allowed : `[1, , 3]`
+            if self.eat(",") {
+                elements.push(None);
+                continue;
+            }
+            elements.push(Some(Box::new(self.assignment()?)));
+            if !self.check("]") {
+                self.expect(",")?;
+            }
+        }
+        Ok(Expression::ArrayExpression(ArrayExp { elements, loc: self.loc_from(start) }))
+    }
+
+    fn object(&mut self) -> ParseResult<Expression> {
+        let start = self.start();
+        self.expect("{")?;
+        let mut properties = vec![];
+        while !self.eat("}") {
+            let lexeme = self.peek().clone();
+            let key = match lexeme.kind {
+                Word(_) => Expression::Identifier(self.identifier_name()?),
+                Str(value) => {
+                    self.advance();
+                    Expression::StringLiteral(StringLit { value, loc: lexeme.loc })
+                }
+                Number(value) => {
+                    self.advance();
+                    Expression::NumericLiteral(NumericLit { value, loc: lexeme.loc })
+                }
+                _ => return Err(self.unexpected()),
+            };
+            self.expect(":")?;
+            let value = self.assignment()?;
+            properties.push(Box::new(Property { key: Box::new(key), value: Box::new(value), kind: None }));
+            if !self.check("}") {
+                self.expect(",")?;
+            }
+        }
+        Ok(Expression::ObjectExpression(ObjectExp { properties, loc: self.loc_from(start) }))
+    }
+
+    /// An identifier that can't be a reserved word
+    fn identifier(&mut self) -> ParseResult<Id> {
+        if let Word(word) = &self.peek().kind {
+            if RESERVED_WORDS.contains(&word.as_str()) {
+                return Err(ParseError {
+                    message: format!("unexpected keyword `{}`", word),
+                    loc: self.peek().loc.clone(),
+                });
+            }
+        }
+        self.identifier_name()
+    }
+
+    /// Any word, property names can be reserved words
+    fn identifier_name(&mut self) -> ParseResult<Id> {
+        let lexeme = self.peek().clone();
+        match lexeme.kind {
+            Word(name) => {
+                self.advance();
+                Ok(Id { name, loc: lexeme.loc, binding: None })
+            }
+            _ => Err(self.expected("an identifier")),
+        }
+    }
+
+    // lexeme helpers
+
+    fn peek(&self) -> &Lexeme {
+        &self.lexemes[self.cursor]
+    }
+
+    fn peek_next(&self) -> &Lexeme {
+        self.lexemes.get(self.cursor + 1).unwrap_or_else(|| self.peek())
+    }
+
+    fn advance(&mut self) {
+        if !self.at_eof() {
+            self.cursor += 1;
+        }
+    }
+
+    fn at_eof(&self) -> bool {
+        self.peek().kind == Eof
+    }
+
+    fn check(&self, punctuator: &str) -> bool {
+        match self.peek().kind {
+            Punctuator(p) => p == punctuator,
+            _ => false,
+        }
+    }
+
+    fn check_word(&self, keyword: &str) -> bool {
+        match &self.peek().kind {
+            Word(word) => word == keyword,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, punctuator: &str) -> bool {
+        let found = self.check(punctuator);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_word(&mut self, keyword: &str) -> bool {
+        let found = self.check_word(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect(&mut self, punctuator: &str) -> ParseResult<()> {
+        if self.eat(punctuator) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", punctuator)))
+        }
+    }
+
+    fn expect_word(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_word(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", keyword)))
+        }
+    }
+
+    fn word(&mut self) -> ParseResult<String> {
+        match self.peek().kind.clone() {
+            Word(word) => {
+                self.advance();
+                Ok(word)
+            }
+            _ => Err(self.expected("a keyword")),
+        }
+    }
+
+    /// Automatic semicolon insertion : a missing `;` is fine before `}`, at the end
+    /// of the file or on a new line
+    fn consume_semicolon(&mut self) -> ParseResult<()> {
+        if self.eat(";") || self.check("}") || self.at_eof() || self.peek().newline_before {
+            Ok(())
+        } else {
+            Err(self.expected("`;`"))
+        }
+    }
+
+    fn start(&self) -> Pos {
+        self.peek().loc.start.clone()
+    }
+
+    /// Location from `start` to the end of the last consumed lexeme
+    fn loc_from(&self, start: Pos) -> Loc {
+        let end = if self.cursor == 0 {
+            start.clone()
+        } else {
+            self.lexemes[self.cursor - 1].loc.end.clone()
+        };
+        Loc { start, end }
+    }
+
+    fn expected(&self, what: &str) -> ParseError {
+        ParseError {
+            message: format!("expected {}, found {}", what, describe(&self.peek().kind)),
+            loc: self.peek().loc.clone(),
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        ParseError {
+            message: format!("unexpected {}", describe(&self.peek().kind)),
+            loc: self.peek().loc.clone(),
+        }
+    }
+
+    /// A construct the ast has no variant for, reported later by the checking pass
+    fn unsupported(&self, node_type: &str, start: Pos) -> Expression {
+        Expression::Unsupported(UnsupportedNode { node_type: node_type.to_string(), loc: self.loc_from(start) })
+    }
+}
+
+const RESERVED_WORDS: &[&'static str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+    "do", "else", "export", "extends", "finally", "for", "function", "if", "import", "in",
+    "instanceof", "new", "return", "super", "switch", "this", "throw", "try", "typeof", "var",
+    "void", "while", "with", "null", "true", "false",
+];
+
+/// Binding power of binary and logical operators, higher binds tighter
+fn binary_precedence(operator: &str) -> Option<u8> {
+    let precedence = match operator {
+        "??" => 1,
+        "||" => 2,
+        "&&" => 3,
+        "|" => 4,
+        "^" => 5,
+        "&" => 6,
+        "==" | "!=" | "===" | "!==" => 7,
+        "<" | ">" | "<=" | ">=" | "instanceof" | "in" => 8,
+        "<<" | ">>" | ">>>" => 9,
+        "+" | "-" => 10,
+        "*" | "/" | "%" => 11,
+        "**" => 12,
+        _ => return None,
+    };
+    Some(precedence)
+}
+
+fn describe(kind: &LexemeKind) -> String {
+    match kind {
+        Number(n) => format!("number `{}`", n),
+        Str(s) => format!("string \"{}\"", s),
+        Word(w) => format!("`{}`", w),
+        Punctuator(p) => format!("`{}`", p),
+        Eof => "end of file".to_string(),
+    }
+}
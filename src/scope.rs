@@ -0,0 +1,655 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::ast::expression::*;
+use crate::ast::expression::Expression::*;
+use crate::ast::statement::*;
+use crate::ast::statement::Statement::*;
+use crate::c_compile;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+
+/// Identifiers every backend provides without a declaration
+pub const BUILTINS: &[&str] = &["print", "console", "undefined", "NaN", "Infinity"];
+
+/// How a symbol is declared, the parameter of a catch clause is a `Param`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Var,
+    Let,
+    Const,
+    Param,
+    Function,
+}
+
+impl SymbolKind {
+    /// `let` and `const` can't be redeclared nor used before their declaration
+    pub fn is_lexical(&self) -> bool {
+        *self == SymbolKind::Let || *self == SymbolKind::Const
+    }
+}
+
+#[derive(Debug)]
+pub struct Symbol {
+    pub name: String,
+    /// Name in the generated code, differs from `name` when a block declaration
+    /// would clash with another variable of the function
+    pub renamed: String,
+    pub kind: SymbolKind,
+    /// The first declaring identifier
+    pub loc: Loc,
+    /// Where a `let` or a `const` starts being usable, the end of its declarator
+    ready: Pos,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScopeKind {
+    Program,
+    Function,
+    Block,
+}
+
+#[derive(Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<usize>,
+    /// The function or program scope the scope belongs to, itself for one of them
+    pub function: usize,
+    pub symbols: Vec<Symbol>,
+}
+
+/// Every scope of the program in the order they start, the program scope first
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    pub scopes: Vec<Scope>,
+}
+
+impl SymbolTable {
+    /// Variables and functions of the top level code outside of any block
+    pub fn globals(&self) -> &[Symbol] {
+        &self.scopes[0].symbols
+    }
+
+    fn symbol(&self, (scope, symbol): (usize, usize)) -> &Symbol {
+        &self.scopes[scope].symbols[symbol]
+    }
+}
+
+/// Resolves every identifier to the declaration it refers to and records it in
+/// `Id::binding`. `var`s and functions are hoisted to their function, `let` and
+/// `const` are only visible in their block and, when renaming, get another name
+/// if they would clash with another variable of the function: the C backend
+/// declares every variable of a function in the same scope. Renaming also moves
+/// the variables off the names the C code reserves, see `c_compile::is_reserved`.
+struct Resolver {
+    table: SymbolTable,
+    /// Scopes being walked, innermost last
+    open: Vec<usize>,
+    /// Every identifier of the program, renamed variables must not collide with them
+    names: HashSet<String>,
+    rename: bool,
+    errors: Vec<CompileError>,
+}
+
+/// Resolve the identifiers of `program` and build its symbol table. Reports the
+/// undeclared identifiers, the duplicate declarations, the assignments to a
+/// `const` and the uses of a `let` or `const` before its declaration. `rename`
+/// gives the shadowing block declarations and the reserved names a name of their own.
+pub fn resolve(program: &mut [Box<Statement>], rename: bool) -> CompileResult<SymbolTable> {
+    let mut resolver = Resolver::new(HashSet::new(), false);
+    resolver.program(program);
+    if !resolver.errors.is_empty() {
+        let mut errors = resolver.errors;
+        errors.sort_by(|a, b| match (a.loc(), b.loc()) {
+            (Some(a), Some(b)) => (a.start.line, a.start.column).partial_cmp(&(b.start.line, b.start.column)).unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        });
+        return match errors.len() {
+            1 => Err(errors.remove(0)),
+            _ => Err(CompileError::Multiple(errors)),
+        };
+    }
+
+    // renamed variables are picked once every name of the program is known
+    if rename {
+        resolver = Resolver::new(resolver.names, true);
+        resolver.program(program);
+    }
+    Ok(resolver.table)
+}
+
+/// True if `a` is before `b` in the source
+fn before(a: &Pos, b: &Pos) -> bool {
+    a.line < b.line || (a.line == b.line && a.column < b.column)
+}
+
+fn kind(declaration: &VariableDec) -> SymbolKind {
+    match declaration.kind.as_str() {
+        "let" => SymbolKind::Let,
+        "const" => SymbolKind::Const,
+        _ => SymbolKind::Var,
+    }
+}
+
+/// The `var`s and function declarations of a function body, its nested
+/// functions have their own
+pub fn hoisted(statements: &[Box<Statement>]) -> Vec<(Id, SymbolKind)> {
+    let mut declarations = vec![];
+    for statement in statements.iter() {
+        hoisted_in(statement, &mut declarations);
+    }
+    declarations
+}
+
+fn hoisted_vars(declaration: &VariableDec, declarations: &mut Vec<(Id, SymbolKind)>) {
+    if kind(declaration) == SymbolKind::Var {
+        for declarator in declaration.declarations.iter() {
+            if let box VariableDeclarator(v) = declarator {
+                declarations.push((v.id.clone(), SymbolKind::Var));
+            }
+        }
+    }
+}
+
+fn hoisted_in(statement: &Statement, declarations: &mut Vec<(Id, SymbolKind)>) {
+    match statement {
+        VariableDeclaration(v) => hoisted_vars(v, declarations),
+        FunctionDeclaration(f) => declarations.push((f.id.clone(), SymbolKind::Function)),
+        BlockStatement(b) => declarations.extend(hoisted(&b.body)),
+        IfStatement(i) => {
+            hoisted_in(&i.consequent, declarations);
+            if let Some(alternate) = &i.alternate {
+                hoisted_in(alternate, declarations);
+            }
+        }
+        SwitchStatement(s) => {
+            for case in s.cases.iter() {
+                declarations.extend(hoisted(&case.consequent));
+            }
+        }
+        ForStatement(f) => {
+            if let Some(ForInit::Declaration(declaration)) = &f.init {
+                hoisted_vars(declaration, declarations);
+            }
+            hoisted_in(&f.body, declarations);
+        }
+        ForInStatement(f) | ForOfStatement(f) => {
+            if let ForInit::Declaration(declaration) = &f.left {
+                hoisted_vars(declaration, declarations);
+            }
+            hoisted_in(&f.body, declarations);
+        }
+        WhileStatement(w) => hoisted_in(&w.body, declarations),
+        DoWhileStatement(d) => hoisted_in(&d.body, declarations),
+        LabeledStatement(l) => hoisted_in(&l.body, declarations),
+        TryStatement(t) => {
+            declarations.extend(hoisted(&t.block.body));
+            if let Some(handler) = &t.handler {
+                declarations.extend(hoisted(&handler.body.body));
+            }
+            if let Some(finalizer) = &t.finalizer {
+                declarations.extend(hoisted(&finalizer.body));
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Resolver {
+    fn new(names: HashSet<String>, rename: bool) -> Self {
+        Resolver { table: SymbolTable::default(), open: vec![], names, rename, errors: vec![] }
+    }
+
+    fn program(&mut self, program: &mut [Box<Statement>]) {
+        self.enter(ScopeKind::Program);
+        self.hoist(&mut [], program, false);
+        self.statements(program);
+        self.open.pop();
+    }
+
+    fn enter(&mut self, kind: ScopeKind) {
+        let index = self.table.scopes.len();
+        let parent = self.open.last().cloned();
+        let function = match (kind, parent) {
+            (ScopeKind::Block, Some(parent)) => self.table.scopes[parent].function,
+            _ => index,
+        };
+        self.table.scopes.push(Scope { kind, parent, function, symbols: vec![] });
+        self.open.push(index);
+    }
+
+    fn current(&self) -> &Scope {
+        &self.table.scopes[*self.open.last().expect("no open scope")]
+    }
+
+    /// Declare the parameters, the `var`s, the functions and the top level
+    /// `let`s and `const`s of a function body. Like in sloppy mode, only arrow
+    /// functions can't have two parameters with the same name
+    fn hoist(&mut self, params: &mut [Id], body: &[Box<Statement>], is_arrow: bool) {
+        for i in 0..params.len() {
+            if is_arrow && params[..i].iter().any(|param| param.name == params[i].name) {
+                self.errors.push(CompileError::duplicate(&params[i]));
+            }
+            let param = &mut params[i];
+            self.declare(param, SymbolKind::Param, param.loc.start.clone());
+            self.declared(param, SymbolKind::Param);
+        }
+        for (id, kind) in hoisted(body) {
+            self.declare(&id, kind, id.loc.start.clone());
+        }
+        self.declare_lexicals(body);
+    }
+
+    /// Declare the `let`s and `const`s of a block, they are visible in the whole
+    /// block but only usable after their declaration
+    fn declare_lexicals(&mut self, statements: &[Box<Statement>]) {
+        for statement in statements.iter() {
+            if let box VariableDeclaration(declaration) = statement {
+                self.declare_all(declaration);
+            }
+        }
+    }
+
+    fn declare_all(&mut self, declaration: &VariableDec) {
+        let kind = kind(declaration);
+        if !kind.is_lexical() {
+            return;
+        }
+        for declarator in declaration.declarations.iter() {
+            if let box VariableDeclarator(v) = declarator {
+                self.declare(&v.id, kind, v.loc.end.clone());
+            }
+        }
+    }
+
+    /// `var`s, functions and parameters can be declared several times in a
+    /// scope, but not with the name of a `let` or a `const`
+    fn declare(&mut self, id: &Id, kind: SymbolKind, ready: Pos) {
+        self.names.insert(id.name.clone());
+        let existing = self.current().symbols.iter().find(|symbol| symbol.name == id.name).map(|symbol| symbol.kind);
+        if let Some(existing) = existing {
+            if kind.is_lexical() || existing.is_lexical() {
+                self.errors.push(CompileError::duplicate(id));
+            }
+            return;
+        }
+
+        let shadows = self.current().kind == ScopeKind::Block && self.is_taken(&id.name);
+        let renamed = if self.rename && (shadows || c_compile::is_reserved(&id.name)) {
+            self.fresh_name(&id.name)
+        } else {
+            id.name.clone()
+        };
+        let symbol = Symbol { name: id.name.clone(), renamed, kind, loc: id.loc.clone(), ready };
+        let scope = *self.open.last().expect("no open scope");
+        self.table.scopes[scope].symbols.push(symbol);
+    }
+
+    /// True if a variable visible here, or another variable of the current
+    /// function, is generated with the name `name`
+    fn is_taken(&self, name: &str) -> bool {
+        let function = self.current().function;
+        let generates = |scope: &Scope| scope.symbols.iter().any(|symbol| symbol.renamed == name);
+        self.open.iter().any(|scope| generates(&self.table.scopes[*scope]))
+            || self.table.scopes.iter().filter(|scope| scope.function == function).any(generates)
+    }
+
+    fn fresh_name(&mut self, name: &str) -> String {
+        // a suffix doesn't take the name off the prefix of the generated ones
+        let name = if name.starts_with(c_compile::GENERATED) { format!("_{}", name) } else { name.to_string() };
+        let mut i = 1;
+        loop {
+            let candidate = format!("{}_{}", name, i);
+            if !self.names.contains(&candidate) && !self.is_taken(&candidate) && !c_compile::is_reserved(&candidate) {
+                self.names.insert(candidate.clone());
+                return candidate;
+            }
+            i += 1;
+        }
+    }
+
+    /// The scope and the index of the symbol `name` refers to here
+    fn lookup(&self, name: &str) -> Option<(usize, usize)> {
+        self.open.iter().rev().find_map(|scope| {
+            let position = self.table.scopes[*scope].symbols.iter().position(|symbol| symbol.name == name);
+            position.map(|symbol| (*scope, symbol))
+        })
+    }
+
+    /// Point `id` to the symbol `found`
+    fn bind(&self, id: &mut Id, found: (usize, usize)) {
+        let scope = &self.table.scopes[found.0];
+        id.binding = Some(if scope.kind == ScopeKind::Program {
+            Binding::Global
+        } else if scope.function == self.current().function {
+            Binding::Local
+        } else {
+            Binding::Enclosing
+        });
+        id.name = self.table.symbol(found).renamed.clone();
+    }
+
+    /// Point `id` to the variable it names, `assigned` if the variable is
+    /// written rather than read
+    fn reference(&mut self, id: &mut Id, assigned: bool) {
+        self.names.insert(id.name.clone());
+        let found = match self.lookup(&id.name) {
+            Some(found) => found,
+            None if BUILTINS.contains(&id.name.as_str()) => {
+                id.binding = Some(Binding::Builtin);
+                return;
+            }
+            None => {
+                self.errors.push(CompileError::undeclared(id));
+                return;
+            }
+        };
+
+        let symbol = self.table.symbol(found);
+        // a nested function may well be called once the variable is ready
+        let same_function = self.table.scopes[found.0].function == self.current().function;
+        if symbol.kind.is_lexical() && same_function && before(&id.loc.start, &symbol.ready) {
+            self.errors.push(CompileError::used_before_declaration(id));
+        } else if symbol.kind == SymbolKind::Const && assigned {
+            self.errors.push(CompileError::const_assignment(id));
+        }
+        self.bind(id, found);
+    }
+
+    /// Point the identifier of a declaration to its symbol, a `var` can't be
+    /// declared in a block declaring a `let` or `const` with the same name
+    fn declared(&mut self, id: &mut Id, kind: SymbolKind) {
+        if let Some(found) = self.lookup(&id.name) {
+            let symbol = self.table.symbol(found);
+            if kind == SymbolKind::Var && symbol.kind.is_lexical() && self.table.scopes[found.0].kind == ScopeKind::Block {
+                self.errors.push(CompileError::duplicate(id));
+            }
+            self.bind(id, found);
+        }
+    }
+
+    fn statements(&mut self, statements: &mut [Box<Statement>]) {
+        for statement in statements.iter_mut() {
+            self.statement(statement);
+        }
+    }
+
+    fn block(&mut self, statements: &mut [Box<Statement>]) {
+        self.enter(ScopeKind::Block);
+        self.declare_lexicals(statements);
+        self.statements(statements);
+        self.open.pop();
+    }
+
+    fn function(&mut self, own_name: Option<&mut Id>, params: &mut [Id], body: &mut BlockStmt, is_arrow: bool) {
+        self.enter(ScopeKind::Function);
+        if let Some(id) = own_name {
+            self.declare(id, SymbolKind::Function, id.loc.start.clone());
+            self.declared(id, SymbolKind::Function);
+        }
+        self.hoist(params, &body.body, is_arrow);
+        self.statements(&mut body.body);
+        self.open.pop();
+    }
+
+    fn declaration(&mut self, declaration: &mut VariableDec) {
+        let kind = kind(declaration);
+        for declarator in declaration.declarations.iter_mut() {
+            if let box VariableDeclarator(v) = declarator {
+                if let Some(init) = &mut v.init {
+                    self.expression(init);
+                }
+                // the declaration itself isn't an assignment
+                self.declared(&mut v.id, kind);
+            }
+        }
+    }
+
+    fn for_init(&mut self, init: &mut ForInit) {
+        match init {
+            ForInit::Declaration(declaration) => self.declaration(declaration),
+            ForInit::Expression(box Identifier(id)) => self.reference(id, true),
+            ForInit::Expression(expression) => self.expression(expression),
+        }
+    }
+
+    fn statement(&mut self, s: &mut Statement) {
+        match s {
+            BlockStatement(b) => self.block(&mut b.body),
+            ExpressionStatement(e) => self.expression(&mut e.expression),
+            VariableDeclaration(v) => self.declaration(v),
+            FunctionDeclaration(f) => {
+                self.declared(&mut f.id, SymbolKind::Function);
+                self.function(None, &mut f.params, &mut f.body, false);
+            }
+            IfStatement(i) => {
+                self.expression(&mut i.test);
+                self.statement(&mut i.consequent);
+                if let Some(alternate) = &mut i.alternate {
+                    self.statement(alternate);
+                }
+            }
+            SwitchStatement(s) => {
+                self.expression(&mut s.discriminant);
+                self.enter(ScopeKind::Block);
+                for case in s.cases.iter() {
+                    self.declare_lexicals(&case.consequent);
+                }
+                for case in s.cases.iter_mut() {
+                    if let Some(test) = &mut case.test {
+                        self.expression(test);
+                    }
+                    self.statements(&mut case.consequent);
+                }
+                self.open.pop();
+            }
+            ForStatement(f) => {
+                self.enter(ScopeKind::Block);
+                if let Some(ForInit::Declaration(declaration)) = &f.init {
+                    self.declare_all(declaration);
+                }
+                if let Some(init) = &mut f.init {
+                    self.for_init(init);
+                }
+                if let Some(test) = &mut f.test {
+                    self.expression(test);
+                }
+                if let Some(update) = &mut f.update {
+                    self.expression(update);
+                }
+                self.statement(&mut f.body);
+                self.open.pop();
+            }
+            ForInStatement(f) | ForOfStatement(f) => {
+                self.expression(&mut f.right);
+                self.enter(ScopeKind::Block);
+                if let ForInit::Declaration(declaration) = &f.left {
+                    self.declare_all(declaration);
+                }
+                self.for_init(&mut f.left);
+                self.statement(&mut f.body);
+                self.open.pop();
+            }
+            WhileStatement(w) => {
+                self.expression(&mut w.test);
+                self.statement(&mut w.body);
+            }
+            DoWhileStatement(d) => {
+                self.statement(&mut d.body);
+                self.expression(&mut d.test);
+            }
+            LabeledStatement(l) => self.statement(&mut l.body),
+            ThrowStatement(t) => self.expression(&mut t.argument),
+            TryStatement(t) => {
+                self.block(&mut t.block.body);
+                // the parameter shares its scope with the declarations of the catch block
+                if let Some(handler) = &mut t.handler {
+                    self.enter(ScopeKind::Block);
+                    if let Some(param) = &mut handler.param {
+                        self.declare(param, SymbolKind::Param, param.loc.start.clone());
+                        self.declared(param, SymbolKind::Param);
+                    }
+                    self.declare_lexicals(&handler.body.body);
+                    self.statements(&mut handler.body.body);
+                    self.open.pop();
+                }
+                if let Some(finalizer) = &mut t.finalizer {
+                    self.block(&mut finalizer.body);
+                }
+            }
+            ReturnStatement(r) => {
+                if let Some(argument) = &mut r.argument {
+                    self.expression(argument);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expression(&mut self, exp: &mut Expression) {
+        match exp {
+            Identifier(id) => self.reference(id, false),
+            BinaryExpression(b) => {
+                self.expression(&mut b.left);
+                self.expression(&mut b.right);
+            }
+            LogicalExpression(l) => {
+                self.expression(&mut l.left);
+                self.expression(&mut l.right);
+            }
+            AssignmentExpression(a) => {
+                match &mut a.left {
+                    box Identifier(id) => self.reference(id, true),
+                    left => self.expression(left),
+                }
+                self.expression(&mut a.right);
+            }
+            // `typeof` of an undeclared variable is "undefined" rather than an error
+            UnaryExpression(u) if u.operator == "typeof" => match &mut u.argument {
+                box Identifier(id) if self.lookup(&id.name).is_none() && !BUILTINS.contains(&id.name.as_str()) => {
+                    self.names.insert(id.name.clone());
+                }
+                argument => self.expression(argument),
+            },
+            UnaryExpression(u) => self.expression(&mut u.argument),
+            UpdateExpression(u) => match &mut u.argument {
+                box Identifier(id) => self.reference(id, true),
+                argument => self.expression(argument),
+            },
+            CallExpression(c) => {
+                self.expression(&mut c.callee);
+                for argument in c.arguments.iter_mut() {
+                    self.expression(argument);
+                }
+            }
+            NewExpression(n) => {
+                self.expression(&mut n.callee);
+                for argument in n.arguments.iter_mut() {
+                    self.expression(argument);
+                }
+            }
+            MemberExpression(m) => {
+                self.expression(&mut m.object);
+                if m.computed {
+                    self.expression(&mut m.property);
+                }
+            }
+            ObjectExpression(o) => {
+                for property in o.properties.iter_mut() {
+                    self.expression(&mut property.value);
+                }
+            }
+            ArrayExpression(a) => {
+                for element in a.elements.iter_mut().flatten() {
+                    self.expression(element);
+                }
+            }
+            FunctionExpression(f) => self.function(f.id.as_mut(), &mut f.params, &mut f.body, false),
+            ArrowFunctionExpression(f) => match &mut f.body {
+                ArrowBody::Block(body) => self.function(None, &mut f.params, body, true),
+                ArrowBody::Expression(body) => {
+                    self.enter(ScopeKind::Function);
+                    self.hoist(&mut f.params, &[], true);
+                    self.expression(body);
+                    self.open.pop();
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::expression::Binding;
+    use crate::ast::statement::Statement;
+    use crate::parser::parse;
+    use crate::scope::resolve;
+    use crate::scope::SymbolKind;
+
+    fn parsed(source: &str) -> Vec<Box<Statement>> {
+        parse(source).unwrap().get_program_root().unwrap()
+    }
+
+    #[test]
+    fn should_rename_shadowing_block_declarations() {
+        let mut program = parsed("let x = 1;\n{ let x = 2; { const x = 3; print(x); } print(x); }\n\
+                                  function f(x_1) { { let x = x_1; return x; } }\n{ let y = 0; }\n{ let y = 1; print(y); }");
+        resolve(&mut program, true).unwrap();
+        let printed = format!("{:?}", program);
+        assert!(printed.contains("id: Id { name: \"x_2\""));
+        assert!(printed.contains("id: Id { name: \"x_3\""));
+        assert_eq!(printed.matches("name: \"x_3\"").count(), 2);
+        // `x_1` is already the name of a parameter
+        assert_eq!(printed.matches("name: \"x_1\"").count(), 2);
+        assert_eq!(printed.matches("name: \"x_4\"").count(), 2);
+        // the second `y` doesn't reuse the variable of the first one
+        assert!(printed.contains("id: Id { name: \"y_1\""));
+    }
+
+    #[test]
+    fn should_rename_variables_off_the_generated_names() {
+        let mut program = parsed("var rjsc_x = 1;\nfunction f(rjsc_y) { return rjsc_x + rjsc_y; }\nvar g = function rjsc_g() { return rjsc_g; };");
+        resolve(&mut program, true).unwrap();
+        let printed = format!("{:?}", program);
+        assert_eq!(printed.matches("name: \"_rjsc_x_1\"").count(), 2);
+        assert_eq!(printed.matches("name: \"_rjsc_y_1\"").count(), 2);
+        assert_eq!(printed.matches("name: \"_rjsc_g_1\"").count(), 2);
+        assert!(!printed.contains("name: \"rjsc_"));
+    }
+
+    #[test]
+    fn should_build_a_symbol_table_and_bind_identifiers() {
+        let mut program = parsed("var g = 1;\nfunction f(p) {\n  let l = p;\n  return () => g + l + print;\n}");
+        let table = resolve(&mut program, false).unwrap();
+        let globals: Vec<(&str, SymbolKind)> = table.globals().iter().map(|symbol| (symbol.name.as_str(), symbol.kind)).collect();
+        assert_eq!(globals, vec![("g", SymbolKind::Var), ("f", SymbolKind::Function)]);
+        let locals: Vec<&str> = table.scopes[1].symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(locals, vec!["p", "l"]);
+        assert_eq!(table.scopes[2].parent, Some(1));
+
+        let printed = format!("{:?}", program);
+        let bindings: Vec<&str> = printed.split("binding: ").skip(1).map(|rest| &rest[..rest.find(')').map_or(4, |end| end + 1)]).collect();
+        let expected = [Binding::Global, Binding::Global, Binding::Local, Binding::Local, Binding::Local, Binding::Global, Binding::Enclosing, Binding::Builtin];
+        let expected: Vec<String> = expected.iter().map(|binding| format!("Some({:?})", binding)).collect();
+        assert_eq!(bindings, expected);
+    }
+
+    #[test]
+    fn should_report_undeclared_and_duplicate_identifiers() {
+        let source = "var a = 1;\nlet a = 2;\nvar g = (p, p) => { return q; };\nfunction f(r, r) { return r; }\nb = 3;\n{ let c; { var c; } }\n\
+                      try {} catch (e) { let e; }\nvar ok = typeof missing;";
+        let err = resolve(&mut parsed(source), false).unwrap_err();
+        assert_eq!(err.to_string(), "2:5: `a` is already declared\n3:13: `p` is already declared\n3:28: `q` is not declared\n\
+                                     5:1: `b` is not declared\n6:16: `c` is already declared\n7:24: `e` is already declared");
+    }
+
+    #[test]
+    fn should_report_const_assignments_and_uses_before_declaration() {
+        let source = "const a = 1;\na = 2;\nprint(b);\nlet b = 3;\nfunction f() { return c; }\nlet c = b;\n\
+                      for (const i = 0; i < 2; i++) {}\nlet d = d;";
+        let err = resolve(&mut parsed(source), false).unwrap_err();
+        assert_eq!(err.to_string(), "2:1: assignment to constant `a`\n3:7: `b` is used before its declaration\n\
+                                     7:26: assignment to constant `i`\n8:9: `d` is used before its declaration");
+    }
+}
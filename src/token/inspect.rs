@@ -0,0 +1,294 @@
+use crate::token::token::Array;
+use crate::token::token::Literal;
+use crate::token::token::Literal::*;
+use crate::token::token::Object;
+use std::rc::Rc;
+
+/// Lines of `console.log` are broken past 80 columns
+const BREAK_LENGTH: usize = 80;
+/// Objects nested deeper are shown as `[Object]`
+const DEPTH: usize = 2;
+/// The number of innermost levels node puts on a single line
+const COMPACT: usize = 3;
+/// Items of an array shown, the others are counted
+const MAX_ITEMS: usize = 100;
+
+impl Literal {
+    /// How `console.log` shows the value like node's `util.inspect` : strings
+    /// as they are, `[ 1, 'a' ]` for arrays and `P { a: 1 }` for objects,
+    /// prefixed with the name of their constructor
+    pub fn inspect(&self) -> String {
+        match self {
+            StringLiteral(s) => s.clone(),
+            value => Inspector::default().value(value, 0),
+        }
+    }
+}
+
+/// The state of one `inspect` : the objects being shown, to find cycles, and
+/// the ones a cycle leads back to, numbered in the order they are found. Like
+/// node, the indentation and the depth of the last object shown decide if an
+/// object fits on a line
+#[derive(Default)]
+struct Inspector {
+    seen: Vec<*const ()>,
+    circular: Vec<*const ()>,
+    indentation: usize,
+    current_depth: usize,
+}
+
+impl Inspector {
+    /// An object inside itself is `[Circular *1]`, the object is then
+    /// `<ref *1> { .. }`
+    fn value(&mut self, value: &Literal, depth: usize) -> String {
+        let identity = match value {
+            ArrayLiteral(a) => Rc::as_ptr(a) as *const (),
+            ObjectLiteral(o) => Rc::as_ptr(o) as *const (),
+            StringLiteral(s) => return self.string(s),
+            NumericLiteral(n) if *n == 0.0 && n.is_sign_negative() => return "-0".to_string(),
+            other => return other.clone().to_string(),
+        };
+        if self.seen.contains(&identity) {
+            return format!("[Circular *{}]", self.circular_index(identity));
+        }
+        match value {
+            ArrayLiteral(a) => self.array(a, identity, depth),
+            ObjectLiteral(o) => self.object(o, identity, depth),
+            _ => unreachable!(),
+        }
+    }
+
+    fn circular_index(&mut self, identity: *const ()) -> usize {
+        match self.circular.iter().position(|circular| *circular == identity) {
+            Some(i) => i + 1,
+            None => {
+                self.circular.push(identity);
+                self.circular.len()
+            }
+        }
+    }
+
+    /// Quoted and escaped, a long string is split after its line breaks
+    fn string(&self, s: &str) -> String {
+        let length = js_length(s);
+        if length <= 16 || length + self.indentation + 4 <= BREAK_LENGTH {
+            return quote(s);
+        }
+        let lines: Vec<String> = s.split_inclusive('\n').map(quote).collect();
+        lines.join(&format!(" +\n{}", " ".repeat(self.indentation + 2)))
+    }
+
+    /// `[ 1, 2 ]`, `[Array]` past the depth limit
+    fn array(&mut self, array: &Array, identity: *const (), depth: usize) -> String {
+        let array = array.borrow();
+        if array.is_empty() {
+            return "[]".to_string();
+        }
+        if depth > DEPTH {
+            return "[Array]".to_string();
+        }
+
+        self.seen.push(identity);
+        self.current_depth = depth + 1;
+        let mut output: Vec<String> = array.iter().take(MAX_ITEMS)
+            .map(|item| self.property(item, depth + 1))
+            .collect();
+        if array.len() > MAX_ITEMS {
+            let more = array.len() - MAX_ITEMS;
+            output.push(format!("... {} more item{}", more, if more > 1 { "s" } else { "" }));
+        }
+        self.seen.pop();
+
+        let entries = output.len();
+        if entries > 6 {
+            output = self.group_array_elements(output, &array);
+        }
+        let single_line = entries == output.len();
+        self.reduce_to_single_string(output, identity, "[", "]", depth + 1, single_line)
+    }
+
+    /// `P { a: 1 }`, `[P]` past the depth limit
+    fn object(&mut self, object: &Object, identity: *const (), depth: usize) -> String {
+        let object = object.borrow();
+        let name = match &object.constructor {
+            Some(constructor) if !constructor.function.name.is_empty() => constructor.function.name.clone(),
+            _ => "Object".to_string(),
+        };
+        let open = if name == "Object" { "{".to_string() } else { format!("{} {{", name) };
+        if object.entries().is_empty() {
+            return format!("{}}}", open);
+        }
+        if depth > DEPTH {
+            return format!("[{}]", name);
+        }
+
+        self.seen.push(identity);
+        self.current_depth = depth + 1;
+        let output: Vec<String> = object.entries().iter()
+            .map(|(key, value)| format!("{}: {}", inspect_key(key), self.property(value, depth + 1)))
+            .collect();
+        self.seen.pop();
+        self.reduce_to_single_string(output, identity, &open, "}", depth + 1, true)
+    }
+
+    /// An item or a property value, indented by 2 when the object takes several lines
+    fn property(&mut self, value: &Literal, depth: usize) -> String {
+        self.indentation += 2;
+        let shown = self.value(value, depth);
+        self.indentation -= 2;
+        shown
+    }
+
+    /// `{ a, b }` if the innermost levels fit on a line, else one entry per line
+    fn reduce_to_single_string(&mut self, output: Vec<String>, identity: *const (), open: &str, close: &str,
+                               depth: usize, single_line: bool) -> String {
+        let base = match self.circular.iter().position(|circular| *circular == identity) {
+            Some(i) => format!("<ref *{}> ", i + 1),
+            None => String::new(),
+        };
+        if single_line && self.current_depth - depth < COMPACT {
+            // the base is counted without its space
+            let start = output.len() + self.indentation + js_length(open) + js_length(&base).saturating_sub(1) + 10;
+            if self.is_below_break_length(&output, start) {
+                let joined = output.join(", ");
+                if !joined.contains('\n') {
+                    return format!("{}{} {} {}", base, open, joined, close);
+                }
+            }
+        }
+        let indentation = format!("\n{}", " ".repeat(self.indentation));
+        format!("{}{}{}  {}{}{}", base, open, indentation, output.join(&format!(",{}  ", indentation)), indentation, close)
+    }
+
+    fn is_below_break_length(&self, output: &[String], start: usize) -> bool {
+        let mut total_length = output.len() + start;
+        if total_length + output.len() > BREAK_LENGTH {
+            return false;
+        }
+        for entry in output {
+            total_length += js_length(entry);
+            if total_length > BREAK_LENGTH {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Node lays out the short items of long arrays in aligned columns, numbers
+    /// are aligned on the right
+    fn group_array_elements(&self, output: Vec<String>, array: &[Literal]) -> Vec<String> {
+        let separator_space = 2;
+        let output_length = if array.len() > MAX_ITEMS { output.len() - 1 } else { output.len() };
+        let data_length: Vec<usize> = output.iter().map(|entry| js_length(entry)).collect();
+        let total_length: usize = data_length[..output_length].iter().map(|length| length + separator_space).sum();
+        let max_length = data_length[..output_length].iter().copied().max().unwrap_or(0);
+        let actual_max = max_length + separator_space;
+        if actual_max * 3 + self.indentation >= BREAK_LENGTH
+            || (total_length as f64 / actual_max as f64 <= 5.0 && max_length > 6) {
+            return output;
+        }
+
+        let approx_char_heights = 2.5;
+        let average_bias = (actual_max as f64 - total_length as f64 / output.len() as f64).sqrt();
+        let biased_max = (actual_max as f64 - 3.0 - average_bias).max(1.0);
+        let columns = *[
+            // javascript Math.round
+            ((approx_char_heights * biased_max * output_length as f64).sqrt() / biased_max + 0.5).floor() as usize,
+            (BREAK_LENGTH - self.indentation) / actual_max,
+            COMPACT * 4,
+            15,
+        ].iter().min().unwrap();
+        if columns <= 1 {
+            return output;
+        }
+
+        let max_line_length: Vec<usize> = (0..columns).map(|i| {
+            (i..output_length).step_by(columns).map(|j| data_length[j]).max().unwrap_or(0) + separator_space
+        }).collect();
+        let numbers = array.iter().take(output.len()).all(|item| matches!(item, NumericLiteral(_) | Infinity));
+        let mut grouped = vec![];
+        for i in (0..output_length).step_by(columns) {
+            let max = (i + columns).min(output_length);
+            let mut line = String::new();
+            for j in i..max - 1 {
+                line.push_str(&pad(&format!("{}, ", output[j]), max_line_length[j - i], numbers));
+            }
+            let j = max - 1;
+            if numbers {
+                line.push_str(&pad(&output[j], max_line_length[j - i] - separator_space, true));
+            } else {
+                line.push_str(&output[j]);
+            }
+            grouped.push(line);
+        }
+        if output_length < output.len() {
+            grouped.push(output[output_length].clone());
+        }
+        grouped
+    }
+}
+
+/// The length javascript gives, in UTF-16 code units
+fn js_length(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// `padStart` or `padEnd` with spaces
+fn pad(s: &str, width: usize, start: bool) -> String {
+    let padding = " ".repeat(width.saturating_sub(js_length(s)));
+    if start { format!("{}{}", padding, s) } else { format!("{}{}", s, padding) }
+}
+
+/// Strings are single quoted, or double quoted or in backticks when they
+/// contain single quotes, with the escapes of node
+fn quote(s: &str) -> String {
+    let quote = if !s.contains('\'') {
+        '\''
+    } else if !s.contains('"') {
+        '"'
+    } else if !s.contains('`') && !s.contains("${") {
+        '`'
+    } else {
+        '\''
+    };
+    let mut quoted = quote.to_string();
+    for c in s.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            '\r' => quoted.push_str("\\r"),
+            '\\' => quoted.push_str("\\\\"),
+            '\'' if quote == '\'' => quoted.push_str("\\'"),
+            c if c < ' ' || ('\u{7f}'..='\u{9f}').contains(&c) => quoted.push_str(&format!("\\x{:02X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push(quote);
+    quoted
+}
+
+/// Object keys are shown quoted like node when they aren't ASCII identifiers
+fn inspect_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let identifier = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if identifier { key.to_string() } else { quote(key) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_quote_and_escape_strings_like_node() {
+        assert_eq!(quote("it's"), "\"it's\"");
+        assert_eq!(quote("say \"hi\""), "'say \"hi\"'");
+        assert_eq!(quote("both ' and \""), "`both ' and \"`");
+        assert_eq!(quote("all ' \" `"), "'all \\' \" `'");
+        assert_eq!(quote("line\nbreak\ttab\\ \u{1} \u{7f}"), "'line\\nbreak\\ttab\\\\ \\x01 \\x7F'");
+        assert_eq!(inspect_key("$a"), "'$a'");
+        assert_eq!(inspect_key("a1"), "a1");
+    }
+}
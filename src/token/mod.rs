@@ -12,6 +12,8 @@ use crate::token::token::Operator;
 use crate::token::token::Operator::*;
 use crate::token::token::Literal;
 use crate::token::token::Literal::*;
+use crate::token::token::Array;
+use crate::token::op_overload::string_to_number;
 use crate::token::token::Token;
 use crate::token::token::Token::*;
 use std::fmt::Display;
@@ -19,9 +21,15 @@ use std::fmt::Formatter;
 use std::fmt::Error;
 use std::fmt::Debug;
 use crate::token::token::Call;
+use crate::ast::expression::Loc;
+use crate::error::CompileError;
+use crate::error::CompileResult;
+use std::convert::TryFrom;
+use std::rc::Rc;
 
 pub mod token;
 pub mod op_overload;
+pub mod inspect;
 pub mod to_token;
 
 impl Operator {
@@ -39,15 +47,113 @@ impl Operator {
 }
 
 impl Literal {
-    pub fn to_string(self) -> String {
+    /// Javascript ToNumber, objects are converted to their string first
+    pub fn to_number(&self) -> f64 {
         match self {
-            NullLiteral => "null".to_string(),
-            Infinity => "Infinity".to_string(),
-            NumericLiteral(n) => format!("{}", n),
-            StringLiteral(s) => s,
-            BooleanLiteral(b) => if b { "1".to_string() } else { "0".to_string() }
+            NumericLiteral(n) => *n,
+            Infinity => f64::INFINITY,
+            BooleanLiteral(b) => if *b { 1.0 } else { 0.0 },
+            NullLiteral => 0.0,
+            Literal::Undefined | FunctionLiteral(_) => f64::NAN,
+            StringLiteral(s) => string_to_number(s),
+            ObjectLiteral(_) | ArrayLiteral(_) => string_to_number(&self.clone().to_string()),
         }
     }
+
+    /// Javascript ToInt32, the number modulo 2^32 as a signed integer
+    pub fn to_int32(&self) -> i32 {
+        self.to_uint32() as i32
+    }
+
+    /// Javascript ToUint32, NaN and infinities are 0
+    pub fn to_uint32(&self) -> u32 {
+        let n = self.to_number();
+        if n.is_finite() {
+            n.trunc().rem_euclid(4294967296.0) as u32
+        } else {
+            0
+        }
+    }
+
+    /// What `typeof` gives
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            NumericLiteral(_) | Infinity => "number",
+            StringLiteral(_) => "string",
+            BooleanLiteral(_) => "boolean",
+            Literal::Undefined => "undefined",
+            FunctionLiteral(_) => "function",
+            NullLiteral | ObjectLiteral(_) | ArrayLiteral(_) => "object",
+        }
+    }
+
+    /// Javascript truthiness
+    pub fn to_bool(&self) -> bool {
+        match self {
+            NullLiteral | Literal::Undefined => false,
+            Infinity => true,
+            NumericLiteral(n) => *n != 0.0 && !n.is_nan(),
+            StringLiteral(s) => !s.is_empty(),
+            BooleanLiteral(b) => *b,
+            ObjectLiteral(_) | ArrayLiteral(_) | FunctionLiteral(_) => true,
+        }
+    }
+}
+
+/// `Number.prototype.toString`, the shortest digits reading back as `n` are
+/// written in decimal from 1e-6 up to 1e21 and in exponent form outside. The
+/// C runtime's `number_to_string` lays the digits out the same way.
+pub fn number_to_string(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    } else if n == 0.0 {
+        return "0".to_string();
+    } else if n < 0.0 {
+        return format!("-{}", number_to_string(-n));
+    } else if n.is_infinite() {
+        return "Infinity".to_string();
+    }
+    // rust's exponent form has the shortest round-trip digits, `1.25e-7`
+    let scientific = format!("{:e}", n);
+    let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap());
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // the value is 0.digits * 10^point
+    let point = exponent[1..].parse::<i32>().unwrap() + 1;
+    if k <= point && point <= 21 {
+        format!("{}{}", digits, "0".repeat((point - k) as usize))
+    } else if 0 < point && point <= 21 {
+        format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else {
+        let sign = if point - 1 < 0 { '-' } else { '+' };
+        let fraction = if k == 1 { String::new() } else { format!(".{}", &digits[1..]) };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, (point - 1).abs())
+    }
+}
+
+/// `Array.prototype.join` with its default separator, null and undefined are
+/// empty. `joining` are the arrays being joined, like javascript an array
+/// inside itself is empty too
+fn join(array: &Array, joining: &mut Vec<*const ()>) -> String {
+    let identity = Rc::as_ptr(array) as *const ();
+    if joining.contains(&identity) {
+        return String::new();
+    }
+    joining.push(identity);
+    let items: Vec<String> = array.borrow().iter().map(|item| match item {
+        NullLiteral | Literal::Undefined => String::new(),
+        ArrayLiteral(inner) => join(inner, joining),
+        item => item.clone().to_string(),
+    }).collect();
+    joining.pop();
+    items.join(",")
+}
+
+/// Parse a javascript operator, an unknown operator is reported at `loc`
+pub fn operator<'op, T: TryFrom<&'op str, Error = String>>(str_op: &'op str, loc: &Loc) -> CompileResult<T> {
+    T::try_from(str_op).map_err(|op| CompileError::unknown_operator(&op, loc))
 }
 
 pub trait Precedence {
@@ -58,6 +164,7 @@ pub trait Precedence {
 impl Precedence for BinaryOperator {
     fn get_precedence(a: &BinaryOperator, b: &BinaryOperator) -> bool {
         match (a, b) {
+            (Exp, _) => true,
             (Mul, _) => true,
             (Div, _) => true,
             (Mod, _) => true,
@@ -93,21 +200,34 @@ impl Precedence for Operator {
     }
 }
 
-impl From<&str> for BinaryOperator {
-    fn from(str_op: &str) -> Self {
+impl TryFrom<&str> for BinaryOperator {
+    type Error = String;
+
+    fn try_from(str_op: &str) -> Result<Self, String> {
         match str_op {
-            "+" => Add,
-            "-" => Sub,
-            "*" => Mul,
-            "/" => Div,
-            "%" => Mod,
-            "<" => LessThan,
-            "<=" => LessThanOrEq,
-            ">" => GreaterThan,
-            ">=" => GreaterThanOrEq,
-            "!=" => PartialEq,
-            "==" => StrictEq,
-            _ => panic!("Unkown token")
+            "+" => Ok(Add),
+            "-" => Ok(Sub),
+            "*" => Ok(Mul),
+            "/" => Ok(Div),
+            "%" => Ok(Mod),
+            "<" => Ok(LessThan),
+            "<=" => Ok(LessThanOrEq),
+            ">" => Ok(GreaterThan),
+            ">=" => Ok(GreaterThanOrEq),
+            "==" => Ok(LooseEq),
+            "!=" => Ok(LooseNotEq),
+            "===" => Ok(StrictEq),
+            "!==" => Ok(StrictNotEq),
+            "**" => Ok(Exp),
+            "&" => Ok(BitAnd),
+            "|" => Ok(BitOr),
+            "^" => Ok(BitXor),
+            "<<" => Ok(ShiftLeft),
+            ">>" => Ok(ShiftRight),
+            ">>>" => Ok(UnsignedShiftRight),
+            "in" => Ok(In),
+            "instanceof" => Ok(InstanceOf),
+            _ => Err(str_op.to_string()),
         }
     }
 }
@@ -124,19 +244,33 @@ impl BinaryOperator {
             LessThanOrEq => "<=",
             GreaterThan => ">",
             GreaterThanOrEq => ">=",
-            PartialEq => "!=",
-            StrictEq => "==",
+            LooseEq => "==",
+            LooseNotEq => "!=",
+            StrictEq => "===",
+            StrictNotEq => "!==",
+            Exp => "**",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
+            ShiftLeft => "<<",
+            ShiftRight => ">>",
+            UnsignedShiftRight => ">>>",
+            In => "in",
+            InstanceOf => "instanceof",
         }
     }
 }
 
 
-impl From<&str> for LogicalOperator {
-    fn from(str_op: &str) -> Self {
+impl TryFrom<&str> for LogicalOperator {
+    type Error = String;
+
+    fn try_from(str_op: &str) -> Result<Self, String> {
         match str_op {
-            "||" => Or,
-            "&&" => And,
-            _ => panic!("Unkown token")
+            "||" => Ok(Or),
+            "&&" => Ok(And),
+            "??" => Ok(Coalesce),
+            _ => Err(str_op.to_string()),
         }
     }
 }
@@ -146,20 +280,32 @@ impl LogicalOperator {
         match self {
             Or => "||",
             And => "&&",
-            _ => "NoP"
+            Coalesce => "??",
+        }
+    }
+
+    /// True if the expression evaluates to its left operand, the right one is
+    /// then never evaluated
+    pub fn short_circuits(&self, left: &Literal) -> bool {
+        match self {
+            Or => left.to_bool(),
+            And => !left.to_bool(),
+            Coalesce => !matches!(left, NullLiteral | Literal::Undefined),
         }
     }
 }
 
-impl From<&str> for AssignmentOperator {
-    fn from(str_op: &str) -> Self {
+impl TryFrom<&str> for AssignmentOperator {
+    type Error = String;
+
+    fn try_from(str_op: &str) -> Result<Self, String> {
         match str_op {
-            "+=" => AddAssign,
-            "-=" => SubAssign,
-            "/=" => DivAssign,
-            "*=" => MulAssign,
-            "%=" => ModAssign,
-            _ => panic!("Unkown token")
+            "+=" => Ok(AddAssign),
+            "-=" => Ok(SubAssign),
+            "/=" => Ok(DivAssign),
+            "*=" => Ok(MulAssign),
+            "%=" => Ok(ModAssign),
+            _ => Err(str_op.to_string()),
         }
     }
 }
@@ -176,12 +322,14 @@ impl AssignmentOperator {
     }
 }
 
-impl From<&str> for UpdateOperator {
-    fn from(str_op: &str) -> Self {
+impl TryFrom<&str> for UpdateOperator {
+    type Error = String;
+
+    fn try_from(str_op: &str) -> Result<Self, String> {
         match str_op {
-            "--" => Decrement,
-            "++" => Increment,
-            _ => panic!("Unkown token")
+            "--" => Ok(Decrement),
+            "++" => Ok(Increment),
+            _ => Err(str_op.to_string()),
         }
     }
 }
@@ -191,22 +339,23 @@ impl UpdateOperator {
         match self {
             Increment => "++",
             Decrement => "--",
-            _ => "NoP"
         }
     }
 }
 
-impl From<&str> for UnaryOperator {
-    fn from(str_op: &str) -> Self {
+impl TryFrom<&str> for UnaryOperator {
+    type Error = String;
+
+    fn try_from(str_op: &str) -> Result<Self, String> {
         match str_op {
-            "+" => Plus,
-            "-" => Minus,
-            "!" => ExPoint,
-            "~" => Tilde,
-            "void" => Void,
-            "typeof" => TypeOf,
-            "delete" => Delete,
-            _ => panic!("Unkown token")
+            "+" => Ok(Plus),
+            "-" => Ok(Minus),
+            "!" => Ok(ExPoint),
+            "~" => Ok(Tilde),
+            "void" => Ok(Void),
+            "typeof" => Ok(TypeOf),
+            "delete" => Ok(Delete),
+            _ => Err(str_op.to_string()),
         }
     }
 }
@@ -218,10 +367,9 @@ impl UnaryOperator {
             Minus => "-",
             ExPoint => "!",
             Tilde => "~",
-            TypeOf => "void",
-            Void => "typeof",
+            TypeOf => "typeof",
+            Void => "void",
             Delete => "delete",
-            _ => "NoP"
         }
     }
 }
@@ -244,34 +392,33 @@ impl Debug for Token {
         match self {
             Token::LiteralToken(literal) => write!(f, "{}", literal),
             Token::OperatorToken(op) => write!(f, "{}", op.as_str()),
-            Token::Undefined => write!(f, "{}", "Undefined"),
+            Token::Undefined => write!(f, "Undefined"),
             Token::IdendifierToken(id) => write!(f, "{}", id),
             Token::FunctionToken(Call { args, callee }) => write!(f, "Call {:?} {:?}", callee, args),
         }
     }
 }
 
+/// Javascript ToString
 impl Display for Literal {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         match self {
             StringLiteral(string) => write!(f, "{}", string),
-            NumericLiteral(num) => write!(f, "{}", num),
+            NumericLiteral(num) => write!(f, "{}", number_to_string(*num)),
             BooleanLiteral(boolean) => write!(f, "{}", boolean),
-            NullLiteral => write!(f, "{}", "Null"),
-            Infinity => write!(f, "{}", "Infinity"),
+            NullLiteral => write!(f, "null"),
+            Literal::Undefined => write!(f, "undefined"),
+            Infinity => write!(f, "Infinity"),
+            ObjectLiteral(_) => write!(f, "[object Object]"),
+            ArrayLiteral(array) => write!(f, "{}", join(array, &mut vec![])),
+            FunctionLiteral(function) => write!(f, "{}", function),
         }
     }
 }
 
 impl Debug for Literal {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        match self {
-            StringLiteral(string) => write!(f, "{}", string),
-            NumericLiteral(num) => write!(f, "{}", num),
-            BooleanLiteral(boolean) => write!(f, "{}", boolean),
-            NullLiteral => write!(f, "{}", "Null"),
-            Infinity => write!(f, "{}", "Infinity"),
-        }
+        write!(f, "{}", self)
     }
 }
 
@@ -333,7 +480,7 @@ impl Token {
     pub fn as_operator(&self) -> Result<Operator, Error> {
         match self {
             OperatorToken(op) => Ok(op.clone()),
-            _ => panic!("not an operator")
+            _ => Err(Error)
         }
     }
 }
@@ -343,6 +490,7 @@ mod tests {
     use crate::token::token::Operator;
     use crate::token::token::BinaryOperator::*;
     use crate::token::Precedence;
+    use crate::token::number_to_string;
 
     #[test]
     fn mul_shall_have_precedence() {
@@ -353,5 +501,17 @@ mod tests {
 
         assert_eq!(true, as_precedence)
     }
+
+    /// The cases are shared with the C runtime tests, the expected strings are
+    /// what node prints
+    #[test]
+    fn numbers_shall_be_written_like_node() {
+        let cases = include_str!("../../c_datalib/number_cases.h");
+        for case in cases.lines().filter_map(|line| line.strip_prefix("NUMBER_CASE(")) {
+            let (number, expected) = case.trim_end_matches(')').split_once(", ").unwrap();
+            let number: f64 = number.parse().unwrap();
+            assert_eq!(number_to_string(number), expected.trim_matches('"'), "{}", case);
+        }
+    }
 }
 
@@ -6,21 +6,16 @@ use std::ops::Div;
 use std::ops::Rem;
 use std::cmp::Ordering;
 use std::ops::Mul;
+use std::rc::Rc;
 
+/// Javascript `+`: when either primitive is a string both are concatenated as
+/// strings, otherwise they are added as numbers
 impl Add<Literal> for Literal {
     type Output = Literal;
     fn add(self, rhs: Literal) -> Self::Output {
-        match (self, rhs) {
-            (StringLiteral(a), StringLiteral(b)) => StringLiteral(format!("{}{}", a, b)),
-            (NumericLiteral(a), StringLiteral(b)) => StringLiteral(format!("{}{}", a, b)),
-            (StringLiteral(a), NumericLiteral(b)) => StringLiteral(format!("{}{}", a, b)),
-            (StringLiteral(a), NullLiteral) => StringLiteral(a),
-            (NullLiteral, StringLiteral(b)) => StringLiteral(b),
-            (NumericLiteral(a), NumericLiteral(b)) => NumericLiteral(a + b),
-            (Infinity, _) => Infinity,
-            (_, Infinity) => Infinity,
-            (NullLiteral, NullLiteral) => NullLiteral,
-            _ => NullLiteral
+        match (self.into_primitive(), rhs.into_primitive()) {
+            (a @ StringLiteral(_), b) | (a, b @ StringLiteral(_)) => StringLiteral(format!("{}{}", a, b)),
+            (a, b) => NumericLiteral(a.to_number() + b.to_number()),
         }
     }
 }
@@ -28,147 +23,44 @@ impl Add<Literal> for Literal {
 impl Sub<Literal> for Literal {
     type Output = Literal;
     fn sub(self, rhs: Literal) -> Self::Output {
-        match (self, rhs) {
-            (StringLiteral(a), StringLiteral(b)) =>
-                {
-                    let result = a.replace(b.as_str(), "");
-                    StringLiteral(result)
-                }
-            (NumericLiteral(..), StringLiteral(..)) =>
-                {
-                    panic!("Are you trying to substract a string from a numeric literal your silly!?")
-                }
-            (StringLiteral(a), NumericLiteral(b)) =>
-                {
-                    let mut copy = a.clone();
-                    copy.remove(b as usize);
-                    StringLiteral(copy)
-                }
-            (NumericLiteral(a), NumericLiteral(b)) => NumericLiteral(a - b),
-            (Infinity, _) => Infinity,
-            (_, Infinity) => Infinity,
-            (StringLiteral(a), NullLiteral) => StringLiteral(a),
-            (NullLiteral, StringLiteral(b)) => StringLiteral(b),
-            (NullLiteral, NullLiteral) => NullLiteral,
-            _ => NullLiteral
-        }
+        NumericLiteral(self.to_number() - rhs.to_number())
     }
 }
 
-
 impl Mul<Literal> for Literal {
     type Output = Literal;
     fn mul(self, rhs: Literal) -> Self::Output {
-        match (self, rhs) {
-            (StringLiteral(a), StringLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    a.chars().for_each(|a_char| {
-                        result.push(a_char);
-                        b.chars().for_each(|b_char| result.push(b_char));
-                    });
-                    StringLiteral(result)
-                }
-            (NumericLiteral(a), StringLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    for i in 0..a as i64 {
-                        result.push_str(b.as_str());
-                    }
-                    StringLiteral(result)
-                }
-            (StringLiteral(a), NumericLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    for i in 0..b as i64 {
-                        result.push_str(a.as_str());
-                    }
-                    StringLiteral(result)
-                }
-
-            (NumericLiteral(a), NumericLiteral(b)) => NumericLiteral(a * b),
-            (Infinity, _) => Infinity,
-            (_, Infinity) => Infinity,
-            (StringLiteral(..), NullLiteral) => NullLiteral,
-            (NullLiteral, StringLiteral(..)) => NullLiteral,
-            (NullLiteral, NullLiteral) => NullLiteral,
-            _ => NullLiteral
-        }
+        NumericLiteral(self.to_number() * rhs.to_number())
     }
 }
 
 impl Div<Literal> for Literal {
     type Output = Literal;
     fn div(self, rhs: Literal) -> Self::Output {
-        match (self, rhs) {
-            (StringLiteral(a), StringLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    a.chars().for_each(|a_char| {
-                        result.push(a_char);
-                        b.chars().for_each(|b_char| result.push(b_char));
-                    });
-                    StringLiteral(result)
-                }
-
-            (NumericLiteral(a), StringLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    for i in 0..a as i64 {
-                        result.push_str(b.as_str());
-                    }
-                    StringLiteral(result)
-                }
-
-            (StringLiteral(a), NumericLiteral(b)) =>
-                {
-                    let mut result: String = String::new();
-                    for i in 0..b as i64 {
-                        result.push_str(a.as_str());
-                    }
-                    StringLiteral(result)
-                }
-            (NumericLiteral(a), NumericLiteral(b)) =>
-                {
-                    if b as i64 == 0 {
-                        Infinity
-                    } else {
-                        NumericLiteral(a / b)
-                    }
-                }
-            (StringLiteral(..), NullLiteral) => NullLiteral,
-            (NullLiteral, StringLiteral(..)) => NullLiteral,
-            (NullLiteral, NullLiteral) => NullLiteral,
-            (Infinity, _) => Infinity,
-            (_, Infinity) => Infinity,
-            _ => NullLiteral
-        }
+        NumericLiteral(self.to_number() / rhs.to_number())
     }
 }
 
 impl Rem<Literal> for Literal {
     type Output = Literal;
     fn rem(self, rhs: Literal) -> Self::Output {
-        match (self, rhs) {
-            (NumericLiteral(a), NumericLiteral(b)) => NumericLiteral(a % b),
-            _ => panic!("Rem not implemented")
-        }
+        NumericLiteral(self.to_number() % rhs.to_number())
     }
 }
 
+/// Javascript `===`, values of different types are never equal and objects are
+/// only equal to themselves
 impl PartialEq for Literal {
     fn eq(&self, other: &Literal) -> bool {
         match (self, other) {
             (StringLiteral(a), StringLiteral(b)) => a.eq(b),
-            (NumericLiteral(a), StringLiteral(b)) => format!("{}", a).eq(b),
-            (StringLiteral(a), NumericLiteral(b)) => format!("{}", b).eq(a),
-            (StringLiteral(a), NullLiteral) => a.eq(&NullLiteral.to_string()),
-            (NullLiteral, StringLiteral(b)) => b.eq(&NullLiteral.to_string()),
-            (NumericLiteral(a), NumericLiteral(b)) => a == b,
-            (Infinity, Infinity) => true,
-            (Infinity, StringLiteral(b)) => b.eq(&Infinity.to_string()),
-            (StringLiteral(a), Infinity) => a.eq(&Infinity.to_string()),
+            (a, b) if a.as_number().is_some() && b.as_number().is_some() => a.as_number() == b.as_number(),
             (NullLiteral, NullLiteral) => true,
+            (BooleanLiteral(a), BooleanLiteral(b)) => a == b,
+            (Undefined, Undefined) => true,
+            (ObjectLiteral(a), ObjectLiteral(b)) => Rc::ptr_eq(a, b),
+            (ArrayLiteral(a), ArrayLiteral(b)) => Rc::ptr_eq(a, b),
+            (FunctionLiteral(a), FunctionLiteral(b)) => Rc::ptr_eq(a, b),
             _ => false
         }
     }
@@ -176,21 +68,155 @@ impl PartialEq for Literal {
     fn ne(&self, other: &Literal) -> bool { !self.eq(other) }
 }
 
+impl Literal {
+    /// Javascript `==`: `null` and `undefined` only equal each other, booleans
+    /// and strings compared to a number are converted to numbers, and objects
+    /// compared to a primitive are converted to a string
+    pub fn loose_eq(&self, other: &Literal) -> bool {
+        match (self, other) {
+            (NullLiteral, NullLiteral) | (NullLiteral, Undefined) | (Undefined, NullLiteral) | (Undefined, Undefined) => true,
+            (NullLiteral, _) | (Undefined, _) | (_, NullLiteral) | (_, Undefined) => false,
+            (BooleanLiteral(a), b) => NumericLiteral(if *a { 1.0 } else { 0.0 }).loose_eq(b),
+            (a, BooleanLiteral(b)) => a.loose_eq(&NumericLiteral(if *b { 1.0 } else { 0.0 })),
+            (StringLiteral(a), b) if b.as_number().is_some() => Some(string_to_number(a)) == b.as_number(),
+            (a, StringLiteral(b)) if a.as_number().is_some() => a.as_number() == Some(string_to_number(b)),
+            (a, b) if a.is_object() && !b.is_object() => a.clone().into_primitive().loose_eq(b),
+            (a, b) if !a.is_object() && b.is_object() => a.loose_eq(&b.clone().into_primitive()),
+            (a, b) => a == b,
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            NumericLiteral(n) => Some(*n),
+            Infinity => Some(f64::INFINITY),
+            _ => None,
+        }
+    }
+
+    /// Javascript ToPrimitive without a hint, objects become their string
+    fn into_primitive(self) -> Literal {
+        if self.is_object() { StringLiteral(self.to_string()) } else { self }
+    }
+
+    fn is_object(&self) -> bool {
+        matches!(self, ObjectLiteral(_) | ArrayLiteral(_) | FunctionLiteral(_))
+    }
+}
+
+/// The number a string converts to: blanks are 0, decimal, `Infinity` and
+/// `0x`, `0o` and `0b` prefixed literals are parsed, anything else is NaN
+pub fn string_to_number(string: &str) -> f64 {
+    let trimmed = string.trim();
+    let radix = |prefix: &str, radix: u32| {
+        trimmed.strip_prefix(prefix).map(|digits| u64::from_str_radix(digits, radix).map_or(f64::NAN, |n| n as f64))
+    };
+    let unsigned = trimmed.trim_start_matches(['+', '-']);
+    if trimmed.is_empty() {
+        0.0
+    } else if unsigned == "Infinity" {
+        if trimmed.starts_with('-') { f64::NEG_INFINITY } else { f64::INFINITY }
+    } else if let Some(n) = radix("0x", 16).or_else(|| radix("0X", 16)).or_else(|| radix("0o", 8))
+        .or_else(|| radix("0O", 8)).or_else(|| radix("0b", 2)).or_else(|| radix("0B", 2)) {
+        n
+    } else if unsigned.chars().any(|c| c.is_alphabetic() && c != 'e' && c != 'E') {
+        // rust also parses `inf` and `NaN`
+        f64::NAN
+    } else {
+        trimmed.parse().unwrap_or(f64::NAN)
+    }
+}
+
+/// Javascript `<`, `<=`, `>` and `>=`: objects are converted to their string,
+/// two strings are compared by code units and anything else as numbers, NaN
+/// isn't ordered
 impl PartialOrd for Literal {
     fn partial_cmp(&self, other: &Literal) -> Option<Ordering> {
-        match (self, other) {
-            (StringLiteral(a), StringLiteral(b)) => a.partial_cmp(b),
-            (NumericLiteral(a), NumericLiteral(b)) => a.partial_cmp(b),
-            (NumericLiteral(a), StringLiteral(b)) => format!("{}", a).partial_cmp(b),
-            (StringLiteral(a), NumericLiteral(b)) => format!("{}", b).partial_cmp(a),
-            (NullLiteral, StringLiteral(b)) => b.partial_cmp(&NullLiteral.to_string()),
-            (NullLiteral, NullLiteral) => Some(Ordering::Equal),
-            (NullLiteral, _) => Some(Ordering::Less),
-            (_, NullLiteral) => Some(Ordering::Greater),
-            (Infinity, Infinity) => Some(Ordering::Equal),
-            (Infinity, _) => Some(Ordering::Greater),
-            (_, Infinity) => Some(Ordering::Less),
-            _ => None
+        match (self.clone().into_primitive(), other.clone().into_primitive()) {
+            (StringLiteral(a), StringLiteral(b)) => a.encode_utf16().partial_cmp(b.encode_utf16()),
+            (a, b) => a.to_number().partial_cmp(&b.to_number()),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::ast::expression::{Loc, Pos};
+    use crate::token::token::{Literal, Operator, Properties};
+    use crate::token::token::BinaryOperator::*;
+    use crate::token::token::Literal::*;
+
+    fn string(s: &str) -> Literal { StringLiteral(s.to_string()) }
+
+    fn array(items: Vec<Literal>) -> Literal { ArrayLiteral(Rc::new(RefCell::new(items))) }
+
+    fn object() -> Literal { ObjectLiteral(Rc::new(RefCell::new(Properties::default()))) }
+
+    /// Each case is `typeof` and `String()` of the result as printed by node
+    #[test]
+    fn arithmetic_shall_match_node() {
+        let cases = vec![
+            (NumericLiteral(1.0), Add, NumericLiteral(2.5), "number 3.5"),
+            (string("3"), Add, NumericLiteral(1.0), "string 31"),
+            (NumericLiteral(1.0), Add, string("3"), "string 13"),
+            (string("abc"), Add, NullLiteral, "string abcnull"),
+            (NullLiteral, Add, string("abc"), "string nullabc"),
+            (string("abc"), Add, Undefined, "string abcundefined"),
+            (NullLiteral, Add, NullLiteral, "number 0"),
+            (NullLiteral, Add, NumericLiteral(1.0), "number 1"),
+            (Undefined, Add, NumericLiteral(1.0), "number NaN"),
+            (BooleanLiteral(true), Add, BooleanLiteral(true), "number 2"),
+            (BooleanLiteral(true), Add, string("3"), "string true3"),
+            (Infinity, Add, NumericLiteral(1.0), "number Infinity"),
+            (Infinity, Add, string("abc"), "string Infinityabc"),
+            (NumericLiteral(-1.0), Add, Infinity, "number Infinity"),
+            (array(vec![NumericLiteral(1.0), NumericLiteral(2.0)]), Add, NumericLiteral(1.0), "string 1,21"),
+            (array(vec![]), Add, array(vec![]), "string "),
+            (object(), Add, NumericLiteral(1.0), "string [object Object]1"),
+            (array(vec![NumericLiteral(5.0)]), Add, object(), "string 5[object Object]"),
+            (array(vec![]), Add, NullLiteral, "string null"),
+            (string("abc"), Sub, string("b"), "number NaN"),
+            (string("abc"), Sub, NumericLiteral(1.0), "number NaN"),
+            (NumericLiteral(1.0), Sub, string("3"), "number -2"),
+            (string("3"), Sub, string("3"), "number 0"),
+            (string(" 12 "), Sub, NumericLiteral(2.5), "number 9.5"),
+            (string("0x10"), Sub, NumericLiteral(1.0), "number 15"),
+            (string(""), Sub, NumericLiteral(1.0), "number -1"),
+            (NullLiteral, Sub, NumericLiteral(1.0), "number -1"),
+            (Undefined, Sub, NumericLiteral(1.0), "number NaN"),
+            (BooleanLiteral(false), Sub, BooleanLiteral(true), "number -1"),
+            (Infinity, Sub, Infinity, "number NaN"),
+            (NumericLiteral(0.0), Sub, Infinity, "number -Infinity"),
+            (array(vec![NumericLiteral(5.0)]), Sub, NumericLiteral(1.0), "number 4"),
+            (array(vec![NumericLiteral(1.0), NumericLiteral(2.0)]), Sub, NumericLiteral(1.0), "number NaN"),
+            (string("3"), Mul, string("3"), "number 9"),
+            (string("abc"), Mul, NumericLiteral(1.0), "number NaN"),
+            (BooleanLiteral(true), Mul, NumericLiteral(2.5), "number 2.5"),
+            (NullLiteral, Mul, Infinity, "number NaN"),
+            (NumericLiteral(-1.0), Mul, Infinity, "number -Infinity"),
+            (array(vec![NumericLiteral(5.0)]), Mul, array(vec![NumericLiteral(5.0)]), "number 25"),
+            (object(), Mul, NumericLiteral(1.0), "number NaN"),
+            (NumericLiteral(1.0), Div, NumericLiteral(0.0), "number Infinity"),
+            (NumericLiteral(-1.0), Div, NumericLiteral(0.0), "number -Infinity"),
+            (NumericLiteral(0.0), Div, NumericLiteral(0.0), "number NaN"),
+            (NumericLiteral(1.0), Div, NumericLiteral(2.5), "number 0.4"),
+            (string("3"), Div, string("b"), "number NaN"),
+            (NumericLiteral(1.0), Div, Infinity, "number 0"),
+            (Infinity, Div, Infinity, "number NaN"),
+            (NumericLiteral(1.0), Div, NullLiteral, "number Infinity"),
+            (array(vec![]), Div, NumericLiteral(1.0), "number 0"),
+            // strings are ordered by UTF-16 code units, a surrogate is below U+E000
+            (string("\u{1F600}"), LessThan, string("\u{E000}"), "boolean true"),
+            (string("\u{E000}"), LessThan, string("\u{1F600}"), "boolean false"),
+            (string("\u{1F600}"), GreaterThan, string("\u{1F601}"), "boolean false"),
+        ];
+        let loc = Loc { start: Pos { line: 1.0, column: 0.0 }, end: Pos { line: 1.0, column: 0.0 } };
+        for (a, operator, b, expected) in cases {
+            let description = format!("{:?} {} {:?}", a, operator, b);
+            let result = Operator::BinOp(operator).solve(&a, &b, &loc).unwrap();
+            assert_eq!(format!("{} {}", result.type_of(), result), expected, "{}", description);
+        }
+    }
+}
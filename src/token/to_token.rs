@@ -10,6 +10,9 @@ use crate::token::token::Token::*;
 use crate::visitor::Visitor;
 use crate::token::Precedence;
 use std::path::Component::Prefix;
+use crate::token::operator;
+use crate::error::CompileError;
+use crate::error::CompileResult;
 
 #[derive(Debug)]
 pub struct Stack {
@@ -27,134 +30,151 @@ impl Stack {
 }
 
 pub trait ToToken {
-    fn to_token(&self) -> Vec<Token>;
+    fn to_token(&self) -> CompileResult<Vec<Token>>;
 }
 
 impl ToToken for Box<Expression> {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         match self {
             box Expression::BinaryExpression(bin_exp) => bin_exp.to_token(),
             box Expression::UnaryExpression(unary_exp) => unary_exp.to_token(),
             box Expression::NumericLiteral(numeric) => numeric.to_token(),
             box Expression::StringLiteral(string) => string.to_token(),
+            box Expression::BooleanLiteral(boolean) => Ok(vec![LiteralToken(Literal::BooleanLiteral(boolean.value))]),
+            box Expression::NullLiteral(_) => Ok(vec![LiteralToken(Literal::NullLiteral)]),
             box Expression::Identifier(identifier) => identifier.to_token(),
             box Expression::UpdateExpression(update) => update.to_token(),
             box Expression::CallExpression(call) => call.to_token(),
             box Expression::AssignmentExpression(assign) => assign.to_token(),
             box Expression::LogicalExpression(log) => log.to_token(),
             box Expression::MemberExpression(member) => member.to_token(),
-            box Expression::CallExpression(call) => call.to_token(),
             box Expression::ObjectExpression(object) => object.to_token(),
+            box Expression::Unsupported(node) => {
+                let feature = format!("`{}`", node.node_type);
+                Err(CompileError::unsupported(&feature, &node.loc))
+            }
+            other => {
+                let feature = format!("`{}`", other.type_name());
+                Err(CompileError::unsupported(&feature, &other.get_loc()))
+            }
         }
     }
 }
 
 impl ToToken for BinaryExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
         if let Some(extra) = &self.extra {
             token_stream.push(OperatorToken(RightParenthesis));
         };
-        let op: BinaryOperator = BinaryOperator::from(self.operator.as_str());
+        let op: BinaryOperator = operator(self.operator.as_str(), &self.loc)?;
         match &self.right {
             box NumericLiteral(num) => token_stream.push(Token::LiteralToken(Literal::NumericLiteral(num.value))),
             box StringLiteral(string) => token_stream.push(Token::LiteralToken(Literal::StringLiteral(string.value.clone()))),
-            box BinaryExpression(bin) => token_stream.append(&mut bin.to_token()),
-            _ => unimplemented!()
+            box BinaryExpression(bin) => token_stream.append(&mut bin.to_token()?),
+            other => return Err(unsupported_operand(other)),
         };
 
         token_stream.push(OperatorToken(Operator::BinOp(op)));
         match &self.left {
             box NumericLiteral(num) => token_stream.push(Token::LiteralToken(Literal::NumericLiteral(num.value))),
             box StringLiteral(string) => token_stream.push(Token::LiteralToken(Literal::StringLiteral(string.value.clone()))),
-            box BinaryExpression(bin) => token_stream.append(&mut bin.to_token()),
-            _ => unimplemented!()
-
+            box BinaryExpression(bin) => token_stream.append(&mut bin.to_token()?),
+            other => return Err(unsupported_operand(other)),
         };
         if let Some(extra) = &self.extra {
             token_stream.push(OperatorToken(LeftParenthesis));
         };
-        token_stream
+        Ok(token_stream)
     }
 }
 
+/// Only literals and nested binary expressions can be turned into a postfix expression
+fn unsupported_operand(operand: &Expression) -> CompileError {
+    let feature = format!("`{}` operand in a binary expression", operand.type_name());
+    CompileError::unsupported(&feature, &operand.get_loc())
+}
+
 impl ToToken for UnaryExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        let op = UnaryOperator::from(self.operator.as_str());
-        token_stream.extend_from_slice(self.argument.to_token().as_slice());
-        token_stream
+        operator::<UnaryOperator>(self.operator.as_str(), &self.loc)?;
+        token_stream.extend_from_slice(self.argument.to_token()?.as_slice());
+        Ok(token_stream)
     }
 }
 
 impl ToToken for StringLit {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
         let token = Token::LiteralToken(Literal::StringLiteral(self.value.clone()));
         token_stream.push(token);
-        token_stream
+        Ok(token_stream)
     }
 }
 
 impl ToToken for NumericLit {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
         let token = Token::LiteralToken(Literal::NumericLiteral(self.value));
         token_stream.push(token);
-        token_stream
+        Ok(token_stream)
     }
 }
 
 
 impl ToToken for Id {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
         let token = Token::IdendifierToken(self.name.clone());
         token_stream.push(token);
-        token_stream
+        Ok(token_stream)
     }
 }
 
 impl ToToken for UpdateExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        token_stream.extend_from_slice(self.argument.to_token().as_slice());
-        let op = UpdateOperator::from(self.operator.as_str());
+        token_stream.extend_from_slice(self.argument.to_token()?.as_slice());
+        let op: UpdateOperator = operator(self.operator.as_str(), &self.loc)?;
         token_stream.push(Token::OperatorToken(UpdateOp(op)));
-        token_stream
+        Ok(token_stream)
     }
 }
 
 impl ToToken for AssignmentExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        token_stream.extend_from_slice(self.left.to_token().as_slice());
-        let op = AssignmentOperator::from(self.operator.as_str());
-        token_stream.extend_from_slice(self.right.to_token().as_slice());
-        token_stream
+        token_stream.extend_from_slice(self.left.to_token()?.as_slice());
+        if self.operator != "=" {
+            operator::<AssignmentOperator>(self.operator.as_str(), &self.loc)?;
+        }
+        token_stream.extend_from_slice(self.right.to_token()?.as_slice());
+        Ok(token_stream)
     }
 }
 
 impl ToToken for LogicalExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        token_stream.extend_from_slice(self.left.to_token().as_slice());
-        let op = AssignmentOperator::from(self.operator.as_str());
-        token_stream.extend_from_slice(self.right.to_token().as_slice());
-        token_stream
+        token_stream.extend_from_slice(self.left.to_token()?.as_slice());
+        let op: LogicalOperator = operator(self.operator.as_str(), &self.loc)?;
+        token_stream.push(OperatorToken(LogOp(op)));
+        token_stream.extend_from_slice(self.right.to_token()?.as_slice());
+        Ok(token_stream)
     }
 }
 
 impl ToToken for MemberExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        token_stream.extend_from_slice(self.property.to_token().as_slice());
-        token_stream
+        token_stream.extend_from_slice(self.property.to_token()?.as_slice());
+        Ok(token_stream)
     }
 }
 
 impl ToToken for CallExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
         let mut callee = String::new();
         if let box Expression::Identifier(i) = &self.callee {
@@ -162,39 +182,42 @@ impl ToToken for CallExp {
         }
 
         let mut args = vec![];
-        self.arguments.iter().for_each(|arg|
-            args.extend_from_slice(arg.to_token().as_slice())
-        );
+        for arg in self.arguments.iter() {
+            args.extend_from_slice(arg.to_token()?.as_slice());
+        }
 
         let call = FunctionToken(Call { args, callee });
 
         token_stream.push(call);
-        token_stream
+        Ok(token_stream)
     }
 }
 
 impl ToToken for ObjectExp {
-    fn to_token(&self) -> Vec<Token> {
+    fn to_token(&self) -> CompileResult<Vec<Token>> {
         let mut token_stream = vec![];
-        self.properties.iter().for_each(|prop| {
-            token_stream.extend_from_slice(prop.value.to_token().as_slice());
-            token_stream.extend_from_slice(prop.key.to_token().as_slice());
-        });
-        token_stream
+        for prop in self.properties.iter() {
+            token_stream.extend_from_slice(prop.value.to_token()?.as_slice());
+            token_stream.extend_from_slice(prop.key.to_token()?.as_slice());
+        }
+        Ok(token_stream)
     }
 }
 
-pub fn postfix(tokens: &mut Vec<Token>) -> Vec<Token> {
+/// The tokens of the expression at `loc` in postfix order, only literals and
+/// operators can be ordered
+pub fn postfix(tokens: &mut Vec<Token>, loc: &Loc) -> CompileResult<Vec<Token>> {
     let mut postfix_expression = vec![];
     let mut stack = Stack::new();
 
     while let Some(token_in) = tokens.pop() {
         match &token_in {
             LiteralToken(_) => postfix_expression.push(token_in.clone()),
-            FunctionToken(_) => unimplemented!(),
+            FunctionToken(call) => return Err(CompileError::unsupported(&format!("the call of `{}` in a constant expression", call.callee), loc)),
             OperatorToken(LeftParenthesis) => stack.op_queue.push(LeftParenthesis),
             OperatorToken(RightParenthesis) => {
-                while stack.op_queue.last().expect("missing left parenthesis") != &LeftParenthesis {
+                while let Some(operator) = stack.op_queue.last() {
+                    if operator == &LeftParenthesis { break; }
                     postfix_expression.push(Token::from(stack.op_queue.pop().unwrap()));
                 };
                 if let Some(LeftParenthesis) = stack.op_queue.last() {
@@ -210,16 +233,15 @@ pub fn postfix(tokens: &mut Vec<Token>) -> Vec<Token> {
                 };
                 stack.op_queue.push(*op_in);
             }
-            IdendifierToken(_) => unimplemented!(),
-            Undefined => unimplemented!(),
+            IdendifierToken(name) => return Err(CompileError::unsupported(&format!("the variable `{}` in a constant expression", name), loc)),
+            Undefined => return Err(CompileError::unsupported("`undefined` in a constant expression", loc)),
         };
     }
 
-    while !stack.op_queue.is_empty() {
-        let leftover = stack.op_queue.pop();
-        postfix_expression.push(Token::from(leftover.unwrap()));
+    while let Some(leftover) = stack.op_queue.pop() {
+        postfix_expression.push(Token::from(leftover));
     };
-    postfix_expression
+    Ok(postfix_expression)
 }
 
 fn is_left_parenthesis(operator: &Operator) -> bool {
@@ -230,42 +252,6 @@ fn is_left_parenthesis(operator: &Operator) -> bool {
     }
 }
 
-fn operator_on_stack_as_greater_precedence(op_from_stack: &Token, op_from_queue: &Token) -> bool {
-    let op_from_stack = match op_from_stack {
-        OperatorToken(operator) => operator,
-        _ => panic!("not an op")
-    };
-
-    let op_in = match op_from_queue {
-        OperatorToken(operator) => operator,
-        _ => panic!("not an op")
-    };
-
-    if Operator::get_precedence(&op_from_stack, &op_in) {
-        true
-    } else {
-        false
-    }
-}
-
-fn operator_on_stack_as_equal_precedence_and_is_left_associative(op_from_stack: &Token, op_from_queue: &Token) -> bool {
-    let op_from_stack = match op_from_stack {
-        OperatorToken(operator) => operator,
-        _ => panic!("not an op")
-    };
-
-    let op_in = match op_from_queue {
-        OperatorToken(operator) => operator,
-        _ => panic!("not an op")
-    };
-
-    if !Operator::get_precedence(&op_from_stack, &op_in) && op_from_stack.is_left_associative() {
-        true
-    } else {
-        false
-    }
-}
-
 fn top_operator_is_function(top_operator: &Token) -> bool {
     if let FunctionToken(_) = top_operator {
         true
@@ -294,6 +280,11 @@ mod tests {
     use crate::token::to_token::postfix;
     use crate::token::token::Token;
     use crate::token::token::BinaryOperator::*;
+    use crate::ast::expression::{Loc, Pos};
+
+    fn loc() -> Loc {
+        Loc { start: Pos { line: 1.0, column: 0.0 }, end: Pos { line: 1.0, column: 5.0 } }
+    }
 
     #[test]
     fn should_postfix_expression() {
@@ -306,7 +297,7 @@ mod tests {
         expected.push(LiteralToken(NumericLiteral(1.0)));
         expected.push(LiteralToken(NumericLiteral(1.0)));
         expected.push(OperatorToken(Operator::BinOp(Add)));
-        let token_postfix = postfix(token_in);
+        let token_postfix = postfix(token_in, &loc()).unwrap();
 
         assert_eq!(token_postfix, expected);
     }
@@ -329,7 +320,7 @@ mod tests {
         expected.push(LiteralToken(NumericLiteral(1.0)));
         expected.push(OperatorToken(Operator::BinOp(Add)));
         expected.push(OperatorToken(Operator::BinOp(Mul)));
-        let token_postfix = postfix(token_in);
+        let token_postfix = postfix(token_in, &loc()).unwrap();
 
         assert_eq!(token_postfix, expected);
     }
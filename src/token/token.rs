@@ -1,6 +1,11 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
 use crate::token::token::Operator::*;
 use crate::token::token::BinaryOperator::*;
+use crate::token::token::Literal::*;
+use crate::interpret::interpreter::Closure;
+use crate::ast::expression::Loc;
+use crate::error::{CompileError, CompileResult};
 
 pub struct Node {
     tokens: Vec<Token>
@@ -15,13 +20,55 @@ pub enum Token {
     Undefined,
 }
 
+/// A javascript object, shared by every value referencing it
+pub type Object = Rc<RefCell<Properties>>;
+
+/// Properties of an object, `for..in` visits them in the order they were created
+#[derive(Default)]
+pub struct Properties {
+    entries: Vec<(String, Literal)>,
+    /// The function `new` called to create the object, see `instanceof`
+    pub constructor: Option<FunctionValue>,
+}
+
+impl Properties {
+    pub fn get(&self, key: &str) -> Option<&Literal> {
+        self.entries.iter().find(|(name, _)| name == key).map(|(_, value)| value)
+    }
+
+    pub fn insert(&mut self, key: String, value: Literal) {
+        match self.entries.iter_mut().find(|(name, _)| *name == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn entries(&self) -> &[(String, Literal)] {
+        &self.entries
+    }
+}
+
+/// A javascript array, shared by every value referencing it
+pub type Array = Rc<RefCell<Vec<Literal>>>;
+
+/// A javascript function value, see the interpreter
+pub type FunctionValue = Rc<Closure>;
+
 #[derive(Clone)]
 pub enum Literal {
     StringLiteral(String),
     NumericLiteral(f64),
     BooleanLiteral(bool),
     NullLiteral,
+    Undefined,
     Infinity,
+    ObjectLiteral(Object),
+    ArrayLiteral(Array),
+    FunctionLiteral(FunctionValue),
 }
 
 #[derive (PartialEq, Clone, Debug, Copy)]
@@ -46,14 +93,29 @@ pub enum BinaryOperator {
     LessThanOrEq,
     GreaterThan,
     GreaterThanOrEq,
-    PartialEq,
+    /// `==` and `!=`, converting the operands
+    LooseEq,
+    LooseNotEq,
+    /// `===` and `!==`
     StrictEq,
+    StrictNotEq,
+    Exp,
+    /// `&`, `|`, `^`, `<<`, `>>` and `>>>` on the operands converted to 32 bits integers
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    UnsignedShiftRight,
+    In,
+    InstanceOf,
 }
 
 #[derive( Clone, PartialEq, Debug, Copy)]
 pub enum LogicalOperator {
     Or,
     And,
+    Coalesce,
 }
 
 #[derive(Clone, PartialEq, Debug, Copy)]
@@ -89,18 +151,74 @@ pub struct Call {
 }
 
 impl Operator {
-    pub fn solve(&self, a: &Literal, b: &Literal) -> Literal {
-        match &self {
+    /// The result of `a op b`, the `TypeError` it throws is a runtime error at
+    /// `loc`
+    pub fn solve(&self, a: &Literal, b: &Literal, loc: &Loc) -> CompileResult<Literal> {
+        let result = match &self {
             BinOp(op) => {
                 match op {
                     Add => a.clone() + b.clone(),
                     Sub => a.clone() - b.clone(),
                     Mul => a.clone() * b.clone(),
                     Div => a.clone() / b.clone(),
-                    _ => unimplemented!()
+                    Mod => a.clone() % b.clone(),
+                    Exp => NumericLiteral(exponent(a.to_number(), b.to_number())),
+                    LessThan => BooleanLiteral(a < b),
+                    LessThanOrEq => BooleanLiteral(a <= b),
+                    GreaterThan => BooleanLiteral(a > b),
+                    GreaterThanOrEq => BooleanLiteral(a >= b),
+                    LooseEq => BooleanLiteral(a.loose_eq(b)),
+                    LooseNotEq => BooleanLiteral(!a.loose_eq(b)),
+                    StrictEq => BooleanLiteral(a == b),
+                    StrictNotEq => BooleanLiteral(a != b),
+                    BitAnd => NumericLiteral((a.to_int32() & b.to_int32()) as f64),
+                    BitOr => NumericLiteral((a.to_int32() | b.to_int32()) as f64),
+                    BitXor => NumericLiteral((a.to_int32() ^ b.to_int32()) as f64),
+                    // only the 5 lowest bits of the count are used
+                    ShiftLeft => NumericLiteral(a.to_int32().wrapping_shl(b.to_uint32() & 31) as f64),
+                    ShiftRight => NumericLiteral((a.to_int32() >> (b.to_uint32() & 31)) as f64),
+                    UnsignedShiftRight => NumericLiteral((a.to_uint32() >> (b.to_uint32() & 31)) as f64),
+                    In => BooleanLiteral(has_property(b, &a.clone().to_string()).map_err(|message| CompileError::runtime(&message, loc))?),
+                    InstanceOf => BooleanLiteral(is_instance(a, b).map_err(|message| CompileError::runtime(&message, loc))?),
                 }
             }
-            _=> unimplemented!(),
+            // both operands are already evaluated here, see `LogicalOperator::short_circuits`
+            LogOp(op) => if op.short_circuits(a) { a.clone() } else { b.clone() },
+            operator => return Err(CompileError::unsupported(&format!("`{}` as a binary operator", operator.as_str()), loc)),
+        };
+        Ok(result)
+    }
+}
+
+/// `a ** b`, unlike `powf` a base of 1 or -1 to an infinite or NaN power is NaN
+fn exponent(a: f64, b: f64) -> f64 {
+    if b.is_nan() || (a.abs() == 1.0 && b.is_infinite()) {
+        f64::NAN
+    } else {
+        a.powf(b)
+    }
+}
+
+/// `key in object`, only objects and arrays have properties to search
+fn has_property(object: &Literal, key: &str) -> Result<bool, String> {
+    match object {
+        ObjectLiteral(object) => Ok(object.borrow().get(key).is_some()),
+        ArrayLiteral(array) => {
+            let is_index = key.parse::<usize>().ok().filter(|i| i.to_string() == key && *i < array.borrow().len());
+            Ok(key == "length" || is_index.is_some())
+        }
+        FunctionLiteral(_) => Ok(false),
+        primitive => Err(format!("TypeError: Cannot use 'in' operator to search for '{}' in {}", key, primitive)),
+    }
+}
+
+/// `object instanceof constructor`, true for the objects `new constructor()` created
+fn is_instance(object: &Literal, constructor: &Literal) -> Result<bool, String> {
+    match (object, constructor) {
+        (ObjectLiteral(object), FunctionLiteral(constructor)) => {
+            Ok(object.borrow().constructor.as_ref().is_some_and(|created_by| Rc::ptr_eq(created_by, constructor)))
         }
+        (_, FunctionLiteral(_)) => Ok(false),
+        _ => Err("TypeError: Right-hand side of 'instanceof' is not callable".to_string()),
     }
 }
\ No newline at end of file
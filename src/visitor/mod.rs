@@ -1,48 +1,74 @@
 use crate::ast::statement::*;
 use crate::ast::expression::*;
 use crate::ast::statement::Statement::*;
+use crate::error::CompileResult;
 
 pub trait Visitor {
     // statement
-    fn visit_statement(&mut self, s: &Statement) {
+    fn visit_statement(&mut self, s: &Statement) -> CompileResult<()> {
         match s {
             BlockStatement(b) => self.visit_block_statement(b),
             ExpressionStatement(e) => self.visit_expression_statement(e),
             WhileStatement(v) => self.visit_while_statement(v),
+            VariableDeclaration(v) => self.visit_variable_declaration(v),
+            VariableDeclarator(v) => self.visit_variable_declarator(v),
+            FunctionDeclaration(f) => self.visit_function_declaration(f),
             IfStatement(i) => self.visit_if_statement(i),
             SwitchStatement(s) => self.visit_switch_statement(s),
             ForStatement(f) => self.visit_for_statement(f),
+            DoWhileStatement(d) => self.visit_do_while_statement(d),
+            ForInStatement(f) => self.visit_for_in_statement(f),
+            ForOfStatement(f) => self.visit_for_of_statement(f),
             BreakStatement(b) => self.visit_break_statement(b),
             ContinueStatement(c) => self.visit_continue_statement(c),
+            LabeledStatement(l) => self.visit_labeled_statement(l),
+            ThrowStatement(t) => self.visit_throw_statement(t),
+            TryStatement(t) => self.visit_try_statement(t),
             ReturnStatement(r) => self.visit_return_statement(r),
             SwitchCase(case) => self.visit_case(case),
-            _ => (),
-        };
+            _ => Ok(()),
+        }
     }
-    fn visit_block_statement(&mut self, s: &BlockStmt);
-    fn visit_variable_declarator(&mut self, v: &Variable);
-    fn visit_while_statement(&mut self, w: &WhileStmt);
-    fn visit_variable_declaration(&mut self, v: &VariableDec);
-    fn visit_if_statement(&mut self, i: &IfStmt);
-    fn visit_switch_statement(&mut self, s: &SwitchStmt);
-    fn visit_case(&mut self, case: &CaseStmt);
-    fn visit_for_statement(&mut self, f: &ForStmt);
-    fn visit_break_statement(&mut self, f: &BreakStmt);
-    fn visit_return_statement(&mut self, r: &ReturnStmt);
-    fn visit_continue_statement(&mut self, c: &ContinueStmt);
-    fn visit_function_declaration(&mut self, f: &FunctionDec);
+    fn visit_block_statement(&mut self, s: &BlockStmt) -> CompileResult<()>;
+    fn visit_variable_declarator(&mut self, v: &Variable) -> CompileResult<()>;
+    fn visit_while_statement(&mut self, w: &WhileStmt) -> CompileResult<()>;
+    fn visit_variable_declaration(&mut self, v: &VariableDec) -> CompileResult<()>;
+    fn visit_if_statement(&mut self, i: &IfStmt) -> CompileResult<()>;
+    fn visit_switch_statement(&mut self, s: &SwitchStmt) -> CompileResult<()>;
+    fn visit_case(&mut self, case: &CaseStmt) -> CompileResult<()>;
+    fn visit_for_statement(&mut self, f: &ForStmt) -> CompileResult<()>;
+    fn visit_do_while_statement(&mut self, d: &DoWhileStmt) -> CompileResult<()>;
+    fn visit_for_in_statement(&mut self, f: &ForEachStmt) -> CompileResult<()>;
+    fn visit_for_of_statement(&mut self, f: &ForEachStmt) -> CompileResult<()>;
+    fn visit_for_init(&mut self, init: &ForInit) -> CompileResult<()> {
+        match init {
+            ForInit::Declaration(declaration) => self.visit_variable_declaration(declaration),
+            ForInit::Expression(expression) => self.visit_expression(expression),
+        }
+    }
+    fn visit_break_statement(&mut self, f: &BreakStmt) -> CompileResult<()>;
+    fn visit_return_statement(&mut self, r: &ReturnStmt) -> CompileResult<()>;
+    fn visit_continue_statement(&mut self, c: &ContinueStmt) -> CompileResult<()>;
+    fn visit_labeled_statement(&mut self, l: &LabeledStmt) -> CompileResult<()>;
+    fn visit_throw_statement(&mut self, t: &ThrowStmt) -> CompileResult<()>;
+    fn visit_try_statement(&mut self, t: &TryStmt) -> CompileResult<()>;
+    fn visit_function_declaration(&mut self, f: &FunctionDec) -> CompileResult<()>;
 
     //expression
-    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>);
-    fn visit_expression(&mut self, exp: &Expression);
-    fn visit_expression_statement(&mut self, s: &ExpressionStmt);
-    fn visit_binary_expression(&mut self, b: &BinaryExp);
-    fn visit_assign(&mut self, a: &AssignmentExp);
-    fn visit_unary_expression(&mut self, u: &UnaryExp);
-    fn visit_update_expression(&mut self, u: &UpdateExp);
-    fn visit_member_expression(&mut self, m: &MemberExp);
-    fn visit_logical_expression(&mut self, l: &LogicalExp);
-    fn visit_call_expression(&mut self, e: &CallExp);
-    fn visit_object_expression(&mut self, o: &ObjectExp, id: String);
-    fn visit_property_expression(&mut self, id: &str, p: &Property);
+    fn visit_option_expression(&mut self, exp: &Option<Box<Expression>>) -> CompileResult<()>;
+    fn visit_expression(&mut self, exp: &Expression) -> CompileResult<()>;
+    fn visit_expression_statement(&mut self, s: &ExpressionStmt) -> CompileResult<()>;
+    fn visit_binary_expression(&mut self, b: &BinaryExp) -> CompileResult<()>;
+    fn visit_assign(&mut self, a: &AssignmentExp) -> CompileResult<()>;
+    fn visit_unary_expression(&mut self, u: &UnaryExp) -> CompileResult<()>;
+    fn visit_update_expression(&mut self, u: &UpdateExp) -> CompileResult<()>;
+    fn visit_member_expression(&mut self, m: &MemberExp) -> CompileResult<()>;
+    fn visit_logical_expression(&mut self, l: &LogicalExp) -> CompileResult<()>;
+    fn visit_call_expression(&mut self, e: &CallExp) -> CompileResult<()>;
+    fn visit_new_expression(&mut self, n: &NewExp) -> CompileResult<()>;
+    fn visit_function_expression(&mut self, f: &FunctionExp) -> CompileResult<()>;
+    fn visit_arrow_function_expression(&mut self, f: &ArrowFunctionExp) -> CompileResult<()>;
+    fn visit_array_expression(&mut self, a: &ArrayExp) -> CompileResult<()>;
+    fn visit_object_expression(&mut self, o: &ObjectExp, id: String) -> CompileResult<()>;
+    fn visit_property_expression(&mut self, id: &str, p: &Property) -> CompileResult<()>;
 }
@@ -6,5 +6,6 @@ pub const EQ: &'static str = "=";
 pub const NEW_LINE: &'static str = "\n";
 pub const SEMI_COL: &'static str = ";";
 pub const COL: &'static str = ":";
+pub const QUESTION_MARK: &'static str = "?";
 pub const COMA: &'static str = ",";
 pub const TAB: &'static str = "\t";
\ No newline at end of file
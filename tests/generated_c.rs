@@ -0,0 +1,146 @@
+//! Builds the C the compiler generates against `c_datalib` with gcc and checks
+//! that the binary prints what the interpreter prints
+
+use std::env;
+use std::fs;
+use std::process;
+use std::process::Command;
+
+use rjsc::runtime::*;
+use rjsc::Options;
+
+/// The examples both backends run
+const EXAMPLES: &[&str] = &[
+    "03-while", "04-if-while", "05-fors", "06-while-break", "11-func", "13-switch", "15-new", "16-funcs",
+    "17-double-parenth-func", "18-func-in-func",
+];
+
+/// Programs covering what the examples don't
+const PROGRAMS: &[(&str, &str)] = &[
+    ("closures", "function f(a) {\n  function g(b) { return a + b; }\n  return g;\n}\nprint(f(1)(2));"),
+    ("this", "function Box(v) {\n  var set = () => { this.v = v; };\n  set();\n}\n\
+              var b = new Box(4);\nb.get = function () { return this.v; };\nvar twice = x => x + x;\n\
+              print(b.get(), twice(b.v), twice);"),
+    ("arrays", "var a = [1, 'b', , 3];\na[5] = a.push(4);\nprint(a, a.length, a.pop(), a[\"1\"], []);"),
+    ("loops", "var s = 0;\nfor (var i = 0; i < 4; i = i + 1) { if (i == 1) { continue; } s = s + i; }\n\
+               do { s = s + 10; } while (false);\nvar o = { a: 1, b: 2 };\nfor (var k in o) { s = s + o[k]; }\n\
+               for (var x of [7, 8, 9]) { if (x == 9) { break; } s = s + x; }\nprint(s);"),
+    ("labels", "var n = 0;\nouter: for (var i = 0; i < 4; i = i + 1) {\n\
+                for (var j = 0; j < 4; j = j + 1) { if (j == 2) { continue outer; } if (i == 3) { break outer; } n = n + 1; }\n}\n\
+                block: { n = n + 10; break block; n = 0; }\nprint(n);"),
+    ("exceptions", "var o = null;\nfunction f(x) {\n  try { if (x) { return 1; } throw 2; }\n  \
+                    catch (e) { return e; }\n  finally { print(0); }\n}\nprint(f(true));\nprint(f(false));\n\
+                    try { o.x; } catch (e) { print(e.name); }"),
+    ("block scopes", "let x = 1;\nfunction f() {\n  var v = x;\n  if (v) { let x = 2; v = v + x; }\n  return v;\n}\n\
+                      { const x = 3; print(x); }\ntry { throw 4; } catch (x) { print(x); }\nprint(x, f());"),
+    ("equality", "var o = {};\nprint(1 == \"1\", \"1\" === 1, null == undefined, null == 0, true == \"1\", \
+                  \" 0x10 \" == 16, \"1a\" != 1, o == o, o === {}, [1, null] == \"1,\");"),
+    ("operators", "function P() {}\nvar p = new P();\nvar o = {a: 1};\nprint(-7 % 3, 2 ** 10, ~5, 1 << 31, -16 >>> 28, \
+                   null >= 0, \"a\" in o, p instanceof P, o instanceof P, typeof o, typeof missing, -\"3\");"),
+    ("logical operators", "var missing;\nprint(0 || \"a\", 1 && null, null ?? 2, 0 ?? 2, 0 && missing.x);"),
+    ("logical operand names", "var logical_operand = 3;\nvar rjsc_logical_operand = 4;\n\
+                               print(0 || logical_operand, 0 || rjsc_logical_operand);"),
+    ("member names", "var object_literal = 5;\nvar member_value = 10;\nvar rjsc_member_value = 20;\n\
+                      var o = { a: object_literal, x: 1 };\no.x += member_value;\no.x += rjsc_member_value;\n\
+                      print({ a: object_literal }.a, o.a, o.x);"),
+    ("c names", "var argc = 1;\nvar free = 2;\nfunction f_g() { return free; }\n\
+                 function f(env, argv) { function g() { return argc + env + argv; } return g; }\n\
+                 function main(int) { return int; }\nfunction negate(index) { return -index; }\n\
+                 var array = [f(3, 4)(), f_g(), main(5), negate(6)];\nprint(array, typeof negate);"),
+    ("statement names", "var returned = 7;\nvar try_0 = 1;\nvar switch_1 = 2;\n\
+                         function f() { try { return 1; } finally { print(returned); } }\nprint(f());\n\
+                         for (var x of [try_0, switch_1]) { print(x); }"),
+    ("function identity", "function f() {}\nfunction P() {}\nvar g = function h(n) { if (n) { return h; } return h === g; };\n\
+                           var Q = function R() { if (!(this instanceof R)) { return new R(); } };\n\
+                           print(f === f, f == f, new P() instanceof P, g(0), g(1) === g, Q() instanceof Q, [f][0] === f);"),
+    ("block functions", "var x = true;\nif (x) { function h() { print(3); } h(); }\n{ g(); function g() { print(h === h); } }\n\
+                         while (x) { function w() { return 5; } x = false; print(w()); }\n\
+                         function f() { if (x) { function i() { return 6; } } return i(); }\nx = true;\nprint(f());"),
+    ("duplicate parameters", "function f(a, a) { return a; }\nvar g = function (b, c, b) { return [b, c]; };\n\
+                              function h(x, x) { return () => x; }\nprint(f(1, 2), f(1), g(1, 2, 3), h(4, 5)());"),
+    ("loop variables", "var fs = [];\nfor (let i = 0; i < 3; i++) { fs.push(() => i); }\nvar n = 0;\n\
+                        while (n < 2) { let m = n; fs.push(() => m); n++; }\nfor (const k in { a: 1, b: 2 }) { fs.push(() => k); }\n\
+                        function g() { var r = []; for (let j = 0; j < 2; j++) { let q = j * 2; r.push(() => j + q); } return r; }\n\
+                        print(fs[0](), fs[1](), fs[2](), fs[3](), fs[4](), fs[5](), fs[6](), g()[1]());"),
+    ("huge arrays", "var a = [1];\ntry { a[2000000000] = 1; } catch (e) { print(e.name, e.message); }\n\
+                     a[4294967295] = 2;\na[1.5] = 3;\na.length = 3;\nprint(a);\ntry { a.length = -1; } catch (e) { print(e.message); }\n\
+                     a.length = 0;\nprint(a.length);"),
+    ("constructor names", "function P(x) { this.x = x; }\nvar new_this = 3;\nprint(new P(new_this).x);"),
+    ("constructor expression names", "function P(x) { this.x = x; }\nvar new_callee = 4;\nvar o = { P: P };\n\
+                                      print(new (o.P)(new_callee).x);"),
+    ("builtins", "console.log(Infinity + 1, -Infinity, NaN, typeof NaN, NaN == NaN, 1 / 0 === Infinity, undefined);"),
+    ("print values", "var log = console.log;\nlog(3);\nvar print = console.log;\nfunction f(p) { p(\"in f\"); }\n\
+                      f(print);\nprint(typeof print, typeof console, log, console.log(1));"),
+    ("print declarations", "function print(x) { console.log(\"mine\", x); }\nprint(1);\n\
+                            function f() { var console = { log: function (x) { print(x + 1); } }; console.log(2); }\nf();"),
+    ("updates", "var i = 0;\nvar j = i++;\nvar a = [];\na[i++] = 5;\nvar s = \"1\";\nvar k = s++;\nvar u;\nu--;\n\
+                 var o = { x: \"2\" };\nvar m = o.x--;\nprint(i, j, a, ++i, --i, k, s, u, m, o.x, i--, i);"),
+    ("objects", "function P(nom) { this.nom = nom; }\nvar o = { a: 1, \"b-c\": [\"s\", { d: null }], p: new P(\"x\") };\n\
+                 console.log(o, {}, new P());"),
+    ("cycles", "var o = {};\no.self = o;\nvar a = [1];\na.push(a);\na.push({ x: a });\nvar q = { a: {} };\nq.a.b = q.a;\nq.c = q;\n\
+                console.log(o, a, q, \"\" + a);"),
+    ("inspect", "function P() { this.x = 1; }\nvar list = [];\nfor (var i = 0; i < 120; i++) { list.push(i * i); }\n\
+                 var o = { s: \"it's\", t: 'say \"hi\"', e: \"tab\\t\\\\\", z: -0, deep: { a: { b: { c: new P() } } }, '$k': [\"a\", \"b\"] };\n\
+                 console.log(o, list, [\"x\", \"y\", \"z\", 1, 2, 3, 4, 5], -0);"),
+    ("switch", "function f(x) {\n  var s = \"\";\n  switch (x) {\n    case 1: s += \"a\";\n    case \"1\": s += \"b\"; break;\n\
+                \x20   default: s += \"d\";\n    case 3: s += \"c\";\n  }\n  return s;\n}\nprint(f(1), f(\"1\"), f(2), f(3));\n\
+                for (var i = 0; i < 3; i++) { switch (i) { case 1: continue; } print(i); }"),
+    ("property case", "var o = {};\no.a = 1;\no.A = 2;\nprint(o.a, o.A);"),
+    // the operators are called directly, gcc expands the nested generic macros exponentially
+    ("long sums", "var a = 1;\nvar s = \"s\";\nprint(a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + \
+                   a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a + a, \
+                   s + a + a + a + a + a + a + a + a + a + a < s);"),
+    ("strings", "var s = \"a\" + 1;\nprint(s, s + s < \"b\", \"x\" + null);"),
+    ("unicode strings", "var s = \"é😀a\";\nvar n = 0;\nfor (var c of s) { n = n + 1; }\nvar keys = [];\n\
+                         for (var k in s) { keys.push(k); }\nprint(s.length, s[0], s[1], s[3], s[4], n, keys);"),
+    ("string order", "print(\"\u{1F600}\" < \"\u{E000}\", \"a\u{1F600}\" > \"a\u{1F601}\", \"\u{E9}\" > \"z\", \"ab\" < \"abc\");"),
+];
+
+/// What the program compiled to C prints, on stdout and stderr
+fn run_c(name: &str, js_source: &str) -> process::Output {
+    let dir = env::temp_dir().join(format!("rjsc-{}-{}", name.replace(' ', "-"), process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let runtime = [
+        ("databox.h", DATABOX_H), ("databox.c", DATABOX_C), ("print.h", PRINT_H), ("print.c", PRINT_C),
+        ("dict.h", DICT_H), ("dict.c", DICT_C), ("keyval.h", KEYVAL_H), ("keyval.c", KEYVAL_C),
+    ];
+    for (file, content) in runtime.iter() {
+        fs::write(dir.join(file), content).unwrap();
+    }
+    let program = rjsc::parse(js_source).unwrap();
+    let generated = rjsc::compile_to_c(&program, &Options::default()).unwrap();
+    fs::write(dir.join("out.c"), generated.source).unwrap();
+
+    let gcc = Command::new("gcc")
+        .current_dir(&dir)
+        .args(["out.c", "databox.c", "print.c", "dict.c", "keyval.c", "-o", "out", "-lm"])
+        .output()
+        .expect("gcc is needed to build the generated C");
+    assert!(gcc.status.success(), "{}: gcc failed\n{}", name, String::from_utf8_lossy(&gcc.stderr));
+    let out = Command::new(dir.join("out")).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    out
+}
+
+#[test]
+fn should_print_what_the_interpreter_prints() {
+    let examples = EXAMPLES.iter().map(|name| (*name, fs::read_to_string(format!("exemples/{}.js", name)).unwrap()));
+    let programs = PROGRAMS.iter().map(|(name, source)| (*name, source.to_string()));
+    for (name, source) in examples.chain(programs) {
+        let program = rjsc::parse(&source).unwrap();
+        let expected = rjsc::interpret(&program, &Options::default()).unwrap();
+        assert_eq!(String::from_utf8(run_c(name, &source).stdout).unwrap(), expected, "{}", name);
+    }
+}
+
+#[test]
+fn should_report_uncaught_values_like_the_interpreter() {
+    let programs = ["throw { code: 1 };", "throw [1, 'a'];", "throw 'oops';", "throw -0;"];
+    for (i, source) in programs.iter().enumerate() {
+        let program = rjsc::parse(source).unwrap();
+        let expected = rjsc::interpret(&program, &Options::default()).unwrap_err().message();
+        let out = run_c(&format!("uncaught-{}", i), source);
+        assert!(!out.status.success(), "{}", source);
+        assert_eq!(String::from_utf8(out.stderr).unwrap(), format!("{}\n", expected), "{}", source);
+    }
+}